@@ -0,0 +1,201 @@
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier};
+
+/// How many colours the terminal can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// No colour: only bold, reverse and other modifiers
+    Mono,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// The `--color` flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Parse `auto`, `always` or `never`.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "always" => Some(Self::Always),
+            "never" => Some(Self::Never),
+            _ => None,
+        }
+    }
+}
+
+/// Colour depth from the environment. `FORCE_COLOR` (0–3) wins over `NO_COLOR`, which wins
+/// over `COLORTERM` and `TERM`.
+#[must_use]
+pub fn detect_from(
+    colorterm: Option<&str>,
+    term: Option<&str>,
+    no_color: Option<&str>,
+    force_color: Option<&str>,
+) -> ColorDepth {
+    if let Some(force) = force_color {
+        return match force.trim() {
+            "0" | "false" => ColorDepth::Mono,
+            "2" => ColorDepth::Ansi256,
+            "3" => ColorDepth::TrueColor,
+            _ => ColorDepth::Ansi16,
+        };
+    }
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        return ColorDepth::Mono;
+    }
+    if colorterm
+        .is_some_and(|c| c.eq_ignore_ascii_case("truecolor") || c.eq_ignore_ascii_case("24bit"))
+    {
+        return ColorDepth::TrueColor;
+    }
+    let term = term.unwrap_or_default().to_ascii_lowercase();
+    // Windows consoles leave TERM unset but handle colour escapes
+    if (term.is_empty() && cfg!(unix)) || term == "dumb" || term.starts_with("vt") {
+        ColorDepth::Mono
+    } else if term.contains("direct") {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        // Linux console, plain xterm/screen/tmux and anything unknown
+        ColorDepth::Ansi16
+    }
+}
+
+/// Colour depth for this process's environment and the `--color` choice.
+#[must_use]
+pub fn resolve(choice: ColorChoice) -> ColorDepth {
+    let env = |k| std::env::var(k).ok();
+    match choice {
+        ColorChoice::Never => ColorDepth::Mono,
+        ColorChoice::Auto => detect_from(
+            env("COLORTERM").as_deref(),
+            env("TERM").as_deref(),
+            env("NO_COLOR").as_deref(),
+            env("FORCE_COLOR").as_deref(),
+        ),
+        // Ignore NO_COLOR, but still respect what the terminal can show
+        ColorChoice::Always => detect_from(
+            env("COLORTERM").as_deref(),
+            env("TERM").as_deref(),
+            None,
+            env("FORCE_COLOR").as_deref(),
+        )
+        .max(ColorDepth::Ansi16),
+    }
+}
+
+/// xterm's default RGB values for the 16 basic colours.
+const ANSI16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).unsigned_abs();
+    d(a.0, b.0).pow(2) + d(a.1, b.1).pow(2) + d(a.2, b.2).pow(2)
+}
+
+/// RGB value of a 256-colour palette index.
+#[must_use]
+pub fn index_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI16[usize::from(index)].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE[usize::from(i / 36)],
+                CUBE[usize::from(i / 6 % 6)],
+                CUBE[usize::from(i % 6)],
+            )
+        }
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            (v, v, v)
+        }
+    }
+}
+
+/// Nearest entry of the 6×6×6 colour cube or the grey ramp.
+#[must_use]
+pub fn rgb_to_256(rgb: (u8, u8, u8)) -> u8 {
+    let level = |v: u8| {
+        CUBE.iter()
+            .enumerate()
+            .min_by_key(|(_, c)| (i32::from(**c) - i32::from(v)).unsigned_abs())
+            .map_or(0, |(i, _)| i as u8)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = 16 + 36 * r + 6 * g + b;
+    let avg = (u16::from(rgb.0) + u16::from(rgb.1) + u16::from(rgb.2)) / 3;
+    let grey = 232 + (avg.saturating_sub(3) / 10).min(23) as u8;
+    if distance(index_to_rgb(grey), rgb) < distance(index_to_rgb(cube), rgb) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// Nearest of the 16 basic colours.
+#[must_use]
+pub fn rgb_to_16(rgb: (u8, u8, u8)) -> Color {
+    ANSI16
+        .iter()
+        .min_by_key(|(_, c)| distance(*c, rgb))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+/// Map a colour to one the terminal can show; `Mono` drops it.
+#[must_use]
+pub fn downsample(color: Color, depth: ColorDepth) -> Color {
+    match (depth, color) {
+        (ColorDepth::TrueColor, c) | (_, c @ Color::Reset) => c,
+        (ColorDepth::Mono, _) => Color::Reset,
+        (ColorDepth::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(rgb_to_256((r, g, b))),
+        (ColorDepth::Ansi256, c) => c,
+        (ColorDepth::Ansi16, Color::Rgb(r, g, b)) => rgb_to_16((r, g, b)),
+        (ColorDepth::Ansi16, Color::Indexed(i)) => rgb_to_16(index_to_rgb(i)),
+        (ColorDepth::Ansi16, c) => c,
+    }
+}
+
+/// Downsample every cell of a drawn frame. Without colour, cells that had a background
+/// (selections, status bar, search hits) are shown in reverse video instead.
+pub fn downsample_buffer(buf: &mut Buffer, depth: ColorDepth) {
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+    for cell in &mut buf.content {
+        if depth == ColorDepth::Mono && cell.bg != Color::Reset {
+            cell.modifier.insert(Modifier::REVERSED);
+        }
+        cell.fg = downsample(cell.fg, depth);
+        cell.bg = downsample(cell.bg, depth);
+    }
+}
@@ -3,10 +3,105 @@
 // TODO: Fix clippy warnings for better code quality
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use crate::AppConfig;
+use crate::asciinema::AsciinemaConfig;
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StatusConfig {
+    pub text: Option<String>,
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct MotdConfig {
+    #[serde(default)]
+    pub vars: HashMap<String, String>, // template name -> shell command printing its value
+    #[serde(default)]
+    pub timeout: Option<String>, // per-command limit, default 2s
+    #[serde(default)]
+    pub refresh: Option<String>, // re-evaluate the template this often
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub status: Option<StatusConfig>,
+    #[serde(default)]
+    pub motd: Option<MotdConfig>,
+    #[serde(default)]
+    pub allow_project_override: bool,
+    #[serde(default)]
+    pub motd_wrap: Option<bool>,
+    #[serde(default)]
+    pub motd_color: Option<String>,
+    #[serde(default)]
+    pub markdown_enabled: Option<bool>,
+    #[serde(default)]
+    pub output_dim: Option<bool>,
+    #[serde(default)]
+    pub theme: Option<String>, // "dark" or "light" or custom name
+    #[serde(default)]
+    pub theme_file: Option<String>, // path to external YAML .dx-theme
+    #[serde(default)]
+    pub theme_overrides: Option<HashMap<String, String>>, // token->color overrides
+    #[serde(default)]
+    pub theme_dir: Option<String>, // directory with *.dx-theme files
+    #[serde(default)]
+    pub theme_family: Option<String>, // with theme "auto": use <family>-dark/-light.dx-theme
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
+    #[serde(default)]
+    pub update: Option<UpdateConfig>,
+    #[serde(default)]
+    pub asciinema: Option<AsciinemaConfig>,
+    #[serde(default)]
+    pub show_fps: Option<bool>,
+    #[serde(default)]
+    pub scrollback_lines: Option<usize>, // emulator rows kept above the live screen
+    #[serde(default)]
+    pub sigint_grace: Option<String>, // wait after SIGINT before SIGTERM on a confirmed kill
+    #[serde(default)]
+    pub sigterm_grace: Option<String>, // wait after SIGTERM before SIGKILL
+    #[serde(default)]
+    pub notify_after: Option<String>, // notify when a command ran at least this long
+    #[serde(default)]
+    pub notify: Option<String>, // "auto", "osc9", "osc777" or "bell"
+    #[serde(default)]
+    pub notify_command: Option<String>, // notifier command used instead of an escape sequence
+    #[serde(default)]
+    pub history_runs: Option<usize>, // finished runs kept per item under ~/.dx/history
+    #[serde(default)]
+    pub images: Option<String>, // "auto", "kitty", "iterm", "sixel", "blocks" or "off"
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TelemetryConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct UpdateConfig {
+    #[serde(default)]
+    pub on_start: bool,
+    #[serde(default = "default_build_cmd")]
+    pub build_cmd: String,
+    pub relaunch_path: Option<String>,
+    #[serde(default = "default_preserve_args")]
+    pub preserve_args: bool,
+}
+
+pub fn default_build_cmd() -> String {
+    "cargo build --release".to_string()
+}
+pub fn default_preserve_args() -> bool {
+    true
+}
 
 pub fn load_app_config_file(path: &Path) -> Option<AppConfig> {
     fs::read_to_string(path).ok().and_then(|s| {
@@ -24,20 +119,6 @@ pub fn load_app_config_file(path: &Path) -> Option<AppConfig> {
     })
 }
 
-#[allow(dead_code)]
-pub fn load_global_config() -> Option<AppConfig> {
-    std::env::var("HOME")
-        .ok()
-        .map(|h| PathBuf::from(h).join(".dx").join("config.toml"))
-        .and_then(|p| load_app_config_file(&p))
-}
-
-#[allow(dead_code)]
-pub fn load_local_config() -> Option<AppConfig> {
-    let project_root = crate::exec::find_project_root();
-    load_app_config_file(&project_root.join("config.toml"))
-}
-
 pub fn save_app_config(path: &Path, cfg: &AppConfig) -> Result<()> {
     if let Some(parent) = path.parent() {
         let _ = std::fs::create_dir_all(parent);
@@ -110,7 +191,352 @@ pub fn validate_app_config(cfg: &AppConfig) -> (Vec<String>, Vec<String>) {
     (errors, warnings)
 }
 
-use crate::{AsciinemaConfig, TelemetryConfig, UpdateConfig, default_stream_mode};
+/// Config file names looked up in `~/.dx` and the project root, most preferred first.
+pub const CONFIG_FILE_NAMES: [&str; 4] =
+    ["config.yaml", "config.yml", "config.toml", "config.json"];
+
+/// The first config file present in `dir`.
+pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|n| dir.join(n))
+        .find(|p| p.exists())
+}
+
+/// The `config:` section of a menu file. A bad setting there is kept as an error to report,
+/// so it does not stop the menu from loading.
+#[derive(Debug, Clone)]
+pub enum ConfigSection {
+    Valid(Box<AppConfig>),
+    Invalid(String),
+}
+
+impl<'de> Deserialize<'de> for ConfigSection {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Ok(match serde_json::from_value::<AppConfig>(value) {
+            Ok(cfg) => Self::Valid(Box::new(cfg)),
+            Err(e) => Self::Invalid(e.to_string()),
+        })
+    }
+}
+
+/// Where a setting comes from; later sources override earlier ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigSource {
+    Default,
+    Global,
+    Project,
+    DxFile,
+    Env,
+    Cli,
+}
+
+impl ConfigSource {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::Global => "home",
+            Self::Project => "project",
+            Self::DxFile => "dx file",
+            Self::Env => "env",
+            Self::Cli => "cli",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    pub source: ConfigSource,
+    pub path: Option<PathBuf>,
+    pub cfg: AppConfig,
+}
+
+/// Every configuration layer, lowest precedence first, and the settings they add up to.
+#[derive(Debug, Clone, Default)]
+pub struct LayeredConfig {
+    pub layers: Vec<ConfigLayer>,
+    pub merged: AppConfig,
+    /// Settings that were ignored because they did not parse (`DX_*` values, the menu
+    /// file's `config:` section)
+    pub issues: Vec<String>,
+}
+
+impl LayeredConfig {
+    /// Merge `layers` (lowest precedence first). A global config with
+    /// `allow_project_override = false` keeps the project config and the menu file from
+    /// setting the `status` and `motd` commands.
+    pub fn new(layers: Vec<ConfigLayer>) -> Self {
+        let allow_override = layers
+            .iter()
+            .find(|l| l.source == ConfigSource::Global)
+            .is_none_or(|l| l.cfg.allow_project_override);
+        let mut merged = AppConfig::default();
+        for layer in &layers {
+            let mut cfg = layer.cfg.clone();
+            if !allow_override
+                && matches!(layer.source, ConfigSource::Project | ConfigSource::DxFile)
+            {
+                cfg.status = None;
+                cfg.motd = None;
+            }
+            merge_config(&mut merged, cfg);
+        }
+        merged.allow_project_override = allow_override;
+        Self {
+            layers,
+            merged,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Read `~/.dx/config.*`, the project's `config.*`, the `config:` section of the menu
+    /// file, `DX_*` variables from `env` and `--set KEY=VALUE` flags.
+    ///
+    /// # Errors
+    /// Returns an error if a `--set` flag names an unknown key or has a bad value.
+    pub fn load(
+        home: Option<&Path>,
+        project_root: &Path,
+        dx_file: Option<&Path>,
+        env: impl IntoIterator<Item = (String, String)>,
+        sets: &[String],
+    ) -> Result<Self> {
+        let mut layers = Vec::new();
+        let mut push_file = |source, path: Option<PathBuf>| {
+            if let Some(path) = path
+                && let Some(cfg) = load_app_config_file(&path)
+            {
+                layers.push(ConfigLayer {
+                    source,
+                    path: Some(path),
+                    cfg,
+                });
+            }
+        };
+        push_file(
+            ConfigSource::Global,
+            home.and_then(|h| find_config_file(&h.join(".dx"))),
+        );
+        push_file(ConfigSource::Project, find_config_file(project_root));
+        let mut issues = Vec::new();
+        if let Some(path) = dx_file
+            && let Ok(dx) = crate::menu::load_dx_file(path)
+        {
+            match dx.config {
+                Some(ConfigSection::Valid(cfg)) => layers.push(ConfigLayer {
+                    source: ConfigSource::DxFile,
+                    path: Some(path.to_path_buf()),
+                    cfg: *cfg,
+                }),
+                Some(ConfigSection::Invalid(e)) => {
+                    issues.push(format!("{} config: {e}", path.display()));
+                }
+                None => {}
+            }
+        }
+        let (env_cfg, env_issues) = env_config(env);
+        issues.extend(env_issues);
+        if let Some(cfg) = env_cfg {
+            layers.push(ConfigLayer {
+                source: ConfigSource::Env,
+                path: None,
+                cfg,
+            });
+        }
+        if !sets.is_empty() {
+            let mut cfg = AppConfig::default();
+            for set in sets {
+                let (key, value) = set
+                    .split_once('=')
+                    .ok_or_else(|| anyhow::anyhow!("--set '{set}' is not KEY=VALUE"))?;
+                apply_setting(&mut cfg, key.trim(), value)
+                    .map_err(|e| anyhow::anyhow!("--set {set}: {e}"))?;
+            }
+            layers.push(ConfigLayer {
+                source: ConfigSource::Cli,
+                path: None,
+                cfg,
+            });
+        }
+        let mut layered = Self::new(layers);
+        layered.issues = issues;
+        Ok(layered)
+    }
+
+    pub fn layer(&self, source: ConfigSource) -> Option<&ConfigLayer> {
+        self.layers.iter().find(|l| l.source == source)
+    }
+
+    /// Replace (or add) the layer from `layer.source`, e.g. once the Config screen saved it.
+    pub fn set_layer(&mut self, layer: ConfigLayer) {
+        let mut layers = std::mem::take(&mut self.layers);
+        layers.retain(|l| l.source != layer.source);
+        let at = layers
+            .iter()
+            .position(|l| l.source > layer.source)
+            .unwrap_or(layers.len());
+        layers.insert(at, layer);
+        let issues = std::mem::take(&mut self.issues);
+        *self = Self::new(layers);
+        self.issues = issues;
+    }
+
+    /// The layer above `saved_to` that sets a key, i.e. why a value saved to `saved_to`
+    /// would not take effect.
+    pub fn overridden_by(
+        &self,
+        saved_to: ConfigSource,
+        is_set: impl Fn(&AppConfig) -> bool,
+    ) -> Option<ConfigSource> {
+        let source = self.source_of(is_set);
+        (source > saved_to).then_some(source)
+    }
+
+    /// The highest-precedence layer for which `is_set` holds, or `Default`.
+    pub fn source_of(&self, is_set: impl Fn(&AppConfig) -> bool) -> ConfigSource {
+        self.layers
+            .iter()
+            .rev()
+            .find(|l| is_set(&l.cfg))
+            .map_or(ConfigSource::Default, |l| l.source)
+    }
+}
+
+/// Overlay `over` onto `base`: every key `over` sets replaces the one in `base`. Sections
+/// (`status`, `motd`, `telemetry`, `update`, `asciinema`) are replaced whole;
+/// `theme_overrides` is merged token by token. `allow_project_override` is left to
+/// [`LayeredConfig::new`].
+pub fn merge_config(base: &mut AppConfig, over: AppConfig) {
+    macro_rules! take {
+        ($($field:ident),*) => {
+            $(if over.$field.is_some() {
+                base.$field = over.$field;
+            })*
+        };
+    }
+    take!(
+        status,
+        motd,
+        motd_wrap,
+        motd_color,
+        markdown_enabled,
+        output_dim,
+        theme,
+        theme_file,
+        theme_dir,
+        theme_family,
+        telemetry,
+        update,
+        asciinema,
+        show_fps,
+        scrollback_lines,
+        sigint_grace,
+        sigterm_grace,
+        notify_after,
+        notify,
+        notify_command,
+        history_runs,
+        images
+    );
+    if let Some(overrides) = over.theme_overrides {
+        base.theme_overrides
+            .get_or_insert_with(HashMap::new)
+            .extend(overrides);
+    }
+}
+
+/// Top-level keys that can be set from `DX_<KEY>` variables and `--set KEY=VALUE`.
+pub const SETTING_KEYS: [&str; 17] = [
+    "theme",
+    "theme_file",
+    "theme_dir",
+    "theme_family",
+    "motd_wrap",
+    "motd_color",
+    "markdown_enabled",
+    "output_dim",
+    "show_fps",
+    "scrollback_lines",
+    "sigint_grace",
+    "sigterm_grace",
+    "notify_after",
+    "notify",
+    "notify_command",
+    "history_runs",
+    "images",
+];
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(format!("'{value}' is not a boolean")),
+    }
+}
+
+/// Set one top-level key of `cfg` from its string form.
+///
+/// # Errors
+/// Returns a message if the key is unknown or the value does not parse.
+pub fn apply_setting(cfg: &mut AppConfig, key: &str, value: &str) -> Result<(), String> {
+    let text = || Some(value.to_string());
+    let duration = || match crate::menu::parse_duration(value) {
+        Some(_) => Ok(text()),
+        None => Err(format!("'{value}' is not a duration")),
+    };
+    let number = || {
+        value
+            .trim()
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| format!("'{value}' is not a number"))
+    };
+    match key {
+        "theme" => cfg.theme = text(),
+        "theme_file" => cfg.theme_file = text(),
+        "theme_dir" => cfg.theme_dir = text(),
+        "theme_family" => cfg.theme_family = text(),
+        "motd_wrap" => cfg.motd_wrap = Some(parse_bool(value)?),
+        "motd_color" => cfg.motd_color = text(),
+        "markdown_enabled" => cfg.markdown_enabled = Some(parse_bool(value)?),
+        "output_dim" => cfg.output_dim = Some(parse_bool(value)?),
+        "show_fps" => cfg.show_fps = Some(parse_bool(value)?),
+        "scrollback_lines" => cfg.scrollback_lines = number()?,
+        "sigint_grace" => cfg.sigint_grace = duration()?,
+        "sigterm_grace" => cfg.sigterm_grace = duration()?,
+        "notify_after" => cfg.notify_after = duration()?,
+        "notify" => cfg.notify = text(),
+        "notify_command" => cfg.notify_command = text(),
+        "history_runs" => cfg.history_runs = number()?,
+        "images" => cfg.images = text(),
+        _ => return Err(format!("unknown key '{key}'")),
+    }
+    Ok(())
+}
+
+/// Settings from `DX_<KEY>` variables (`DX_THEME`, `DX_SHOW_FPS`, ...) and a message for
+/// each value that does not parse; other `DX_*` variables are not settings and are skipped.
+pub fn env_config(
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> (Option<AppConfig>, Vec<String>) {
+    let mut cfg = AppConfig::default();
+    let mut any = false;
+    let mut issues = Vec::new();
+    for (name, value) in vars {
+        let Some(key) = name.strip_prefix("DX_").map(str::to_ascii_lowercase) else {
+            continue;
+        };
+        if !SETTING_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        match apply_setting(&mut cfg, &key, &value) {
+            Ok(()) => any = true,
+            Err(e) => issues.push(format!("{name}: {e}")),
+        }
+    }
+    (any.then_some(cfg), issues)
+}
 
 #[derive(Debug, Clone)]
 pub struct ConfigState {
@@ -118,78 +544,48 @@ pub struct ConfigState {
     pub is_global: bool,
     pub cfg: AppConfig,
     pub message: Option<String>,
+    pub picker: Option<ThemePicker>,
+}
+
+/// Theme list opened with `t`; moving through it previews each theme, Esc restores `original`.
+#[derive(Debug, Clone)]
+pub struct ThemePicker {
+    pub choices: Vec<crate::theme::ThemeChoice>,
+    pub selected: usize,
+    pub original: crate::theme::ThemeTokens,
 }
 
-pub fn open_config_state() -> ConfigState {
-    // Prefer YAML, then TOML
-    let project_root = crate::exec::find_project_root();
-    let local_yaml = project_root.join("config.yaml");
-    let local_yml = project_root.join("config.yml");
-    let local_toml = project_root.join("config.toml");
-    let (path, is_global) = if local_yaml.exists() {
-        (local_yaml, false)
-    } else if local_yml.exists() {
-        (local_yml, false)
-    } else if local_toml.exists() {
-        (local_toml, false)
-    } else {
-        let home = std::env::var("HOME").ok();
-        let cfg_dir = home
-            .map(|h| PathBuf::from(h).join(".dx"))
-            .unwrap_or_else(|| PathBuf::from("~/.dx"));
-        let gyaml = cfg_dir.join("config.yaml");
-        let gyml = cfg_dir.join("config.yml");
-        let gtoml = cfg_dir.join("config.toml");
-        if gyaml.exists() {
-            (gyaml, true)
-        } else if gyml.exists() {
-            (gyml, true)
-        } else {
-            (gtoml, true)
+/// Config screen for the file the loaded settings came from: the project's `config.*`,
+/// else the global one, else a new `~/.dx/config.toml`.
+#[must_use]
+pub fn open_config_state(settings: &LayeredConfig) -> ConfigState {
+    let layer = settings
+        .layer(ConfigSource::Project)
+        .or_else(|| settings.layer(ConfigSource::Global));
+    let (path, is_global, cfg) = match layer {
+        Some(ConfigLayer {
+            source,
+            path: Some(path),
+            cfg,
+        }) => (path.clone(), *source == ConfigSource::Global, cfg.clone()),
+        _ => {
+            let home = std::env::var("HOME").ok();
+            let cfg_dir = home
+                .map(|h| PathBuf::from(h).join(".dx"))
+                .unwrap_or_else(|| PathBuf::from("~/.dx"));
+            let cfg = AppConfig {
+                allow_project_override: true,
+                ..AppConfig::default()
+            };
+            (cfg_dir.join("config.toml"), true, cfg)
         }
     };
-    let cfg = load_app_config_file(&path).unwrap_or(AppConfig {
-        status: None,
-        allow_project_override: true,
-        motd_wrap: Some(true),
-        motd_color: None,
-        markdown_enabled: Some(true),
-        output_dim: Some(true),
-        theme: Some("dark".to_string()),
-        theme_file: None,
-        theme_overrides: None,
-        theme_dir: None,
-        telemetry: Some(TelemetryConfig {
-            enabled: false,
-            endpoint: None,
-        }),
-        update: Some(UpdateConfig {
-            on_start: false,
-            build_cmd: crate::default_build_cmd(),
-            relaunch_path: None,
-            preserve_args: true,
-        }),
-        asciinema: Some(AsciinemaConfig {
-            enabled: false,
-            external: false,
-            on_relaunch: false,
-            dir: None,
-            file_prefix: Some("dx".to_string()),
-            title: None,
-            quiet: false,
-            overwrite: false,
-            stream: false,
-            stream_mode: default_stream_mode(),
-            local_addr: None,
-            remote: None,
-        }),
-        show_fps: Some(true),
-    });
     ConfigState {
         path,
         is_global,
         cfg,
         message: None,
+        picker: None,
     }
 }
 
@@ -0,0 +1,234 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ratatui::text::Text;
+
+use crate::markdown::{MdLayout, render_markdown};
+use crate::menu::MenuItem;
+use crate::search::find_matches;
+use crate::theme::ThemeTokens;
+
+/// Most search hits listed for one query.
+pub const MAX_HITS: usize = 200;
+
+/// A line of a document that matches a full-text search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocHit {
+    pub path: PathBuf,
+    pub title: String,
+    /// 1-based line number in the file (best guess when rendering moved the text)
+    pub line: usize,
+    pub text: String,
+    /// How many matches come before this one in the rendered document, to find it again
+    /// in the viewer
+    pub nth: usize,
+}
+
+/// Markdown files are the documents; everything else in a docs directory is ignored.
+#[must_use]
+pub fn is_doc(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+}
+
+/// The document's first heading, or its file name when it has none.
+#[must_use]
+pub fn doc_title(path: &Path, content: &str) -> String {
+    let mut in_fence = false;
+    for line in content.lines() {
+        let t = line.trim_start();
+        if t.starts_with("```") || t.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let hashes = t.chars().take_while(|&c| c == '#').count();
+        if (1..=6).contains(&hashes) && (t.len() == hashes || t[hashes..].starts_with(' ')) {
+            let title = t[hashes..].trim().trim_end_matches('#').trim();
+            if !title.is_empty() {
+                return title.to_string();
+            }
+        }
+    }
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Sub-directories and documents of `dir`, sorted by name; hidden entries are skipped.
+/// Symlinked directories are skipped too, so a link back up the tree cannot loop.
+fn entries(dir: &Path) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let mut dirs = Vec::new();
+    let mut docs = Vec::new();
+    let Ok(read) = fs::read_dir(dir) else {
+        return (dirs, docs);
+    };
+    for entry in read.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(kind) = entry.file_type() else {
+            continue;
+        };
+        if kind.is_dir() {
+            dirs.push(path);
+        } else if kind.is_symlink() && path.is_dir() {
+            continue;
+        } else if is_doc(&path) {
+            docs.push(path);
+        }
+    }
+    dirs.sort();
+    docs.sort();
+    (dirs, docs)
+}
+
+fn item(name: String, desc: Option<String>) -> MenuItem {
+    MenuItem {
+        name,
+        desc,
+        alias: None,
+        aliases: None,
+        cmd: None,
+        file: None,
+        items: Vec::new(),
+        capture: None,
+        external: None,
+        enhanced_terminal: None,
+        form: None,
+        plugin_list: false,
+        timeout: None,
+        retries: None,
+        backoff: None,
+        key: None,
+        key_global: false,
+        docs: None,
+        reveal: None,
+    }
+}
+
+/// Menu entries for a docs directory: folders first (those without documents are left
+/// out), then documents titled by their first heading.
+#[must_use]
+pub fn docs_items(dir: &Path) -> Vec<MenuItem> {
+    let (dirs, docs) = entries(dir);
+    let mut out = Vec::new();
+    for sub in dirs {
+        let children = docs_items(&sub);
+        if children.is_empty() {
+            continue;
+        }
+        let name = sub
+            .file_name()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut folder = item(name, Some(sub.display().to_string()));
+        folder.items = children;
+        out.push(folder);
+    }
+    for path in docs {
+        let content = fs::read_to_string(&path).unwrap_or_default();
+        let mut doc = item(doc_title(&path, &content), Some(path.display().to_string()));
+        doc.file = Some(path.display().to_string());
+        out.push(doc);
+    }
+    out
+}
+
+fn collect_docs(dir: &Path, out: &mut Vec<PathBuf>) {
+    let (dirs, docs) = entries(dir);
+    out.extend(docs);
+    for sub in dirs {
+        collect_docs(&sub, out);
+    }
+}
+
+/// Full-text search of every document under `dir`, one hit per matching line.
+/// Case-insensitive unless the query has an uppercase letter, like `/` in the viewer.
+/// Documents are searched as rendered with `layout` (its `base_dir` is set per file), so
+/// `nth` counts the same matches the viewer finds when it uses the same layout.
+#[must_use]
+pub fn search_docs(dir: &Path, query: &str, layout: &MdLayout) -> Vec<DocHit> {
+    let mut files = Vec::new();
+    collect_docs(dir, &mut files);
+    let mut hits = Vec::new();
+    for path in files {
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let layout = MdLayout {
+            base_dir: path.parent().map(Path::to_path_buf),
+            ..layout.clone()
+        };
+        let rendered = render_markdown(&content, &ThemeTokens::default(), layout).text;
+        let title = doc_title(&path, &content);
+        let source: Vec<&str> = content.lines().collect();
+        // Source line of the previous hit and where to look for the next; hits are in
+        // document order
+        let (mut cursor, mut next) = (0, 0);
+        let mut last_line = None;
+        for (nth, m) in find_matches(&rendered, query).iter().enumerate() {
+            if last_line == Some(m.line) {
+                continue;
+            }
+            last_line = Some(m.line);
+            let text: String = rendered.lines[m.line]
+                .spans
+                .iter()
+                .map(|s| s.content.as_ref())
+                .collect();
+            let text = text.trim().to_string();
+            // Prefer the source line holding the rendered text, else the next one that
+            // matches the query (inline markup or a link target may be in the way)
+            let needle = text.to_lowercase();
+            let found = (next..source.len())
+                .find(|&i| source[i].to_lowercase().contains(&needle))
+                .or_else(|| {
+                    (next..source.len())
+                        .find(|&i| !find_matches(&Text::from(source[i]), query).is_empty())
+                });
+            if let Some(i) = found {
+                (cursor, next) = (i, i + 1);
+            }
+            hits.push(DocHit {
+                path: path.clone(),
+                title: title.clone(),
+                line: cursor + 1,
+                text,
+                nth,
+            });
+            if hits.len() >= MAX_HITS {
+                return hits;
+            }
+        }
+    }
+    hits
+}
+
+/// A menu folder listing search hits; opening one shows the document at the match.
+#[must_use]
+pub fn results_item(query: &str, hits: &[DocHit]) -> MenuItem {
+    let name = match hits.len() {
+        0 => format!("Search: {query} — no matches"),
+        n if n >= MAX_HITS => format!("Search: {query} — first {n} matches"),
+        n => format!("Search: {query} — {n} matches"),
+    };
+    let mut folder = item(name, None);
+    folder.items = hits
+        .iter()
+        .map(|h| {
+            let mut hit = item(
+                format!("{} · line {}", h.title, h.line),
+                Some(h.text.clone()),
+            );
+            hit.file = Some(h.path.display().to_string());
+            hit.reveal = Some((query.to_string(), h.nth));
+            hit
+        })
+        .collect();
+    folder
+}
@@ -1,12 +1,15 @@
 use std::env;
 use std::io::{Read, Write};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use portable_pty::{
     Child as PtyChild, CommandBuilder as PtyCommandBuilder, MasterPty, PtySize, native_pty_system,
 };
 
+use crate::menu::RunPolicy;
+
 /// Streamed output events from a running process.
 #[derive(Debug)]
 pub enum OutputMsg {
@@ -27,7 +30,8 @@ pub struct Spawned {
 }
 
 /// Spawn a command under a PTY and return handles plus a channel of `OutputMsg` events.
-/// The command is run via `bash -lc "<cmd>; code=$?; printf '\n__DX_EXIT_CODE:%d\n' "$code""` to capture exit code.
+/// The command is run via `$SHELL -lc "<cmd>"`; poll it with [`pty_try_wait`] for the exit status.
+/// The shell starts a new session, so its pid is also the process group id used by [`pty_signal`].
 /// 
 /// # Errors
 /// Returns error if PTY creation or command spawn fails.
@@ -53,8 +57,7 @@ pub fn spawn_pty_with_size(cmd_str: &str, rows: u16, cols: u16) -> Result<Spawne
     let shell = env::var("SHELL").unwrap_or_else(|_| "/bin/bash".to_string());
     let mut cmd = PtyCommandBuilder::new(shell);
     cmd.arg("-lc");
-    let wrapped = format!("{cmd_str}; code=$?; printf '\n__DX_EXIT_CODE:%d\n' \"$code\"");
-    cmd.arg(wrapped);
+    cmd.arg(cmd_str);
     
     // Set working directory to where dx was invoked
     if let Ok(original_cwd) = env::current_dir() {
@@ -173,6 +176,470 @@ pub fn spawn_pty_with_size(cmd_str: &str, rows: u16, cols: u16) -> Result<Spawne
     })
 }
 
+/// Exit code reported when the final attempt was killed by its timeout (matches coreutils `timeout`).
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Run a command attached to the current TTY, honouring a menu item's timeout/retry policy.
+/// `make_cmd` builds a fresh `Command` for every attempt; a delimiter is printed to stderr
+/// between attempts. Returns the exit code of the last attempt.
+///
+/// # Errors
+/// Returns error if the command cannot be spawned or waited on.
+pub fn run_blocking_with_policy(
+    mut make_cmd: impl FnMut() -> std::process::Command,
+    policy: &RunPolicy,
+) -> std::io::Result<i32> {
+    let attempts = policy.retries.saturating_add(1);
+    let mut attempt: u32 = 1;
+    loop {
+        let mut cmd = make_cmd();
+        let foreground = own_process_group(&mut cmd);
+        let mut child = cmd.spawn()?;
+        if foreground {
+            give_terminal_to(child.id());
+        }
+        let deadline = policy.timeout.map(|t| Instant::now() + t);
+        let mut timed_out = false;
+        let status = loop {
+            if let Some(st) = child.try_wait()? {
+                break st;
+            }
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                timed_out = true;
+                break stop_group(&mut child)?;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        if foreground {
+            give_terminal_to(std::process::id());
+        }
+        // Ctrl+C reaches only the command's group now; it stops the run instead of retrying
+        #[cfg(unix)]
+        if std::os::unix::process::ExitStatusExt::signal(&status) == Some(libc::SIGINT) {
+            return Ok(128 + libc::SIGINT);
+        }
+        let code = if timed_out {
+            TIMEOUT_EXIT_CODE
+        } else {
+            status.code().unwrap_or(1)
+        };
+        if (!timed_out && status.success()) || attempt >= attempts {
+            if timed_out {
+                eprintln!(
+                    "\x1b[31mdx: timed out after {}\x1b[0m",
+                    format_duration(policy.timeout.unwrap_or_default())
+                );
+            }
+            return Ok(code);
+        }
+        let delay = policy.delay_before(attempt + 1);
+        let reason = if timed_out {
+            "timed out".to_string()
+        } else {
+            format!("exit {code}")
+        };
+        eprintln!(
+            "\x1b[33m── dx: attempt {attempt}/{attempts} failed ({reason}); retrying in {} ──\x1b[0m",
+            format_duration(delay)
+        );
+        std::thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+/// Start `cmd` in a process group of its own so a timeout can stop everything it starts.
+/// When dx owns the terminal, the group is also made the foreground group so keyboard input
+/// and Ctrl+C still reach the command; returns whether that hand-over is needed.
+#[cfg(unix)]
+fn own_process_group(cmd: &mut std::process::Command) -> bool {
+    use std::os::unix::process::CommandExt;
+
+    cmd.process_group(0);
+    // SAFETY: tcgetpgrp and getpgrp only read the state of this process and its terminal.
+    let foreground = unsafe { libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp() };
+    if foreground {
+        // SAFETY: the hook runs in the forked child right before exec and only makes
+        // async-signal-safe calls (getpid, tcsetpgrp, signal).
+        unsafe {
+            cmd.pre_exec(|| {
+                give_terminal_to(std::process::id());
+                Ok(())
+            });
+        }
+    }
+    foreground
+}
+
+#[cfg(not(unix))]
+fn own_process_group(_cmd: &mut std::process::Command) -> bool {
+    false
+}
+
+/// Make process group `pgid` the terminal's foreground group. Both the command (before exec)
+/// and dx (after spawning) do this, so neither order of events leaves the command stopped.
+#[cfg(unix)]
+fn give_terminal_to(pgid: u32) {
+    // SAFETY: SIGTTOU is ignored only around tcsetpgrp, so that a process outside the
+    // foreground group is not stopped for taking the terminal; its disposition is restored.
+    unsafe {
+        let previous = libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+        libc::tcsetpgrp(libc::STDIN_FILENO, pgid as libc::pid_t);
+        libc::signal(libc::SIGTTOU, previous);
+    }
+}
+
+#[cfg(not(unix))]
+fn give_terminal_to(_pgid: u32) {}
+
+/// Stop a timed-out command and whatever it started: SIGTERM to its process group, then
+/// SIGKILL for anything still there after the usual grace period.
+fn stop_group(child: &mut std::process::Child) -> std::io::Result<std::process::ExitStatus> {
+    let pgid = child.id();
+    if !signal_group(pgid, StopStage::Terminate) {
+        let _ = child.kill();
+        return child.wait();
+    }
+    let grace = StopGrace::default()
+        .after(StopStage::Terminate)
+        .unwrap_or_default();
+    let until = Instant::now() + grace;
+    let status = loop {
+        if let Some(st) = child.try_wait()? {
+            break st;
+        }
+        if Instant::now() >= until {
+            signal_group(pgid, StopStage::Kill);
+            break child.wait()?;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    // Grandchildren that ignored SIGTERM outlive the shell
+    signal_group(pgid, StopStage::Kill);
+    Ok(status)
+}
+
+/// Compact duration label used everywhere a duration is shown (run timer, retry/timeout
+/// messages, notifications): "1.5s", "12m 3s", "1h 5m".
+#[must_use]
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    match secs {
+        0..60 => {
+            let s = format!("{:.1}", d.as_secs_f64());
+            format!("{}s", s.strip_suffix(".0").unwrap_or(&s))
+        }
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+/// CPU time and peak memory of a finished process (from `wait4`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceUsage {
+    pub user: Duration,
+    pub system: Duration,
+    pub max_rss_kb: u64,
+}
+
+impl ResourceUsage {
+    /// One-line summary, e.g. "user 1.2s · sys 0.3s · max RSS 45.1 MB".
+    #[must_use]
+    pub fn summary(&self) -> String {
+        format!(
+            "user {} · sys {} · max RSS {:.1} MB",
+            format_duration(self.user),
+            format_duration(self.system),
+            self.max_rss_kb as f64 / 1024.0
+        )
+    }
+}
+
+/// How a child process ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessExit {
+    /// Exit code when the process exited normally
+    pub code: Option<i32>,
+    /// Signal number when the process was killed by a signal
+    pub signal: Option<i32>,
+    pub core_dumped: bool,
+    pub usage: Option<ResourceUsage>,
+}
+
+impl ProcessExit {
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// Shell-style status: the exit code, or 128 + signal number.
+    #[must_use]
+    pub fn exit_code(&self) -> i32 {
+        match (self.code, self.signal) {
+            (Some(code), _) => code,
+            (None, Some(sig)) => 128 + sig,
+            (None, None) => 1,
+        }
+    }
+
+    /// Short reason, e.g. "exit 2" or "killed by SIGSEGV, core dumped".
+    #[must_use]
+    pub fn describe(&self) -> String {
+        match self.signal {
+            Some(sig) => {
+                let core = if self.core_dumped { ", core dumped" } else { "" };
+                format!("killed by {}{core}", signal_name(sig))
+            }
+            None => format!("exit {}", self.exit_code()),
+        }
+    }
+
+    #[cfg(unix)]
+    fn from_wait_status(status: i32, usage: Option<ResourceUsage>) -> Self {
+        let signaled = libc::WIFSIGNALED(status);
+        Self {
+            code: libc::WIFEXITED(status).then(|| libc::WEXITSTATUS(status)),
+            signal: signaled.then(|| libc::WTERMSIG(status)),
+            core_dumped: signaled && libc::WCOREDUMP(status),
+            usage,
+        }
+    }
+}
+
+/// Conventional name of a signal number ("SIGTERM"), or "signal N" for others.
+#[must_use]
+pub fn signal_name(sig: i32) -> String {
+    let name = match sig {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return format!("signal {sig}"),
+    };
+    name.to_string()
+}
+
+/// Poll a PTY child without blocking. On unix the child is reaped with `wait4` so the
+/// status includes signal/core-dump details and resource usage.
+///
+/// # Errors
+/// Returns error if waiting on the child fails.
+pub fn pty_try_wait(child: &mut Box<dyn PtyChild + Send>) -> std::io::Result<Option<ProcessExit>> {
+    #[cfg(unix)]
+    if let Some(pid) = child.process_id() {
+        let mut status: libc::c_int = 0;
+        // SAFETY: rusage is plain old data; wait4 only writes into the provided pointers.
+        let mut ru: libc::rusage = unsafe { std::mem::zeroed() };
+        let ret = unsafe { libc::wait4(pid as libc::pid_t, &mut status, libc::WNOHANG, &mut ru) };
+        if ret == 0 {
+            return Ok(None);
+        }
+        if ret > 0 {
+            let tv = |t: libc::timeval| {
+                Duration::from_secs(t.tv_sec.max(0) as u64)
+                    + Duration::from_micros(t.tv_usec.max(0) as u64)
+            };
+            // ru_maxrss is KiB on Linux but bytes on macOS
+            let max_rss_kb = if cfg!(target_os = "macos") {
+                ru.ru_maxrss.max(0) as u64 / 1024
+            } else {
+                ru.ru_maxrss.max(0) as u64
+            };
+            let usage = ResourceUsage {
+                user: tv(ru.ru_utime),
+                system: tv(ru.ru_stime),
+                max_rss_kb,
+            };
+            return Ok(Some(ProcessExit::from_wait_status(status, Some(usage))));
+        }
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ECHILD) {
+            return Err(err);
+        }
+        // Already reaped elsewhere: fall through to the portable path
+    }
+    Ok(child.try_wait()?.map(|st| ProcessExit {
+        code: Some(st.exit_code() as i32),
+        signal: None,
+        core_dumped: false,
+        usage: None,
+    }))
+}
+
+/// Escalation steps of a graceful stop, each sent to the command's whole process group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopStage {
+    Interrupt,
+    Terminate,
+    Kill,
+}
+
+impl StopStage {
+    #[must_use]
+    pub fn signal_name(self) -> &'static str {
+        match self {
+            StopStage::Interrupt => "SIGINT",
+            StopStage::Terminate => "SIGTERM",
+            StopStage::Kill => "SIGKILL",
+        }
+    }
+
+    #[must_use]
+    pub fn next(self) -> Option<StopStage> {
+        match self {
+            StopStage::Interrupt => Some(StopStage::Terminate),
+            StopStage::Terminate => Some(StopStage::Kill),
+            StopStage::Kill => None,
+        }
+    }
+
+    #[cfg(unix)]
+    fn signal(self) -> libc::c_int {
+        match self {
+            StopStage::Interrupt => libc::SIGINT,
+            StopStage::Terminate => libc::SIGTERM,
+            StopStage::Kill => libc::SIGKILL,
+        }
+    }
+}
+
+/// How long each stage of a graceful stop waits before escalating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StopGrace {
+    /// SIGINT → SIGTERM
+    pub interrupt: Duration,
+    /// SIGTERM → SIGKILL
+    pub terminate: Duration,
+}
+
+impl Default for StopGrace {
+    fn default() -> Self {
+        Self {
+            interrupt: Duration::from_secs(2),
+            terminate: Duration::from_secs(5),
+        }
+    }
+}
+
+impl StopGrace {
+    /// Wait after sending `stage` before moving to the next one (`None` after SIGKILL).
+    #[must_use]
+    pub fn after(&self, stage: StopStage) -> Option<Duration> {
+        match stage {
+            StopStage::Interrupt => Some(self.interrupt),
+            StopStage::Terminate => Some(self.terminate),
+            StopStage::Kill => None,
+        }
+    }
+}
+
+/// Send the signal of `stage` to the process group of a PTY child, reaching grandchildren
+/// such as dev servers started by npm. Falls back to `Child::kill` where process groups
+/// are unavailable.
+pub fn pty_signal(child: &mut Box<dyn PtyChild + Send>, stage: StopStage) {
+    #[cfg(unix)]
+    if let Some(pid) = child.process_id() {
+        if signal_group(pid, stage) {
+            return;
+        }
+        // SAFETY: kill only sends a signal; the pid comes from our own child
+        if unsafe { libc::kill(pid as libc::pid_t, stage.signal()) } == 0 {
+            return;
+        }
+    }
+    let _ = child.kill();
+}
+
+/// Send the signal of `stage` to process group `pgid`; false when no such group exists.
+#[cfg(unix)]
+fn signal_group(pgid: u32, stage: StopStage) -> bool {
+    // SAFETY: killpg only sends a signal; the group id comes from our own child
+    unsafe { libc::killpg(pgid as libc::pid_t, stage.signal()) == 0 }
+}
+
+#[cfg(not(unix))]
+fn signal_group(_pgid: u32, _stage: StopStage) -> bool {
+    false
+}
+
+/// A process that outlived the command it was started by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Survivor {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Processes still running in the process group or session led by `leader`, excluding zombies.
+#[cfg(unix)]
+#[must_use]
+pub fn group_survivors(leader: u32) -> Vec<Survivor> {
+    let mut out = Vec::new();
+    #[cfg(target_os = "linux")]
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+                continue;
+            };
+            let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            // "pid (comm) state ppid pgrp session ..." where comm may contain spaces
+            let (Some(open), Some(close)) = (stat.find('('), stat.rfind(')')) else {
+                continue;
+            };
+            let fields: Vec<&str> = stat[close + 1..].split_whitespace().collect();
+            let (Some(state), Some(pgrp), Some(session)) = (fields.first(), fields.get(2), fields.get(3))
+            else {
+                continue;
+            };
+            let ours = [pgrp, session]
+                .iter()
+                .any(|f| f.parse::<u32>().ok() == Some(leader));
+            if ours && *state != "Z" && pid != leader {
+                out.push(Survivor {
+                    pid,
+                    name: stat[open + 1..close].to_string(),
+                });
+            }
+        }
+        return out;
+    }
+    if let Ok(o) = std::process::Command::new("ps")
+        .args(["-A", "-o", "pid=,pgid=,state=,comm="])
+        .output()
+    {
+        for line in String::from_utf8_lossy(&o.stdout).lines() {
+            let mut it = line.split_whitespace();
+            let (Some(pid), Some(pgid), Some(state)) = (it.next(), it.next(), it.next()) else {
+                continue;
+            };
+            let (Ok(pid), Ok(pgid)) = (pid.parse::<u32>(), pgid.parse::<u32>()) else {
+                continue;
+            };
+            if pgid == leader && pid != leader && !state.starts_with('Z') {
+                out.push(Survivor {
+                    pid,
+                    name: it.collect::<Vec<_>>().join(" "),
+                });
+            }
+        }
+    }
+    out
+}
+
+#[cfg(not(unix))]
+#[must_use]
+pub fn group_survivors(_leader: u32) -> Vec<Survivor> {
+    Vec::new()
+}
+
 // Convenience helpers so UI code does not touch PTY primitives directly
 pub fn pty_write(writer: &mut Option<Box<dyn Write + Send>>, bytes: &[u8]) {
     if let Some(w) = writer {
@@ -184,7 +651,7 @@ pub fn pty_write(writer: &mut Option<Box<dyn Write + Send>>, bytes: &[u8]) {
 #[allow(dead_code)]
 pub fn pty_kill(child: &mut Option<Box<dyn PtyChild + Send>>) {
     if let Some(c) = child {
-        let _ = c.kill();
+        pty_signal(c, StopStage::Kill);
     }
 }
 
@@ -1,6 +1,6 @@
 use ratatui::Frame;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Modifier, Style};
+use ratatui::style::Style;
 use ratatui::text::Line;
 use ratatui::text::Text;
 use ratatui::widgets::{Block, Borders, Clear, Padding, Paragraph};
@@ -43,22 +43,28 @@ pub fn render_border_block<'a>(title: impl Into<Line<'a>>, area: Rect, f: &mut F
     }
 }
 
-pub fn render_modal<'a>(title: impl Into<Line<'a>>, msg: &str, area: Rect, f: &mut Frame) {
-    let inverted = Style::default().add_modifier(Modifier::REVERSED);
+/// Draw a dialog box in the theme's `modal` style over whatever is below it.
+pub fn render_modal<'a>(
+    title: impl Into<Line<'a>>,
+    msg: &str,
+    style: Style,
+    area: Rect,
+    f: &mut Frame,
+) {
     let modal = Paragraph::new(Text::from(msg.to_string()))
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title(title)
-                .style(inverted),
+                .style(style),
         )
-        .style(inverted);
+        .style(style);
     f.render_widget(Clear, area);
     f.render_widget(modal, area);
 }
 
-pub fn render_status_bar(spans: Line, area: Rect, f: &mut Frame) {
-    let status_para = Paragraph::new(spans);
+pub fn render_status_bar(spans: Line, style: Style, area: Rect, f: &mut Frame) {
+    let status_para = Paragraph::new(spans).style(style);
     f.render_widget(Clear, area);
     f.render_widget(status_para, area);
 }
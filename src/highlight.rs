@@ -0,0 +1,162 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+use crate::theme::ThemeTokens;
+
+/// Files larger than this are shown without highlighting.
+const MAX_HIGHLIGHT_BYTES: usize = 1024 * 1024;
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SET: OnceLock<SyntaxSet> = OnceLock::new();
+    SET.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+/// Highlight classes; each maps to a `syntax_*` colour of the theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Function,
+    Type,
+}
+
+/// Scope prefixes checked from the innermost scope outwards; the first hit wins.
+fn scope_classes() -> &'static [(Scope, Class)] {
+    static RULES: OnceLock<Vec<(Scope, Class)>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        [
+            ("comment", Class::Comment),
+            ("string", Class::String),
+            ("constant.numeric", Class::Number),
+            ("constant.language", Class::Number),
+            ("constant.character", Class::String),
+            ("keyword", Class::Keyword),
+            ("storage", Class::Keyword),
+            ("entity.name.tag", Class::Keyword),
+            ("entity.name.function", Class::Function),
+            ("support.function", Class::Function),
+            ("variable.function", Class::Function),
+            ("entity.name.type", Class::Type),
+            ("entity.name.class", Class::Type),
+            ("entity.name.struct", Class::Type),
+            ("entity.name.enum", Class::Type),
+            ("support.type", Class::Type),
+            ("support.class", Class::Type),
+            ("entity.other.attribute-name", Class::Function),
+        ]
+        .into_iter()
+        .filter_map(|(s, c)| Scope::new(s).ok().map(|s| (s, c)))
+        .collect()
+    })
+}
+
+fn class_color(class: Class, theme: &ThemeTokens) -> Color {
+    match class {
+        Class::Comment => theme.syntax_comment,
+        Class::String => theme.syntax_string,
+        Class::Number => theme.syntax_number,
+        Class::Keyword => theme.syntax_keyword,
+        Class::Function => theme.syntax_function,
+        Class::Type => theme.syntax_type,
+    }
+}
+
+fn style_for(stack: &ScopeStack, theme: &ThemeTokens, base: Style) -> Style {
+    for scope in stack.as_slice().iter().rev() {
+        if let Some((_, class)) = scope_classes()
+            .iter()
+            .find(|(prefix, _)| prefix.is_prefix_of(*scope))
+        {
+            return base.fg(class_color(*class, theme));
+        }
+    }
+    base
+}
+
+/// Syntax for a fenced code block info string such as `rust`, `sh` or `yaml title="x"`.
+#[must_use]
+pub fn syntax_for_token(info: &str) -> Option<&'static SyntaxReference> {
+    let token = info
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '{')
+        .next()
+        .unwrap_or("")
+        .trim();
+    if token.is_empty() {
+        return None;
+    }
+    let ss = syntax_set();
+    ss.find_syntax_by_token(token)
+        .or_else(|| ss.find_syntax_by_token(&token.to_ascii_lowercase()))
+}
+
+/// Syntax for a file, by extension or else by its first line (shebang, modelines).
+#[must_use]
+pub fn syntax_for_file(path: &Path, content: &str) -> Option<&'static SyntaxReference> {
+    let ss = syntax_set();
+    let by_name = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .and_then(|e| ss.find_syntax_by_extension(e))
+        .or_else(|| {
+            // Extension-less files such as `Makefile` or `Dockerfile`
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| ss.find_syntax_by_extension(n))
+        });
+    by_name
+        .or_else(|| ss.find_syntax_by_first_line(content.lines().next().unwrap_or("")))
+        .filter(|s| s.name != "Plain Text")
+}
+
+/// Highlight `code` line by line; text outside any recognised scope uses `base`.
+#[must_use]
+pub fn highlight_lines(
+    code: &str,
+    syntax: &SyntaxReference,
+    theme: &ThemeTokens,
+    base: Style,
+) -> Vec<Line<'static>> {
+    if code.len() > MAX_HIGHLIGHT_BYTES {
+        return code
+            .lines()
+            .map(|l| Line::from(Span::styled(l.to_string(), base)))
+            .collect();
+    }
+    let ss = syntax_set();
+    let mut state = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut out = Vec::new();
+    for line in code.lines() {
+        let with_nl = format!("{line}\n");
+        let Ok(ops) = state.parse_line(&with_nl, ss) else {
+            out.push(Line::from(Span::styled(line.to_string(), base)));
+            continue;
+        };
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        // Scope changes often keep the colour; extend the last span instead of splitting
+        let mut push = |text: &str, style: Style| match spans.last_mut() {
+            Some(last) if last.style == style => last.content.to_mut().push_str(text),
+            _ => spans.push(Span::styled(text.to_string(), style)),
+        };
+        let mut pos = 0;
+        for (at, op) in ops {
+            let at = at.min(line.len());
+            if at > pos {
+                push(&line[pos..at], style_for(&stack, theme, base));
+                pos = at;
+            }
+            let _ = stack.apply(&op);
+        }
+        if pos < line.len() {
+            push(&line[pos..], style_for(&stack, theme, base));
+        }
+        out.push(Line::from(spans));
+    }
+    out
+}
@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Runs kept per item when `history_runs` is not configured.
+pub const DEFAULT_KEEP: usize = 10;
+
+/// Output of one finished run, stored as JSON under `~/.dx/history/<key>/<started_ms>.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub title: String,
+    /// Unix time in milliseconds when the run started
+    pub started_ms: u64,
+    pub duration_ms: u64,
+    pub exit_status: Option<i32>,
+    /// Output lines with ANSI escape sequences removed
+    pub lines: Vec<String>,
+}
+
+/// Default history location (`~/.dx/history`).
+#[must_use]
+pub fn default_root() -> Option<PathBuf> {
+    std::env::var("HOME")
+        .ok()
+        .map(|h| PathBuf::from(h).join(".dx").join("history"))
+}
+
+/// Milliseconds since the Unix epoch for `t`.
+#[must_use]
+pub fn unix_ms(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// History key of an item (alias or name) within a project, so two projects with a
+/// `build` item keep separate histories: `<item>@<project dir name>-<path hash>`.
+#[must_use]
+pub fn project_key(project: &Path, item: &str) -> String {
+    // FNV-1a: stable across builds, unlike `DefaultHasher`
+    let hash = project
+        .to_string_lossy()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |h, b| {
+            (h ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+        });
+    let name = project
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    format!("{item}@{name}-{:08x}", hash as u32)
+}
+
+/// Directory holding the runs of `key` (an alias or item name), made filesystem-safe.
+#[must_use]
+pub fn key_dir(root: &Path, key: &str) -> PathBuf {
+    let safe: String = key
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    root.join(safe.trim_start_matches('.'))
+}
+
+/// Stored runs of `key`, oldest first.
+#[must_use]
+pub fn list_runs(root: &Path, key: &str) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(key_dir(root, key)) else {
+        return Vec::new();
+    };
+    let mut runs: Vec<(u64, PathBuf)> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter_map(|p| {
+            let ms = p.file_stem()?.to_str()?.parse::<u64>().ok()?;
+            (p.extension()? == "json").then_some((ms, p))
+        })
+        .collect();
+    runs.sort();
+    runs.into_iter().map(|(_, p)| p).collect()
+}
+
+#[must_use]
+pub fn load_run(path: &Path) -> Option<RunRecord> {
+    let s = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&s).ok()
+}
+
+/// Most recent stored run of `key`.
+#[must_use]
+pub fn latest_run(root: &Path, key: &str) -> Option<RunRecord> {
+    list_runs(root, key).last().and_then(|p| load_run(p))
+}
+
+/// Store `record` and delete the oldest runs beyond `keep`.
+///
+/// # Errors
+/// Returns error if the directory or file cannot be written.
+pub fn save_run(
+    root: &Path,
+    key: &str,
+    record: &RunRecord,
+    keep: usize,
+) -> std::io::Result<PathBuf> {
+    let dir = key_dir(root, key);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.json", record.started_ms));
+    std::fs::write(&path, serde_json::to_vec_pretty(record)?)?;
+    let runs = list_runs(root, key);
+    for old in runs.iter().take(runs.len().saturating_sub(keep)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Remove ANSI escape sequences (CSI, OSC, two-byte escapes) and stray control characters.
+#[must_use]
+pub fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            if !c.is_control() || c == '\t' {
+                out.push(c);
+            }
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameters until a final byte in @..~
+            Some('[') => {
+                for n in chars.by_ref() {
+                    if ('@'..='~').contains(&n) {
+                        break;
+                    }
+                }
+            }
+            // OSC/DCS/APC: until BEL or ST (ESC \)
+            Some(']' | 'P' | '_' | '^') => {
+                while let Some(n) = chars.next() {
+                    if n == '\x07' {
+                        break;
+                    }
+                    if n == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// One line of a line-based diff between two runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+/// Line diff of `old` → `new` (longest common subsequence). Very large middles that would
+/// make the table too big are shown as a block removal followed by a block addition.
+#[must_use]
+pub fn diff_lines(old: &[String], new: &[String]) -> Vec<DiffLine> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut out: Vec<DiffLine> = old[..prefix].iter().cloned().map(DiffLine::Same).collect();
+    if a.len().saturating_mul(b.len()) > 4_000_000 {
+        out.extend(a.iter().cloned().map(DiffLine::Removed));
+        out.extend(b.iter().cloned().map(DiffLine::Added));
+    } else {
+        // lcs[i][j] = LCS length of a[i..] and b[j..]
+        let w = b.len() + 1;
+        let mut lcs = vec![0u32; (a.len() + 1) * w];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i * w + j] = if a[i] == b[j] {
+                    lcs[(i + 1) * w + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * w + j].max(lcs[i * w + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                out.push(DiffLine::Same(a[i].clone()));
+                i += 1;
+                j += 1;
+            } else if lcs[(i + 1) * w + j] >= lcs[i * w + j + 1] {
+                out.push(DiffLine::Removed(a[i].clone()));
+                i += 1;
+            } else {
+                out.push(DiffLine::Added(b[j].clone()));
+                j += 1;
+            }
+        }
+        out.extend(a[i..].iter().cloned().map(DiffLine::Removed));
+        out.extend(b[j..].iter().cloned().map(DiffLine::Added));
+    }
+    out.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .cloned()
+            .map(DiffLine::Same),
+    );
+    out
+}
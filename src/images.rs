@@ -0,0 +1,395 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
+
+use base64::Engine as _;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use image::imageops::FilterType;
+use image::{ImageFormat, RgbaImage};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// Images larger than this on disk are not decoded.
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+/// How often a cached image's file is looked at again for changes.
+pub const RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Cell size assumed when sizing an image in cells (and for sixel when the terminal does not
+/// report pixels).
+const CELL_PX: (u32, u32) = (8, 16);
+
+/// How images are drawn on this terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageProtocol {
+    Kitty,
+    Iterm2,
+    Sixel,
+    /// Unicode half blocks (`▀`) with true colour; works everywhere
+    Blocks,
+}
+
+impl ImageProtocol {
+    /// Parse an `images` config value; `Ok(None)` means images are turned off and
+    /// `auto` detects the terminal.
+    ///
+    /// # Errors
+    /// Returns the value back when it is not a known protocol.
+    pub fn parse(s: &str) -> Result<Option<Self>, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" | "" => Ok(Some(Self::detect())),
+            "kitty" => Ok(Some(Self::Kitty)),
+            "iterm" | "iterm2" => Ok(Some(Self::Iterm2)),
+            "sixel" => Ok(Some(Self::Sixel)),
+            "blocks" | "halfblocks" => Ok(Some(Self::Blocks)),
+            "off" | "none" | "false" => Ok(None),
+            other => Err(other.to_string()),
+        }
+    }
+
+    /// Best protocol for the current terminal, judged from its environment.
+    #[must_use]
+    pub fn detect() -> Self {
+        let var = |k: &str| std::env::var(k).unwrap_or_default();
+        Self::detect_from(&var("TERM"), &var("TERM_PROGRAM"), !var("TMUX").is_empty()).unwrap_or(
+            if std::env::var_os("KITTY_WINDOW_ID").is_some() {
+                Self::Kitty
+            } else {
+                Self::Blocks
+            },
+        )
+    }
+
+    /// Protocol implied by `TERM`/`TERM_PROGRAM`; multiplexers get half blocks because
+    /// they do not pass graphics through.
+    #[must_use]
+    pub fn detect_from(term: &str, term_program: &str, in_tmux: bool) -> Option<Self> {
+        if in_tmux || term.starts_with("screen") {
+            return Some(Self::Blocks);
+        }
+        match term_program {
+            "iTerm.app" => return Some(Self::Iterm2),
+            "WezTerm" | "ghostty" => return Some(Self::Kitty),
+            _ => {}
+        }
+        if term.contains("kitty") || term.contains("ghostty") {
+            Some(Self::Kitty)
+        } else if term.contains("sixel") || term.starts_with("foot") || term == "mlterm" {
+            Some(Self::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+/// An image fully inside the viewport, to be drawn at cell `x`,`y` over `cols`×`rows`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placement {
+    pub path: PathBuf,
+    pub x: u16,
+    pub y: u16,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+#[derive(Default)]
+struct Entry {
+    modified: Option<SystemTime>,
+    /// When the file was last looked at; `None` until the first look
+    checked: Option<Instant>,
+    /// Filled in by a worker thread once the file is decoded
+    image: Option<Arc<RgbaImage>>,
+    blocks: HashMap<(u16, u16), Vec<Line<'static>>>,
+    encoded: HashMap<(ImageProtocol, u16, u16), Arc<String>>,
+}
+
+fn cache() -> std::sync::MutexGuard<'static, HashMap<PathBuf, Entry>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, Entry>>> = OnceLock::new();
+    CACHE
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Run `f` on the cache entry of `path`. The file is looked at again at most once per
+/// [`RECHECK_INTERVAL`]; when it is new or changed, it is decoded on a worker thread and the
+/// entry has no image until that finishes.
+fn with_entry<T>(path: &Path, f: impl FnOnce(&mut Entry) -> T) -> T {
+    let mut cache = cache();
+    let entry = cache.entry(path.to_path_buf()).or_default();
+    if entry
+        .checked
+        .is_none_or(|at| at.elapsed() >= RECHECK_INTERVAL)
+    {
+        let first = entry.checked.is_none();
+        let meta = std::fs::metadata(path).ok();
+        let modified = meta.as_ref().and_then(|m| m.modified().ok());
+        entry.checked = Some(Instant::now());
+        if first || entry.modified != modified {
+            *entry = Entry {
+                modified,
+                checked: entry.checked,
+                ..Entry::default()
+            };
+            if meta.is_some_and(|m| m.len() <= MAX_IMAGE_BYTES) {
+                spawn_decode(path.to_path_buf(), modified);
+            }
+        }
+    }
+    f(entry)
+}
+
+/// Bumped whenever a decode finishes, so rendered documents know to lay out again.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Number of decodes finished so far; a change means some image may now draw differently.
+#[must_use]
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+/// Decode `path` off the UI thread; the result is dropped if the file changed meanwhile.
+fn spawn_decode(path: PathBuf, modified: Option<SystemTime>) {
+    std::thread::spawn(move || {
+        let image = image::open(&path).ok().map(|img| Arc::new(img.to_rgba8()));
+        if let Some(entry) = cache().get_mut(&path)
+            && entry.modified == modified
+        {
+            entry.image = image;
+            GENERATION.fetch_add(1, Ordering::Relaxed);
+        }
+    });
+}
+
+/// Decoded image at `path` (PNG, JPEG or GIF), cached until the file changes. `None` while
+/// it is still being decoded.
+#[must_use]
+pub fn load(path: &Path) -> Option<Arc<RgbaImage>> {
+    with_entry(path, |e| e.image.clone())
+}
+
+/// Size in cells for an image of `width`×`height` pixels, shrunk (keeping its aspect) to fit
+/// `max_cols`×`max_rows`.
+#[must_use]
+pub fn fit_cells(width: u32, height: u32, max_cols: u16, max_rows: u16) -> (u16, u16) {
+    let natural_cols = f64::from(width.max(1)) / f64::from(CELL_PX.0);
+    let natural_rows = f64::from(height.max(1)) / f64::from(CELL_PX.1);
+    let scale = 1f64
+        .min(f64::from(max_cols.max(1)) / natural_cols)
+        .min(f64::from(max_rows.max(1)) / natural_rows);
+    let cols = (natural_cols * scale)
+        .round()
+        .clamp(1.0, f64::from(max_cols.max(1)));
+    let rows = (natural_rows * scale)
+        .round()
+        .clamp(1.0, f64::from(max_rows.max(1)));
+    (cols as u16, rows as u16)
+}
+
+fn pixel_color(p: image::Rgba<u8>) -> Option<Color> {
+    (p[3] >= 128).then_some(Color::Rgb(p[0], p[1], p[2]))
+}
+
+/// The image as `rows` lines of `cols` half-block cells: each cell shows two pixels, the upper
+/// as foreground and the lower as background. Transparent pixels keep the terminal background.
+#[must_use]
+pub fn half_block_lines(path: &Path, cols: u16, rows: u16) -> Option<Vec<Line<'static>>> {
+    with_entry(path, |entry| {
+        let image = entry.image.clone()?;
+        let lines = entry.blocks.entry((cols, rows)).or_insert_with(|| {
+            let small = image::imageops::resize(
+                image.as_ref(),
+                u32::from(cols),
+                u32::from(rows) * 2,
+                FilterType::Triangle,
+            );
+            (0..u32::from(rows))
+                .map(|row| {
+                    let spans: Vec<Span<'static>> = (0..u32::from(cols))
+                        .map(|x| {
+                            let top = pixel_color(*small.get_pixel(x, row * 2));
+                            let bottom = pixel_color(*small.get_pixel(x, row * 2 + 1));
+                            let mut style = Style::default();
+                            if let Some(c) = bottom {
+                                style = style.bg(c);
+                            }
+                            match top {
+                                Some(c) => Span::styled("▀", style.fg(c)),
+                                None => Span::styled(" ", style),
+                            }
+                        })
+                        .collect();
+                    Line::from(spans)
+                })
+                .collect()
+        });
+        Some(lines.clone())
+    })
+}
+
+/// PNG bytes of `image` scaled down to at most `max_w`×`max_h` pixels.
+fn png_bytes(image: &RgbaImage, max_w: u32, max_h: u32) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    if image.width() > max_w || image.height() > max_h {
+        image::DynamicImage::ImageRgba8(image.clone())
+            .resize(max_w, max_h, FilterType::Triangle)
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .ok()?;
+    } else {
+        image
+            .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+            .ok()?;
+    }
+    Some(out)
+}
+
+/// Kitty graphics protocol: transmit and display a PNG over `cols`×`rows` cells at the cursor.
+#[must_use]
+pub fn kitty_sequence(png: &[u8], cols: u16, rows: u16) -> String {
+    let data = BASE64.encode(png);
+    let chunks: Vec<&[u8]> = data.as_bytes().chunks(4096).collect();
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if i == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=100,c={cols},r={rows},C=1,q=2,m={more};{chunk}\x1b\\"
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    out
+}
+
+/// Remove every image placed with the kitty protocol.
+pub const KITTY_CLEAR: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// iTerm2 inline image over `cols`×`rows` cells at the cursor.
+#[must_use]
+pub fn iterm_sequence(png: &[u8], cols: u16, rows: u16) -> String {
+    format!(
+        "\x1b]1337;File=inline=1;size={};width={cols};height={rows};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        BASE64.encode(png)
+    )
+}
+
+/// Sixel image of `image` scaled to `width`×`height` pixels, using a 6×6×6 colour cube.
+#[must_use]
+pub fn sixel_sequence(image: &RgbaImage, width: u32, height: u32) -> String {
+    let img = image::imageops::resize(image, width.max(1), height.max(1), FilterType::Triangle);
+    let (w, h) = img.dimensions();
+    let level = |v: u8| (u16::from(v) * 5 + 127) / 255;
+    let index: Vec<Option<u16>> = img
+        .pixels()
+        .map(|p| (p[3] >= 128).then(|| level(p[0]) * 36 + level(p[1]) * 6 + level(p[2])))
+        .collect();
+
+    let mut out = format!("\x1bPq\"1;1;{w};{h}");
+    let mut used = [false; 216];
+    for i in index.iter().flatten() {
+        used[*i as usize] = true;
+    }
+    for (i, _) in used.iter().enumerate().filter(|(_, u)| **u) {
+        let pct = |l: usize| l * 100 / 5;
+        let _ = write!(
+            out,
+            "#{i};2;{};{};{}",
+            pct(i / 36),
+            pct(i / 6 % 6),
+            pct(i % 6)
+        );
+    }
+    for band in (0..h).step_by(6) {
+        let band_rows = (h - band).min(6);
+        let mut colours: Vec<u16> = (0..band_rows)
+            .flat_map(|dy| {
+                let row = ((band + dy) * w) as usize;
+                index[row..row + w as usize].iter().flatten().copied()
+            })
+            .collect();
+        colours.sort_unstable();
+        colours.dedup();
+        for colour in colours {
+            let _ = write!(out, "#{colour}");
+            let mut run: Option<(char, usize)> = None;
+            let flush = |out: &mut String, run: Option<(char, usize)>| match run {
+                Some((c, n)) if n > 3 => {
+                    let _ = write!(out, "!{n}{c}");
+                }
+                Some((c, n)) => out.extend(std::iter::repeat_n(c, n)),
+                None => {}
+            };
+            for x in 0..w {
+                let mut bits = 0u8;
+                for dy in 0..band_rows {
+                    if index[((band + dy) * w + x) as usize] == Some(colour) {
+                        bits |= 1 << dy;
+                    }
+                }
+                let c = char::from(63 + bits);
+                run = match run {
+                    Some((prev, n)) if prev == c => Some((prev, n + 1)),
+                    other => {
+                        flush(&mut out, other);
+                        Some((c, 1))
+                    }
+                };
+            }
+            flush(&mut out, run);
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Escape sequence drawing `placement` with `protocol`, cursor saved and restored around it.
+/// `cell_px` is the terminal's cell size in pixels, when known (needed for sixel).
+#[must_use]
+pub fn overlay_sequence(
+    protocol: ImageProtocol,
+    placement: &Placement,
+    cell_px: Option<(u32, u32)>,
+) -> Option<String> {
+    if protocol == ImageProtocol::Blocks {
+        return None;
+    }
+    let (cols, rows) = (placement.cols, placement.rows);
+    let body = with_entry(&placement.path, |entry| {
+        let image = entry.image.clone()?;
+        if let Some(seq) = entry.encoded.get(&(protocol, cols, rows)) {
+            return Some(seq.clone());
+        }
+        let (cw, ch) = cell_px.unwrap_or(CELL_PX);
+        let (px_w, px_h) = (u32::from(cols) * cw, u32::from(rows) * ch);
+        let seq = match protocol {
+            ImageProtocol::Kitty => kitty_sequence(&png_bytes(&image, px_w, px_h)?, cols, rows),
+            ImageProtocol::Iterm2 => iterm_sequence(&png_bytes(&image, px_w, px_h)?, cols, rows),
+            ImageProtocol::Sixel => {
+                // Keep the image's aspect inside the reserved cells
+                let scale = (f64::from(px_w) / f64::from(image.width()))
+                    .min(f64::from(px_h) / f64::from(image.height()));
+                let w = (f64::from(image.width()) * scale) as u32;
+                let h = (f64::from(image.height()) * scale) as u32;
+                sixel_sequence(&image, w, h)
+            }
+            ImageProtocol::Blocks => return None,
+        };
+        let seq = Arc::new(seq);
+        entry.encoded.insert((protocol, cols, rows), seq.clone());
+        Some(seq)
+    })?;
+    Some(format!(
+        "\x1b7\x1b[{};{}H{}\x1b8",
+        placement.y + 1,
+        placement.x + 1,
+        body
+    ))
+}
@@ -0,0 +1,223 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+/// Terminal modes of the program on the other side of the PTY that change key encoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyModes {
+    /// DECCKM (`CSI ? 1 h`): unmodified arrows/Home/End use SS3 (`ESC O A`)
+    pub application_cursor: bool,
+    /// Mode 2004: pasted text is wrapped in `ESC [200~` / `ESC [201~`
+    pub bracketed_paste: bool,
+}
+
+/// Encode a key press as the bytes an xterm-compatible terminal would send.
+/// Returns `None` for key releases and keys with no terminal encoding.
+#[must_use]
+pub fn encode_key(key: &KeyEvent, modes: KeyModes) -> Option<Vec<u8>> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    let mods = key.modifiers;
+    let alt = mods.contains(KeyModifiers::ALT);
+    let ctrl = mods.contains(KeyModifiers::CONTROL);
+    let with_alt = |mut bytes: Vec<u8>| {
+        if alt {
+            bytes.insert(0, 0x1b);
+        }
+        bytes
+    };
+    let bytes = match key.code {
+        KeyCode::Char(c) => {
+            if let Some(b) = ctrl_byte(c).filter(|_| ctrl) {
+                return Some(with_alt(vec![b]));
+            }
+            let mut buf = [0u8; 4];
+            with_alt(c.encode_utf8(&mut buf).as_bytes().to_vec())
+        }
+        KeyCode::Enter => with_alt(b"\r".to_vec()),
+        KeyCode::Tab => with_alt(b"\t".to_vec()),
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => with_alt(if ctrl { vec![0x08] } else { vec![0x7f] }),
+        KeyCode::Esc => with_alt(vec![0x1b]),
+        KeyCode::Null => vec![0],
+        KeyCode::Up => cursor_key(b'A', mods, modes),
+        KeyCode::Down => cursor_key(b'B', mods, modes),
+        KeyCode::Right => cursor_key(b'C', mods, modes),
+        KeyCode::Left => cursor_key(b'D', mods, modes),
+        KeyCode::Home => cursor_key(b'H', mods, modes),
+        KeyCode::End => cursor_key(b'F', mods, modes),
+        KeyCode::Insert => tilde_key(2, mods),
+        KeyCode::Delete => tilde_key(3, mods),
+        KeyCode::PageUp => tilde_key(5, mods),
+        KeyCode::PageDown => tilde_key(6, mods),
+        KeyCode::F(n @ 1..=4) => {
+            let fin = b"PQRS"[usize::from(n - 1)];
+            match modifier_param(mods) {
+                Some(m) => format!("\x1b[1;{m}{}", fin as char).into_bytes(),
+                None => vec![0x1b, b'O', fin],
+            }
+        }
+        KeyCode::F(n @ 5..=12) => {
+            const CODES: [u8; 8] = [15, 17, 18, 19, 20, 21, 23, 24];
+            tilde_key(CODES[usize::from(n - 5)], mods)
+        }
+        _ => return None,
+    };
+    Some(bytes)
+}
+
+/// Encode pasted text, wrapping it when the program enabled bracketed paste.
+#[must_use]
+pub fn encode_paste(text: &str, modes: KeyModes) -> Vec<u8> {
+    if modes.bracketed_paste {
+        // Strip embedded end markers so pasted text cannot end the paste early
+        let clean = text.replace("\x1b[201~", "");
+        let mut out = b"\x1b[200~".to_vec();
+        out.extend_from_slice(clean.as_bytes());
+        out.extend_from_slice(b"\x1b[201~");
+        out
+    } else {
+        text.as_bytes().to_vec()
+    }
+}
+
+/// Decode raw terminal input into key presses, the reverse of [`encode_key`].
+/// Sequences without a key meaning (focus reports, unknown CSI) are dropped.
+#[must_use]
+pub fn decode_input(bytes: &[u8]) -> Vec<KeyEvent> {
+    let text = String::from_utf8_lossy(bytes);
+    let mut chars = text.chars().peekable();
+    let mut keys = Vec::new();
+    while let Some(c) = chars.next() {
+        let key = match c {
+            '\x1b' => match chars.next() {
+                None => Some(KeyEvent::from(KeyCode::Esc)),
+                Some('[') => {
+                    let mut seq = String::new();
+                    for c in chars.by_ref() {
+                        seq.push(c);
+                        if ('@'..='~').contains(&c) {
+                            break;
+                        }
+                    }
+                    decode_csi(&seq)
+                }
+                Some('O') => chars.next().and_then(|fin| decode_csi(&fin.to_string())),
+                Some(c) => {
+                    let mut key = decode_char(c);
+                    key.modifiers |= KeyModifiers::ALT;
+                    Some(key)
+                }
+            },
+            c => Some(decode_char(c)),
+        };
+        keys.extend(key);
+    }
+    keys
+}
+
+/// A single character of input: printable text or a control byte.
+fn decode_char(c: char) -> KeyEvent {
+    let ctrl = KeyModifiers::CONTROL;
+    match c {
+        '\r' | '\n' => KeyEvent::from(KeyCode::Enter),
+        '\t' => KeyEvent::from(KeyCode::Tab),
+        '\x7f' => KeyEvent::from(KeyCode::Backspace),
+        '\x08' => KeyEvent::new(KeyCode::Backspace, ctrl),
+        '\0' => KeyEvent::new(KeyCode::Char(' '), ctrl),
+        '\x01'..='\x1a' => KeyEvent::new(KeyCode::Char(char::from(c as u8 - 1 + b'a')), ctrl),
+        '\x1c'..='\x1f' => KeyEvent::new(KeyCode::Char(char::from(c as u8 + 0x40)), ctrl),
+        c if c.is_uppercase() => KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT),
+        c => KeyEvent::from(KeyCode::Char(c)),
+    }
+}
+
+/// A CSI/SS3 sequence after its introducer: parameters and final byte (`"1;5A"`).
+fn decode_csi(seq: &str) -> Option<KeyEvent> {
+    let fin = seq.chars().last()?;
+    let params = &seq[..seq.len() - fin.len_utf8()];
+    let mut nums = params.split(';').map(|p| p.parse::<u8>().ok());
+    let first = nums.next().flatten();
+    let mods = nums.next().flatten().map_or(KeyModifiers::NONE, |m| {
+        let bits = m.saturating_sub(1);
+        let mut mods = KeyModifiers::NONE;
+        if bits & 1 != 0 {
+            mods |= KeyModifiers::SHIFT;
+        }
+        if bits & 2 != 0 {
+            mods |= KeyModifiers::ALT;
+        }
+        if bits & 4 != 0 {
+            mods |= KeyModifiers::CONTROL;
+        }
+        mods
+    });
+    let code = match fin {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'Z' => return Some(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+        'P'..='S' => KeyCode::F(fin as u8 - b'P' + 1),
+        '~' => match first? {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            15 => KeyCode::F(5),
+            n @ 17..=21 => KeyCode::F(n - 11),
+            n @ 23..=24 => KeyCode::F(n - 12),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(KeyEvent::new(code, mods))
+}
+
+/// xterm modifier parameter: 1 + Shift(1) + Alt(2) + Ctrl(4); `None` when unmodified.
+fn modifier_param(mods: KeyModifiers) -> Option<u8> {
+    let mut m = 0u8;
+    if mods.contains(KeyModifiers::SHIFT) {
+        m |= 1;
+    }
+    if mods.contains(KeyModifiers::ALT) {
+        m |= 2;
+    }
+    if mods.contains(KeyModifiers::CONTROL) {
+        m |= 4;
+    }
+    (m != 0).then_some(m + 1)
+}
+
+fn cursor_key(fin: u8, mods: KeyModifiers, modes: KeyModes) -> Vec<u8> {
+    match modifier_param(mods) {
+        Some(m) => format!("\x1b[1;{m}{}", fin as char).into_bytes(),
+        None if modes.application_cursor => vec![0x1b, b'O', fin],
+        None => vec![0x1b, b'[', fin],
+    }
+}
+
+fn tilde_key(code: u8, mods: KeyModifiers) -> Vec<u8> {
+    match modifier_param(mods) {
+        Some(m) => format!("\x1b[{code};{m}~").into_bytes(),
+        None => format!("\x1b[{code}~").into_bytes(),
+    }
+}
+
+/// Control byte for Ctrl+`c`, following the usual terminal mapping.
+fn ctrl_byte(c: char) -> Option<u8> {
+    match c {
+        'a'..='z' | 'A'..='Z' => Some((c.to_ascii_lowercase() as u8) & 0x1f),
+        '@' | ' ' | '2' => Some(0),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '/' | '7' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => None,
+    }
+}
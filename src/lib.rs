@@ -4,13 +4,24 @@ pub mod overlay {
 pub mod app;
 pub mod asciinema;
 pub mod checks;
+pub mod color;
+pub mod config;
+pub mod docs;
 pub mod exec;
 pub mod frame;
+pub mod highlight;
+pub mod history;
+pub mod images;
+pub mod keys;
+pub mod links;
 pub mod markdown;
 pub mod menu;
 pub mod motd;
+pub mod notify;
+pub mod search;
 pub mod term;
 pub mod theme;
+pub mod watch;
 
 pub mod components {
     pub mod statusbar;
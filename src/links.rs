@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+/// Where a Markdown link points, relative to the document it appears in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// URL handed to the system browser (`http`, `https`, `mailto`)
+    External(String),
+    /// `#heading` within the current document
+    Anchor(String),
+    /// Another local file, optionally at a `#heading`
+    Document {
+        path: PathBuf,
+        anchor: Option<String>,
+    },
+}
+
+/// Resolve `dest` against the directory of the current document (`base` is its path).
+/// Returns `None` for missing files and schemes we do not open.
+#[must_use]
+pub fn resolve_link(dest: &str, base: Option<&Path>) -> Option<LinkTarget> {
+    let dest = dest.trim();
+    // A one-letter scheme is a Windows drive (`C:\docs`), not a URL
+    if let Some((scheme, _)) = dest.split_once(':')
+        && scheme.len() > 1
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    {
+        let scheme = scheme.to_ascii_lowercase();
+        return matches!(scheme.as_str(), "http" | "https" | "mailto")
+            .then(|| LinkTarget::External(dest.to_string()));
+    }
+    if let Some(anchor) = dest.strip_prefix('#') {
+        return Some(LinkTarget::Anchor(anchor.to_string()));
+    }
+    let (file, anchor) = match dest.split_once('#') {
+        Some((f, a)) => (f, Some(a.to_string()).filter(|a| !a.is_empty())),
+        None => (dest, None),
+    };
+    let dir = base.and_then(Path::parent).unwrap_or(Path::new(""));
+    let path = dir.join(file);
+    path.is_file()
+        .then_some(LinkTarget::Document { path, anchor })
+}
+
+/// GitHub-style heading anchor: lowercase, punctuation dropped, spaces become `-`.
+#[must_use]
+pub fn slugify(heading: &str) -> String {
+    heading
+        .trim()
+        .chars()
+        .filter_map(|c| {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                Some(c.to_lowercase().next().unwrap_or(c))
+            } else if c == ' ' {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// A place in a document, for back/forward navigation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocPos {
+    pub path: PathBuf,
+    pub scroll: u16,
+}
+
+/// Back and forward stacks of the document viewer.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocHistory {
+    pub back: Vec<DocPos>,
+    pub forward: Vec<DocPos>,
+}
+
+impl DocHistory {
+    /// Record `current` before following a link; the forward stack is dropped.
+    pub fn visit(&mut self, current: DocPos) {
+        self.back.push(current);
+        self.forward.clear();
+    }
+
+    /// Position to return to, remembering `current` for `go_forward`.
+    pub fn go_back(&mut self, current: DocPos) -> Option<DocPos> {
+        let pos = self.back.pop()?;
+        self.forward.push(current);
+        Some(pos)
+    }
+
+    /// Position to move forward to, remembering `current` for `go_back`.
+    pub fn go_forward(&mut self, current: DocPos) -> Option<DocPos> {
+        let pos = self.forward.pop()?;
+        self.back.push(current);
+        Some(pos)
+    }
+}
@@ -26,7 +26,8 @@ use anyhow::Result;
 use clap::Parser as ClapParser;
 use clap::Subcommand;
 use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent,
+    self, DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+    EnableFocusChange, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent,
 };
 use crossterm::event::{MouseEvent, MouseEventKind};
 use crossterm::execute;
@@ -39,7 +40,6 @@ use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 // no direct widgets imports needed here
 use ratatui::Terminal;
-use serde::Deserialize;
 use serde::Serialize;
 use tracing::{error, warn};
 use tracing_subscriber::EnvFilter;
@@ -47,22 +47,32 @@ use tracing_subscriber::EnvFilter;
 // use crate::markdown::markdown_to_text_with_links;
 use crate::exec::OutputMsg;
 use crate::menu::{
-    MenuState, collect_aliases, collect_unaliased_commands, find_item_by_alias, load_menu,
-    prepend_readme_item, submenu_at, validate_menu,
+    MenuState, RunPolicy, collect_aliases, collect_unaliased_commands, find_item_by_alias,
+    load_menu, prepend_readme_item, submenu_at, validate_menu,
 };
 use portable_pty::{Child as PtyChild, MasterPty, PtySize};
 use std::env;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 mod asciinema;
+mod color;
 mod config;
+mod docs;
 mod exec;
 mod frame;
+mod highlight;
+mod history;
+mod images;
+mod keys;
+mod links;
 mod markdown;
 mod menu;
 mod motd;
+mod notify;
+mod search;
 mod term;
 mod theme;
+mod watch;
 mod view;
 pub mod overlay {
     pub mod cpu;
@@ -155,6 +165,14 @@ struct Cli {
     #[arg(long, default_value_t = false)]
     disable_auto_open: bool,
 
+    /// Use colours: auto (from COLORTERM, TERM, NO_COLOR and FORCE_COLOR), always or never
+    #[arg(long, value_name = "WHEN", default_value = "auto", value_parser = ["auto", "always", "never"])]
+    color: String,
+
+    /// Override a config setting for this run, e.g. --set theme=light (repeatable)
+    #[arg(long = "set", value_name = "KEY=VALUE")]
+    set: Vec<String>,
+
     /// Subcommands: stream/record passthrough to asciinema
     #[command(subcommand)]
     cmd: Option<DxCmd>,
@@ -202,6 +220,55 @@ struct OutputView {
     scroller: Scroller,
     // Terminal emulator (optional when running PTY TUIs)
     term: Option<term::Emulator>,
+    // Active `/` search, if any
+    search: Option<search::SearchState>,
+    // Signal of the stop stage that ended a killed command
+    stopped_by: Option<&'static str>,
+    // First line of the current attempt's output (after retry banners)
+    output_from: usize,
+    // Previous stored run and this run's output, for the `c` comparison
+    compare: Option<(history::RunRecord, Vec<String>)>,
+    // Diff against the previous run while comparison mode is on
+    diff: Option<Vec<history::DiffLine>>,
+    // Language of a source file opened for viewing, and its highlighted text
+    syntax: Option<&'static syntect::parsing::SyntaxReference>,
+    highlighted: Option<ratatui::text::Text<'static>>,
+    // Rendered Markdown, reused while the document, layout and decoded images stay the same
+    md_cache: Option<MdCache>,
+    // Runnable code blocks of a Markdown document and their last results
+    runbook: Runbook,
+    // Block of the document this command was started from, to report its exit back
+    origin_block: Option<usize>,
+    // Headings and link positions of the rendered document, and the Tab link cursor
+    md_headings: Vec<markdown::MdHeading>,
+    md_link_spots: Vec<Option<markdown::LinkSpot>>,
+    link_cursor: Option<usize>,
+    // Back/forward stacks of followed links, carried from document to document
+    doc_history: links::DocHistory,
+    // Where to scroll once the document has been laid out
+    pending_anchor: Option<String>,
+    pending_scroll: Option<u16>,
+    // Selected entry of the outline panel while it is shown
+    outline: Option<usize>,
+}
+
+#[derive(Debug)]
+struct MdCache {
+    content: String,
+    layout: markdown::MdLayout,
+    image_generation: u64,
+    built: Instant,
+    render: markdown::MdRender,
+}
+
+#[derive(Debug, Default)]
+struct Runbook {
+    blocks: Vec<markdown::RunnableBlock>,
+    selected: Option<usize>,
+    // Dangerous block waiting for a second `r`
+    armed: Option<usize>,
+    // Last exit status of each block that was run, and when it finished
+    status: HashMap<usize, (Option<i32>, Instant)>,
 }
 
 impl OutputView {
@@ -227,6 +294,23 @@ impl OutputView {
             spinner_idx: 0,
             scroller: Scroller::new(),
             term: None,
+            search: None,
+            stopped_by: None,
+            output_from: 0,
+            compare: None,
+            diff: None,
+            syntax: None,
+            highlighted: None,
+            md_cache: None,
+            runbook: Runbook::default(),
+            origin_block: None,
+            md_headings: Vec::new(),
+            md_link_spots: Vec::new(),
+            link_cursor: None,
+            doc_history: links::DocHistory::default(),
+            pending_anchor: None,
+            pending_scroll: None,
+            outline: None,
         }
     }
 }
@@ -278,6 +362,14 @@ impl Scroller {
         self.scroll_y = 0;
         self.auto = false;
     }
+    // Scroll just enough to bring `line` into view, centring it when it was off-screen
+    fn reveal(&mut self, line: u16) {
+        if line < self.scroll_y || line >= self.scroll_y.saturating_add(self.viewport) {
+            self.scroll_y = line.saturating_sub(self.viewport / 2);
+            self.clamp();
+        }
+        self.auto = false;
+    }
     fn line_up(&mut self) {
         self.scroll_y = self.scroll_y.saturating_sub(1);
         self.auto = false;
@@ -312,55 +404,76 @@ enum Screen {
     Form(screens::form::FormState),
 }
 
-use crate::config::ConfigState;
+use crate::config::{
+    AppConfig, CONFIG_FILE_NAMES, ConfigSource, ConfigState, LayeredConfig, TelemetryConfig, UpdateConfig, default_build_cmd,
+    default_preserve_args,
+};
+
+/// Timeout/retry bookkeeping for the PTY command shown in the output view.
+#[derive(Debug, Clone)]
+struct RunState {
+    cmd: String,
+    enhanced: bool,
+    policy: RunPolicy,
+    // 1-based number of the attempt currently running (0 before the first spawn)
+    attempt: u32,
+    deadline: Option<Instant>,
+    // Set while waiting for the backoff delay before the next attempt
+    retry_at: Option<Instant>,
+    timed_out: bool,
+}
+
+impl RunState {
+    fn new(cmd: &str, enhanced: bool, policy: RunPolicy) -> Self {
+        Self {
+            cmd: cmd.to_string(),
+            enhanced,
+            policy,
+            attempt: 0,
+            deadline: None,
+            retry_at: None,
+            timed_out: false,
+        }
+    }
+
+    fn attempts(&self) -> u32 {
+        self.policy.retries.saturating_add(1)
+    }
+
+    fn banner(&self) -> String {
+        format!(
+            "\x1b[90m── attempt {}/{} ──\x1b[0m",
+            self.attempt,
+            self.attempts()
+        )
+    }
+}
+
+// Graceful stop in progress after a confirmed kill
+struct StopState {
+    stage: crate::exec::StopStage,
+    requested_at: Instant,
+    // When to escalate to the next stage (None after SIGKILL)
+    next_at: Option<Instant>,
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Confirm {
     KillProcess { yes_selected: bool },
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct StatusConfig {
-    text: Option<String>,
-    command: Option<String>,
+/// Resolved `[motd]` settings, kept to re-evaluate the template when MOTD.md is reloaded.
+#[derive(Debug, Clone)]
+struct MotdEval {
+    commands: HashMap<String, String>,
+    timeout: Duration,
+    refresh: Option<Duration>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct AppConfig {
-    #[serde(default)]
-    status: Option<StatusConfig>,
-    #[serde(default)]
-    allow_project_override: bool,
-    #[serde(default)]
-    motd_wrap: Option<bool>,
-    #[serde(default)]
-    motd_color: Option<String>,
-    #[serde(default)]
-    markdown_enabled: Option<bool>,
-    #[serde(default)]
-    output_dim: Option<bool>,
-    #[serde(default)]
-    theme: Option<String>, // "dark" or "light" or custom name
-    #[serde(default)]
-    theme_file: Option<String>, // path to external YAML .dx-theme
-    #[serde(default)]
-    theme_overrides: Option<HashMap<String, String>>, // token->color overrides
-    #[serde(default)]
-    theme_dir: Option<String>, // directory with *.dx-theme files
-    #[serde(default)]
-    telemetry: Option<TelemetryConfig>,
-    #[serde(default)]
-    update: Option<UpdateConfig>,
-    #[serde(default)]
-    asciinema: Option<AsciinemaConfig>,
-    #[serde(default)]
-    show_fps: Option<bool>,
-}
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct TelemetryConfig {
-    #[serde(default)]
-    enabled: bool,
-    endpoint: Option<String>,
+impl MotdEval {
+    fn spawn(&self) -> tokio::sync::mpsc::Receiver<HashMap<String, String>> {
+        motd::spawn_template_refresh(self.commands.clone(), self.timeout, self.refresh)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -370,24 +483,6 @@ struct TelemetryPayload {
     lines: Vec<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
-struct UpdateConfig {
-    #[serde(default)]
-    on_start: bool,
-    #[serde(default = "default_build_cmd")]
-    build_cmd: String,
-    relaunch_path: Option<String>,
-    #[serde(default = "default_preserve_args")]
-    preserve_args: bool,
-}
-
-fn default_build_cmd() -> String {
-    "cargo build --release".to_string()
-}
-fn default_preserve_args() -> bool {
-    true
-}
-
 use crate::asciinema::{
     AsciinemaConfig, build_asciinema_cmd, build_asciinema_stream_cmd, default_stream_mode,
     first_url_in, generate_asciinema_filename, shell_quote,
@@ -409,6 +504,20 @@ struct App {
     status_rx: Option<tokio::sync::mpsc::Receiver<String>>,
     #[allow(dead_code)]
     status_child: Option<Child>,
+    // MOTD template and the background evaluation of its values
+    motd_template: Option<Vec<String>>,
+    motd_vars_rx: Option<tokio::sync::mpsc::Receiver<HashMap<String, String>>>,
+    motd_vars: HashMap<String, String>,
+    motd_eval: MotdEval,
+    // Processes the last PTY command left running, looked up off the UI thread
+    survivors_rx: Option<(u32, tokio::sync::mpsc::Receiver<Vec<crate::exec::Survivor>>)>,
+    // Live reload: MOTD file in use, startup issues shown above it, polled files, and why
+    // the menu file was last not reloaded or what its validation reported
+    motd_path: Option<PathBuf>,
+    startup_issues: Vec<String>,
+    watcher: watch::FileWatcher,
+    reload_error: Option<String>,
+    reload_warning: Option<String>,
     // MOTD options
     motd_wrap: bool,
     motd_force_raw: bool,
@@ -417,6 +526,10 @@ struct App {
     markdown_enabled: bool,
     // Menu command buffer (for :q)
     menu_cmd: Option<String>,
+    // Query being typed for a full-text search of a docs folder
+    docs_query: Option<String>,
+    // Keys typed so far towards a menu shortcut chord (e.g. "g" of "g s")
+    menu_chord: Vec<char>,
     // Output dimming
     output_dim: bool,
     // Theme (true=dark, false=light)
@@ -424,6 +537,19 @@ struct App {
     theme_dark: bool,
     // Theme tokens for rendering
     theme: theme::ThemeTokens,
+    // Active theme name and .dx-theme file (reloaded when edited), where the picker looks
+    // for more, and inline token overrides applied on top of every theme
+    theme_name: String,
+    theme_file: Option<PathBuf>,
+    // `theme = "auto"`: follow the terminal background, using this family's files if set
+    theme_auto: bool,
+    // Background query sent when focus returned; its replies are read between frames
+    theme_query: Option<theme::BackgroundQuery>,
+    // When the background was last asked for, to ask at most once per requery interval
+    theme_queried_at: Instant,
+    theme_family: Option<String>,
+    theme_dir: Option<String>,
+    theme_overrides: Option<HashMap<String, String>>,
     // PTY integration
     pty_child: Option<Box<dyn PtyChild + Send>>, // PTY child handle
     pty_master: Option<Box<dyn MasterPty + Send>>, // for resizing
@@ -432,7 +558,9 @@ struct App {
     selection_mode: bool,
     mouse_captured: bool,
     // Start a command immediately after TUI initializes (alias support)
-    startup_cmd: Option<(String, String, bool)>, // (title, cmd, external)
+    startup_cmd: Option<(String, String, bool, RunPolicy)>, // (title, cmd, external, policy)
+    // Timeout/retry state of the running PTY command
+    run: Option<RunState>,
     // Telemetry configuration
     telemetry: Option<TelemetryConfig>,
     // Asciinema configuration
@@ -456,11 +584,42 @@ struct App {
     fps_last_instant: Instant,
     fps: f32,
     show_fps: bool,
+    // Scrollback rows for the embedded terminal emulator
+    scrollback_lines: usize,
+    // Escalation delays and state for stopping the PTY command
+    stop_grace: crate::exec::StopGrace,
+    stopping: Option<StopState>,
+    // Completion notifications for long-running commands
+    notifier: Option<notify::Notifier>,
+    // Finished runs kept per item for comparison (0 disables)
+    history_runs: usize,
+    // How document images are drawn (None: alt text only), and the images placed by the
+    // last frame / currently on screen through a graphics protocol
+    image_protocol: Option<images::ImageProtocol>,
+    // Colours the terminal can show (or --color allows); frames are downsampled to it
+    color_depth: color::ColorDepth,
+    image_overlays: Vec<images::Placement>,
+    image_overlays_shown: Vec<images::Placement>,
+    // Configuration layers read at startup, to tell which one wins for a key
+    settings: LayeredConfig,
 }
 
 const PAD_X: u16 = 2; // left/right padding inside boxes
 const PAD_Y: u16 = 1; // top/bottom padding inside boxes
 
+/// Coloured `[source]` tag shown next to config values in `dx doctor`.
+fn source_tag(source: ConfigSource) -> String {
+    let color = match source {
+        ConfigSource::Default => 90,
+        ConfigSource::Global => 36,
+        ConfigSource::Project => 32,
+        ConfigSource::DxFile => 35,
+        ConfigSource::Env => 33,
+        ConfigSource::Cli => 34,
+    };
+    format!("\x1b[{}m[{}]\x1b[0m", color, source.label())
+}
+
 fn init_tracing() {
     let filter = EnvFilter::try_from_default_env()
         .or_else(|_| EnvFilter::try_new("info"))
@@ -498,6 +657,18 @@ async fn main() {
 
 fn cli_main() -> Result<i32> {
     let cli = Cli::parse();
+    // Configuration layers, read once: defaults < ~/.dx/config.* < project config.* <
+    // the menu file's `config:` < DX_* variables < --set. Only read once the subcommand is
+    // known, so a bad --set does not stop `dx stream` or `dx record`.
+    let project_root = crate::exec::find_project_root();
+    let menu_file = cli
+        .menu
+        .clone()
+        .or_else(|| crate::menu::find_menu_file(&project_root));
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let load_settings = |sets: &[String]| {
+        LayeredConfig::load(home.as_deref(), &project_root, menu_file.as_deref(), std::env::vars(), sets)
+    };
 
     // Fast-path subcommands that bypass TUI and exec asciinema directly
     if let Some(cmd) = &cli.cmd {
@@ -551,20 +722,20 @@ fn cli_main() -> Result<i32> {
                 }
             }
             DxCmd::Doctor { full } => {
+                // A bad --set is listed with the other issues instead of stopping the checks
+                let settings = load_settings(&cli.set).unwrap_or_else(|e| {
+                    let mut settings = load_settings(&[]).unwrap_or_default();
+                    settings.issues.push(e.to_string());
+                    settings
+                });
                 println!("DX Doctor\n=========");
-                // Basic config validation in CWD
-                let _path = PathBuf::from("config.toml");
                 println!("\nConfig:");
-                // Detect multiple local configs (toml/yaml/json)
-                let local_cfg_candidates =
-                    ["config.toml", "config.yaml", "config.yml", "config.json"];
-                let mut local_found: Vec<&str> = Vec::new();
-                let project_root = crate::exec::find_project_root();
-                for c in &local_cfg_candidates {
-                    if project_root.join(c).exists() {
-                        local_found.push(c);
-                    }
-                }
+                // Detect multiple local configs (yaml/toml/json)
+                let local_found: Vec<&str> = CONFIG_FILE_NAMES
+                    .iter()
+                    .copied()
+                    .filter(|c| project_root.join(c).exists())
+                    .collect();
                 if local_found.is_empty() {
                     println!("  \x1b[90m[default]\x1b[0m local: (none)");
                 } else {
@@ -572,30 +743,23 @@ fn cli_main() -> Result<i32> {
                     for c in &local_found {
                         println!("    - {}", c);
                     }
-                    // Current loader uses only config.toml; validate it when present
-                    if project_root.join("config.toml").exists() {
-                        match fs::read_to_string(project_root.join("config.toml"))
-                            .ok()
-                            .and_then(|s| toml::from_str::<AppConfig>(&s).ok())
-                        {
-                            Some(_) => {
-                                println!("  \x1b[32m[project]\x1b[0m using: config.toml (valid)")
-                            }
-                            None => {
-                                println!(
-                                    "  \x1b[32m[project]\x1b[0m using: config.toml \x1b[31m(invalid)\x1b[0m"
-                                );
-                                return Ok(1);
-                            }
+                    // The first candidate is the one dx reads; validate it
+                    let using = local_found[0];
+                    match validate_app_config_file(&project_root.join(using)) {
+                        Some((errs, _)) if errs.is_empty() => {
+                            println!("  \x1b[32m[project]\x1b[0m using: {} (valid)", using)
+                        }
+                        _ => {
+                            println!(
+                                "  \x1b[32m[project]\x1b[0m using: {} \x1b[31m(invalid)\x1b[0m",
+                                using
+                            );
+                            return Ok(1);
                         }
-                    } else {
-                        println!(
-                            "  \x1b[33m[warn]\x1b[0m no config.toml found; other formats are currently ignored by dx runtime"
-                        );
                     }
                     if local_found.len() > 1 {
                         println!(
-                            "  \x1b[33m[warn]\x1b[0m multiple local config files detected; prefer a single config.toml to avoid confusion"
+                            "  \x1b[33m[warn]\x1b[0m multiple local config files detected; prefer a single config file to avoid confusion"
                         );
                     }
                 }
@@ -646,10 +810,8 @@ fn cli_main() -> Result<i32> {
                 let home = std::env::var("HOME").ok();
                 if let Some(h) = &home {
                     let dxdir = PathBuf::from(h).join(".dx");
-                    let global_candidates =
-                        ["config.toml", "config.yaml", "config.yml", "config.json"];
                     let mut gfound: Vec<String> = Vec::new();
-                    for g in &global_candidates {
+                    for g in &CONFIG_FILE_NAMES {
                         let p = dxdir.join(g);
                         if p.exists() {
                             gfound.push(p.to_string_lossy().to_string());
@@ -660,17 +822,31 @@ fn cli_main() -> Result<i32> {
                         for p in &gfound {
                             println!("    - {}", p);
                         }
-                        let gtoml = dxdir.join("config.toml");
-                        if gtoml.exists() {
-                            println!("  \x1b[36m[home]\x1b[0m using: {}", gtoml.to_string_lossy());
+                        if let Some(p) = find_config_file(&dxdir) {
+                            println!("  \x1b[36m[home]\x1b[0m using: {}", p.to_string_lossy());
                         }
                         if gfound.len() > 1 {
                             println!(
-                                "  \x1b[33m[warn]\x1b[0m multiple global config files detected; prefer a single ~/.dx/config.toml"
+                                "  \x1b[33m[warn]\x1b[0m multiple global config files detected; prefer a single ~/.dx/config file"
                             );
                         }
                     }
                 }
+                // Layers in the order they apply; later ones win
+                println!("\nConfig layers (lowest precedence first):");
+                println!("  {} built-in defaults", source_tag(ConfigSource::Default));
+                for layer in &settings.layers {
+                    let what = match (layer.source, &layer.path) {
+                        (ConfigSource::DxFile, Some(p)) => format!("config: section of {}", p.display()),
+                        (_, Some(p)) => p.display().to_string(),
+                        (ConfigSource::Env, None) => "DX_* environment variables".to_string(),
+                        _ => "--set flags".to_string(),
+                    };
+                    println!("  {} {}", source_tag(layer.source), what);
+                }
+                for issue in &settings.issues {
+                    println!("  \x1b[33m[warn]\x1b[0m {}", issue);
+                }
 
                 if *full {
                     // Show plugin search paths and discovered candidates
@@ -754,175 +930,91 @@ fn cli_main() -> Result<i32> {
 
                     // Effective configuration overview (grouped)
                     println!("\nEffective config (source):");
-                    let read_cfg = |p: &Path| {
-                        fs::read_to_string(p)
-                            .ok()
-                            .and_then(|s| toml::from_str::<AppConfig>(&s).ok())
-                    };
-                    let global_path = home
-                        .as_ref()
-                        .map(|h| PathBuf::from(h).join(".dx/config.toml"));
-                    let global_cfg = global_path.as_ref().and_then(|p| read_cfg(p));
-                    let local_path = PathBuf::from("config.toml");
-                    let local_cfg = read_cfg(&local_path);
-
+                    let cfg = &settings.merged;
                     let print_bool_src =
-                        |label: &str, gv: Option<bool>, lv: Option<bool>, dv: Option<bool>| {
-                            let color_src = |src: &str| -> String {
-                                match src {
-                                    "project" => "\x1b[32m[project]\x1b[0m".to_string(),
-                                    "home" => "\x1b[36m[home]\x1b[0m".to_string(),
-                                    _ => "\x1b[90m[default]\x1b[0m".to_string(),
-                                }
-                            };
-                            let (src, v) = match (lv, gv, dv) {
-                                (Some(v), _, _) => ("project", Some(v)),
-                                (None, Some(v), _) => ("home", Some(v)),
-                                (None, None, Some(v)) => ("default", Some(v)),
-                                _ => ("default", None),
-                            };
-                            let tag = color_src(src);
-                            match v {
-                                Some(b) => println!("  {} {}: {}", tag, label, b),
-                                None => println!("  {} {}: (unset)", tag, label),
+                        |label: &str, get: &dyn Fn(&AppConfig) -> Option<bool>, dv: Option<bool>| {
+                            let src = settings.source_of(|c| get(c).is_some());
+                            match get(cfg).or(dv) {
+                                Some(b) => println!("  {} {}: {}", source_tag(src), label, b),
+                                None => println!("  {} {}: (unset)", source_tag(src), label),
                             }
                         };
                     let print_str_src =
-                        |label: &str, gv: Option<String>, lv: Option<String>, dv: Option<&str>| {
-                            let color_src = |src: &str| -> String {
-                                match src {
-                                    "project" => "\x1b[32m[project]\x1b[0m".to_string(),
-                                    "home" => "\x1b[36m[home]\x1b[0m".to_string(),
-                                    _ => "\x1b[90m[default]\x1b[0m".to_string(),
-                                }
-                            };
-                            let (src, v) = match (lv, gv, dv) {
-                                (Some(v), _, _) => ("project", Some(v)),
-                                (None, Some(v), _) => ("home", Some(v)),
-                                (None, None, Some(v)) => ("default", Some(v.to_string())),
-                                _ => ("default", None),
-                            };
-                            let tag = color_src(src);
-                            match v {
-                                Some(s) => println!("  {} {}: '{}'", tag, label, s),
-                                None => println!("  {} {}: (unset)", tag, label),
+                        |label: &str, get: &dyn Fn(&AppConfig) -> Option<String>, dv: Option<&str>| {
+                            let src = settings.source_of(|c| get(c).is_some());
+                            match get(cfg).or(dv.map(str::to_string)) {
+                                Some(s) => println!("  {} {}: '{}'", source_tag(src), label, s),
+                                None => println!("  {} {}: (unset)", source_tag(src), label),
                             }
                         };
 
                     // General
                     println!("\n  General:");
-                    print_bool_src(
-                        "markdown_enabled",
-                        global_cfg.as_ref().and_then(|c| c.markdown_enabled),
-                        local_cfg.as_ref().and_then(|c| c.markdown_enabled),
-                        None,
-                    );
-                    print_bool_src(
-                        "output_dim",
-                        global_cfg.as_ref().and_then(|c| c.output_dim),
-                        local_cfg.as_ref().and_then(|c| c.output_dim),
-                        None,
-                    );
-                    print_bool_src(
-                        "show_fps",
-                        global_cfg.as_ref().and_then(|c| c.show_fps),
-                        local_cfg.as_ref().and_then(|c| c.show_fps),
-                        None,
-                    );
-                    let allow_local = local_cfg.as_ref().map(|c| c.allow_project_override);
-                    let allow_global = global_cfg.as_ref().map(|c| c.allow_project_override);
-                    print_bool_src(
-                        "allow_project_override",
-                        allow_global,
-                        allow_local,
-                        Some(true),
-                    );
+                    print_bool_src("markdown_enabled", &|c| c.markdown_enabled, None);
+                    print_bool_src("output_dim", &|c| c.output_dim, None);
+                    print_bool_src("show_fps", &|c| c.show_fps, None);
                     print_str_src(
-                        "theme",
-                        global_cfg.as_ref().and_then(|c| c.theme.clone()),
-                        local_cfg.as_ref().and_then(|c| c.theme.clone()),
-                        None,
+                        "scrollback_lines",
+                        &|c| c.scrollback_lines.map(|n| n.to_string()),
+                        Some("5000"),
                     );
+                    print_str_src("sigint_grace", &|c| c.sigint_grace.clone(), Some("2s"));
+                    print_str_src("sigterm_grace", &|c| c.sigterm_grace.clone(), Some("5s"));
+                    print_str_src("notify_after", &|c| c.notify_after.clone(), None);
+                    print_str_src("notify", &|c| c.notify.clone(), Some("auto"));
+                    print_str_src("notify_command", &|c| c.notify_command.clone(), None);
                     print_str_src(
-                        "theme_file",
-                        global_cfg.as_ref().and_then(|c| c.theme_file.clone()),
-                        local_cfg.as_ref().and_then(|c| c.theme_file.clone()),
-                        None,
-                    );
-                    print_str_src(
-                        "theme_dir",
-                        global_cfg.as_ref().and_then(|c| c.theme_dir.clone()),
-                        local_cfg.as_ref().and_then(|c| c.theme_dir.clone()),
-                        None,
-                    );
-                    print_str_src(
-                        "motd_color",
-                        global_cfg.as_ref().and_then(|c| c.motd_color.clone()),
-                        local_cfg.as_ref().and_then(|c| c.motd_color.clone()),
-                        None,
+                        "history_runs",
+                        &|c| c.history_runs.map(|n| n.to_string()),
+                        Some("10"),
                     );
-                    print_bool_src(
-                        "motd_wrap",
-                        global_cfg.as_ref().and_then(|c| c.motd_wrap),
-                        local_cfg.as_ref().and_then(|c| c.motd_wrap),
-                        None,
+                    print_str_src("images", &|c| c.images.clone(), Some("auto"));
+                    println!(
+                        "  {} allow_project_override: {}",
+                        source_tag(if settings.layer(ConfigSource::Global).is_some() {
+                            ConfigSource::Global
+                        } else {
+                            ConfigSource::Default
+                        }),
+                        cfg.allow_project_override
                     );
+                    print_str_src("theme", &|c| c.theme.clone(), None);
+                    print_str_src("theme_file", &|c| c.theme_file.clone(), None);
+                    print_str_src("theme_dir", &|c| c.theme_dir.clone(), None);
+                    print_str_src("theme_family", &|c| c.theme_family.clone(), None);
+                    print_str_src("motd_color", &|c| c.motd_color.clone(), None);
+                    print_bool_src("motd_wrap", &|c| c.motd_wrap, None);
 
                     // Telemetry
                     println!("\n  Telemetry:");
-                    let tel_g = global_cfg
-                        .as_ref()
-                        .and_then(|c| c.telemetry.as_ref())
-                        .map(|t| t.enabled);
-                    let tel_l = local_cfg
-                        .as_ref()
-                        .and_then(|c| c.telemetry.as_ref())
-                        .map(|t| t.enabled);
-                    print_bool_src("telemetry.enabled", tel_g, tel_l, Some(false));
-                    let tel_ep_g = global_cfg
-                        .as_ref()
-                        .and_then(|c| c.telemetry.as_ref())
-                        .and_then(|t| t.endpoint.clone());
-                    let tel_ep_l = local_cfg
-                        .as_ref()
-                        .and_then(|c| c.telemetry.as_ref())
-                        .and_then(|t| t.endpoint.clone());
-                    print_str_src("telemetry.endpoint", tel_ep_g, tel_ep_l, None);
+                    print_bool_src(
+                        "telemetry.enabled",
+                        &|c| c.telemetry.as_ref().map(|t| t.enabled),
+                        Some(false),
+                    );
+                    print_str_src(
+                        "telemetry.endpoint",
+                        &|c| c.telemetry.as_ref().and_then(|t| t.endpoint.clone()),
+                        None,
+                    );
 
                     // asciinema.* with defaults
-                    let asc_g_ref = global_cfg.as_ref().and_then(|c| c.asciinema.as_ref());
-                    let asc_l_ref = local_cfg.as_ref().and_then(|c| c.asciinema.as_ref());
-                    let asc_src = if asc_l_ref.is_some() {
-                        "project"
-                    } else if asc_g_ref.is_some() {
-                        "home"
-                    } else {
-                        "default"
-                    };
-                    let asc =
-                        asc_l_ref
-                            .cloned()
-                            .or(asc_g_ref.cloned())
-                            .unwrap_or(AsciinemaConfig {
-                                enabled: false,
-                                external: false,
-                                on_relaunch: false,
-                                dir: None,
-                                file_prefix: None,
-                                title: None,
-                                quiet: false,
-                                overwrite: false,
-                                stream: false,
-                                stream_mode: default_stream_mode(),
-                                local_addr: None,
-                                remote: None,
-                            });
+                    let asc_tag = source_tag(settings.source_of(|c| c.asciinema.is_some()));
+                    let asc = cfg.asciinema.clone().unwrap_or(AsciinemaConfig {
+                        enabled: false,
+                        external: false,
+                        on_relaunch: false,
+                        dir: None,
+                        file_prefix: None,
+                        title: None,
+                        quiet: false,
+                        overwrite: false,
+                        stream: false,
+                        stream_mode: default_stream_mode(),
+                        local_addr: None,
+                        remote: None,
+                    });
                     println!("\n  Asciinema:");
-                    let asc_tag = match asc_src {
-                        "project" => "\x1b[32m[project]\x1b[0m",
-                        "home" => "\x1b[36m[home]\x1b[0m",
-                        _ => "\x1b[90m[default]\x1b[0m",
-                    };
                     println!("    {} enabled: {}", asc_tag, asc.enabled);
                     println!("    {} external: {}", asc_tag, asc.external);
                     println!("    {} on_relaunch: {}", asc_tag, asc.on_relaunch);
@@ -941,29 +1033,14 @@ fn cli_main() -> Result<i32> {
                     pstr("remote", asc.remote.as_deref());
 
                     // update.* with defaults
-                    let upd_g = global_cfg.as_ref().and_then(|c| c.update.as_ref());
-                    let upd_l = local_cfg.as_ref().and_then(|c| c.update.as_ref());
-                    let (upd_src, upd) = if let Some(u) = upd_l {
-                        ("project", u)
-                    } else if let Some(u) = upd_g {
-                        ("home", u)
-                    } else {
-                        (
-                            "default",
-                            &UpdateConfig {
-                                on_start: false,
-                                build_cmd: default_build_cmd(),
-                                relaunch_path: None,
-                                preserve_args: default_preserve_args(),
-                            },
-                        )
-                    };
+                    let upd_tag = source_tag(settings.source_of(|c| c.update.is_some()));
+                    let upd = cfg.update.clone().unwrap_or(UpdateConfig {
+                        on_start: false,
+                        build_cmd: default_build_cmd(),
+                        relaunch_path: None,
+                        preserve_args: default_preserve_args(),
+                    });
                     println!("\n  Update:");
-                    let upd_tag = match upd_src {
-                        "project" => "\x1b[32m[project]\x1b[0m",
-                        "home" => "\x1b[36m[home]\x1b[0m",
-                        _ => "\x1b[90m[default]\x1b[0m",
-                    };
                     println!("    {} on_start: {}", upd_tag, upd.on_start);
                     println!("    {} build_cmd: '{}'", upd_tag, upd.build_cmd);
                     println!("    {} preserve_args: {}", upd_tag, upd.preserve_args);
@@ -974,52 +1051,20 @@ fn cli_main() -> Result<i32> {
 
                     // Status
                     println!("\n  Status:");
-                    let st_txt_l = local_cfg
-                        .as_ref()
-                        .and_then(|c| c.status.as_ref())
-                        .and_then(|s| s.text.clone());
-                    let st_txt_g = global_cfg
-                        .as_ref()
-                        .and_then(|c| c.status.as_ref())
-                        .and_then(|s| s.text.clone());
-                    let (src, val) = if let Some(v) = st_txt_l {
-                        ("project", Some(v))
-                    } else if let Some(v) = st_txt_g {
-                        ("home", Some(v))
-                    } else {
-                        ("default", None)
-                    };
-                    let st_tag = match src {
-                        "project" => "\x1b[32m[project]\x1b[0m",
-                        "home" => "\x1b[36m[home]\x1b[0m",
-                        _ => "\x1b[90m[default]\x1b[0m",
-                    };
-                    match val {
-                        Some(s) => println!("    {} text: '{}'", st_tag, s),
-                        None => println!("    {} text: (unset)", st_tag),
-                    }
+                    print_str_src(
+                        "text",
+                        &|c| c.status.as_ref().and_then(|s| s.text.clone()),
+                        None,
+                    );
                 }
                 return Ok(0);
             }
         }
     }
+    let settings = load_settings(&cli.set)?;
     // Lightweight: auto-relaunch under asciinema stream on start when --live or config requests it
     if !cli.console && std::env::var("DX_RELAUNCHED").ok().as_deref() != Some("1") {
-        // minimal config read to detect asciinema preferences
-        fn read_app_config(path: &Path) -> Option<AppConfig> {
-            fs::read_to_string(path)
-                .ok()
-                .and_then(|s| toml::from_str::<AppConfig>(&s).ok())
-        }
-        let home = std::env::var("HOME").ok();
-        let global_cfg = home
-            .map(|h| PathBuf::from(h).join(".dx").join("config.toml"))
-            .and_then(|p| read_app_config(&p));
-        let local_cfg = read_app_config(Path::new("config.toml"));
-        let asciinema_cfg = local_cfg
-            .as_ref()
-            .and_then(|c| c.asciinema.clone())
-            .or_else(|| global_cfg.as_ref().and_then(|c| c.asciinema.clone()));
+        let asciinema_cfg = settings.merged.asciinema.clone();
         let want_stream = if let Some(ac) = &asciinema_cfg {
             ac.enabled && ac.stream
         } else {
@@ -1156,7 +1201,7 @@ fn cli_main() -> Result<i32> {
     // Optionally load a menu upfront to resolve aliases or to show the menu
     let mut initial_menu: Option<MenuState> = None;
     let mut menu_path: Option<PathBuf> = None;
-    let mut startup_cmd: Option<(String, String, bool)> = None;
+    let mut startup_cmd: Option<(String, String, bool, RunPolicy)> = None;
     if let Some(p) = cli.menu.as_deref() {
         let mut m = load_menu(p)?;
         prepend_readme_item(&mut m);
@@ -1165,34 +1210,7 @@ fn cli_main() -> Result<i32> {
         menu_path = Some(p.to_path_buf());
     } else {
         // Try common menu filenames in order (prefer YAML, then TOML, then JSON; dx.* before menu.*)
-        let candidates = [
-            "dx.yaml",
-            "dx.yml",
-            "dx.toml",
-            "dx.json",
-            "DX.yaml",
-            "DX.yml",
-            "DX.toml",
-            "DX.json",
-            "menu.yaml",
-            "menu.yml",
-            "menu.toml",
-            "menu.json",
-            "Menu.yaml",
-            "Menu.yml",
-            "Menu.toml",
-            "Menu.json",
-        ];
-        let mut found: Option<PathBuf> = None;
-        let project_root = crate::exec::find_project_root();
-        for c in candidates.iter() {
-            let p = project_root.join(c);
-            if p.exists() {
-                found = Some(p);
-                break;
-            }
-        }
-        if let Some(p) = found {
+        if let Some(p) = menu_file.clone() {
             let mut m = load_menu(&p)?;
             prepend_readme_item(&mut m);
             crate::menu::append_dx_menu(&mut m);
@@ -1222,20 +1240,11 @@ fn cli_main() -> Result<i32> {
                                 cmd.clone()
                             };
 
-                            // Execute command directly; optionally wrap with asciinema record
+                            // Execute command directly (honouring timeout/retries); optionally wrap with asciinema record
+                            let policy = item.run_policy();
                             let (status, record_path) = if cli.record {
-                                // Try to read recording preferences from config for dir/prefix/quiet/title
-                                let home = std::env::var("HOME").ok();
-                                let global_cfg = home
-                                    .map(|h| PathBuf::from(h).join(".dx").join("config.toml"))
-                                    .and_then(|p| read_app_config_file(&p));
-                                let local_cfg = read_app_config_file(Path::new("config.toml"));
-                                let acfg = local_cfg
-                                    .as_ref()
-                                    .and_then(|c| c.asciinema.clone())
-                                    .or_else(|| {
-                                        global_cfg.as_ref().and_then(|c| c.asciinema.clone())
-                                    });
+                                // Recording preferences from config for dir/prefix/quiet/title
+                                let acfg = settings.merged.asciinema.clone();
 
                                 let dir = acfg
                                     .as_ref()
@@ -1270,26 +1279,32 @@ fn cli_main() -> Result<i32> {
                                 let joined: Vec<String> =
                                     parts.into_iter().map(|p| shell_quote(&p)).collect();
                                 let cmdline = joined.join(" ");
-                                let mut c = Command::new("sh");
-                                c.arg("-lc").arg(cmdline);
-                                c.current_dir(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")));
-                                c.env("DX_ASCIINEMA", "record");
-                                c.env("DX_ASC_FILE", file.clone());
-                                (c.status(), Some(file))
+                                let make_cmd = || {
+                                    let mut c = Command::new("sh");
+                                    c.arg("-lc").arg(&cmdline);
+                                    c.current_dir(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")));
+                                    c.env("DX_ASCIINEMA", "record");
+                                    c.env("DX_ASC_FILE", file.clone());
+                                    c
+                                };
+                                (crate::exec::run_blocking_with_policy(make_cmd, &policy), Some(file.clone()))
                             } else {
                                 // Execute command attached to current TTY; inherit stdio
-                                (Command::new("sh")
-                    .arg("-lc")
-                    .arg(&full_cmd)
-                    .current_dir(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")))
-                    .status(), None)
+                                let make_cmd = || {
+                                    let mut c = Command::new("sh");
+                                    c.arg("-lc")
+                                        .arg(&full_cmd)
+                                        .current_dir(std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")));
+                                    c
+                                };
+                                (crate::exec::run_blocking_with_policy(make_cmd, &policy), None)
                             };
                             match status {
-                                Ok(st) => {
+                                Ok(code) => {
                                     if let Some(path) = record_path {
                                         println!("\n\x1b[92mRecording saved:\x1b[0m {}", path);
                                     }
-                                    return Ok(st.code().unwrap_or(0));
+                                    return Ok(code);
                                 }
                                 Err(e) => {
                                     error!(target: "dx", alias = %t, error = %e, "failed to run alias");
@@ -1478,11 +1493,16 @@ fn cli_main() -> Result<i32> {
                     };
 
                     // Defer command start to app loop
-                    startup_cmd = Some((item.name.clone(), full_cmd.clone(), external));
+                    startup_cmd = Some((
+                        item.name.clone(),
+                        full_cmd.clone(),
+                        external,
+                        item.run_policy(),
+                    ));
                     let view = OutputView::new(format!("{}: {}", item.name, full_cmd));
                     (Screen::Output(view), menu_path)
                 } else if item.name == "Configuration" || item.alias.as_deref() == Some("config") {
-                    (Screen::Config(open_config_state()), menu_path)
+                    (Screen::Config(open_config_state(&settings)), menu_path)
                 } else {
                     (Screen::Menu(initial_menu.take().unwrap()), menu_path)
                 }
@@ -1517,39 +1537,20 @@ fn cli_main() -> Result<i32> {
     };
 
     // Load MOTD.md: prefer local .dx/, then global ~/.dx/, then local, then ancestors
-    let (mut motd_lines, motd_force_raw) = {
-        // First check local .dx/MOTD.md (project-specific)
-        let local_dx_motd = PathBuf::from(".dx").join("MOTD.md");
-        if let Some((lines, raw)) = motd::read_motd_file(&local_dx_motd) {
-            (lines, raw)
-        } else {
-            // Then check global ~/.dx/MOTD.md
-            let global_motd = std::env::var("HOME")
-                .ok()
-                .map(|h| PathBuf::from(h).join(".dx").join("MOTD.md"));
-            
-            if let Some(global_path) = &global_motd {
-                if let Some((lines, raw)) = motd::read_motd_file(global_path) {
-                    (lines, raw)
-                } else if let Some((lines, raw)) = motd::read_motd_file(Path::new("MOTD.md")) {
-                    (lines, raw)
-                } else if let Some(p) = motd::find_motd_in_ancestors() {
-                    motd::read_motd_file(&p).unwrap_or((Vec::new(), false))
-                } else {
-                    (Vec::new(), false)
-                }
-            } else {
-                // Fallback if HOME not available
-                if let Some((lines, raw)) = motd::read_motd_file(Path::new("MOTD.md")) {
-                    (lines, raw)
-                } else if let Some(p) = motd::find_motd_in_ancestors() {
-                    motd::read_motd_file(&p).unwrap_or((Vec::new(), false))
-                } else {
-                    (Vec::new(), false)
-                }
-            }
-        }
+    let motd_path: Option<PathBuf> = {
+        let global_motd = std::env::var("HOME")
+            .ok()
+            .map(|h| PathBuf::from(h).join(".dx").join("MOTD.md"));
+        [Some(PathBuf::from(".dx").join("MOTD.md")), global_motd, Some(PathBuf::from("MOTD.md"))]
+            .into_iter()
+            .flatten()
+            .find(|p| p.is_file())
+            .or_else(motd::find_motd_in_ancestors)
     };
+    let (mut motd_lines, motd_force_raw) = motd_path
+        .as_deref()
+        .and_then(motd::read_motd_file)
+        .unwrap_or((Vec::new(), false));
 
     // Validate configuration files early and collect warnings/errors (non-silent)
     let mut startup_issues: Vec<String> = Vec::new();
@@ -1563,43 +1564,41 @@ fn cli_main() -> Result<i32> {
             }
         }
     }
-    // 2) Validate app config structure (global/local)
-    if let Some(path) = std::env::var("HOME")
-        .ok()
-        .map(|h| PathBuf::from(h).join(".dx").join("config.toml"))
-    {
-        if let Some((errs, warns)) = validate_app_config_file(&path) {
-            if !errs.is_empty() {
-                startup_issues.push(format!("Config {} errors:", path.display()));
-                for e in errs {
-                    startup_issues.push(format!("  - {}", e));
-                }
+    // 2) Validate app config structure (global, project and the menu file's `config:`)
+    let mut report_config = |label: String, (errs, warns): (Vec<String>, Vec<String>)| {
+        if !errs.is_empty() {
+            startup_issues.push(format!("Config {} errors:", label));
+            for e in errs {
+                startup_issues.push(format!("  - {}", e));
             }
-            if !warns.is_empty() {
-                startup_issues.push(format!("Config {} warnings:", path.display()));
-                for w in warns {
-                    startup_issues.push(format!("  - {}", w));
-                }
+        }
+        if !warns.is_empty() {
+            startup_issues.push(format!("Config {} warnings:", label));
+            for w in warns {
+                startup_issues.push(format!("  - {}", w));
             }
         }
+    };
+    let config_files = [
+        std::env::var_os("HOME").and_then(|h| find_config_file(&PathBuf::from(h).join(".dx"))),
+        find_config_file(&project_root),
+    ];
+    for path in config_files.into_iter().flatten() {
+        if let Some(result) = validate_app_config_file(&path) {
+            report_config(path.display().to_string(), result);
+        }
     }
-    {
-        let path = PathBuf::from("config.toml");
-        if path.exists() {
-            if let Some((errs, warns)) = validate_app_config_file(&path) {
-                if !errs.is_empty() {
-                    startup_issues.push(format!("Config {} errors:", path.display()));
-                    for e in errs {
-                        startup_issues.push(format!("  - {}", e));
-                    }
-                }
-                if !warns.is_empty() {
-                    startup_issues.push(format!("Config {} warnings:", path.display()));
-                    for w in warns {
-                        startup_issues.push(format!("  - {}", w));
-                    }
-                }
-            }
+    if let Some(layer) = settings.layer(ConfigSource::DxFile) {
+        let label = layer
+            .path
+            .as_ref()
+            .map_or_else(String::new, |p| format!("{} (config:)", p.display()));
+        report_config(label, validate_app_config(&layer.cfg));
+    }
+    if !settings.issues.is_empty() {
+        startup_issues.push("Ignored settings:".to_string());
+        for issue in &settings.issues {
+            startup_issues.push(format!("  - {}", issue));
         }
     }
     // If issues exist, print to stderr and prepend to MOTD so user sees them in UI
@@ -1623,9 +1622,12 @@ fn cli_main() -> Result<i32> {
     let mut markdown_enabled_cfg = true;
     let mut output_dim_cfg = true;
     let mut theme_dark_cfg = true; // default assume dark terminals
-    let mut telemetry_cfg: Option<TelemetryConfig> = None;
-    let mut asciinema_cfg: Option<AsciinemaConfig> = None;
     let mut show_fps_cfg: bool = true;
+    let mut scrollback_lines_cfg = term::DEFAULT_SCROLLBACK;
+    let mut stop_grace_cfg = crate::exec::StopGrace::default();
+    let mut notify_after_cfg: Option<Duration> = None;
+    let mut notify_method_cfg: Option<notify::NotifyMethod> = None;
+    let mut history_runs_cfg = history::DEFAULT_KEEP;
     // Read environment to detect if we're running under asciinema (relaunch case)
     let asciinema_badge_env: Option<String> = {
         let mode = std::env::var("DX_ASCIINEMA").ok();
@@ -1656,118 +1658,60 @@ fn cli_main() -> Result<i32> {
         }
     };
 
-    fn read_app_config(path: &Path) -> Option<AppConfig> {
-        fs::read_to_string(path)
-            .ok()
-            .and_then(|s| toml::from_str::<AppConfig>(&s).ok())
+    let cfg = &settings.merged;
+    if let Some(b) = cfg.motd_wrap {
+        motd_wrap_cfg = b;
     }
-
-    let home = std::env::var("HOME").ok();
-    let global_cfg = home
-        .map(|h| PathBuf::from(h).join(".dx").join("config.toml"))
-        .and_then(|p| read_app_config(&p));
-    let local_cfg = read_app_config(Path::new("config.toml"));
-
-    if let Some(g) = &global_cfg {
-        if let Some(b) = g.motd_wrap {
-            motd_wrap_cfg = b;
-        }
+    if let Some(c) = &cfg.motd_color {
+        motd_color_cfg = parse_color(c);
     }
-    if let Some(l) = &local_cfg {
-        if let Some(b) = l.motd_wrap {
-            motd_wrap_cfg = b;
-        }
+    if let Some(b) = cfg.markdown_enabled {
+        markdown_enabled_cfg = b;
     }
-    if let Some(g) = &global_cfg {
-        if let Some(c) = &g.motd_color {
-            motd_color_cfg = parse_color(c);
-        }
-    }
-    if let Some(l) = &local_cfg {
-        if let Some(c) = &l.motd_color {
-            motd_color_cfg = parse_color(c);
-        }
-    }
-    if let Some(g) = &global_cfg {
-        if let Some(b) = g.markdown_enabled {
-            markdown_enabled_cfg = b;
-        }
-    }
-    if let Some(l) = &local_cfg {
-        if let Some(b) = l.markdown_enabled {
-            markdown_enabled_cfg = b;
-        }
-    }
-    if let Some(g) = &global_cfg {
-        if let Some(b) = g.output_dim {
-            output_dim_cfg = b;
-        }
+    if let Some(b) = cfg.output_dim {
+        output_dim_cfg = b;
     }
-    if let Some(l) = &local_cfg {
-        if let Some(b) = l.output_dim {
-            output_dim_cfg = b;
-        }
+    if let Some(t) = &cfg.theme {
+        theme_dark_cfg = t.eq_ignore_ascii_case("dark");
     }
-    if let Some(g) = &global_cfg {
-        if let Some(t) = &g.theme {
-            theme_dark_cfg = t.eq_ignore_ascii_case("dark");
-        }
+    let mut theme_name_cfg = cfg.theme.clone();
+    let theme_file_cfg = cfg.theme_file.clone();
+    let theme_dir_cfg = cfg.theme_dir.clone();
+    let theme_family_cfg = cfg.theme_family.clone();
+    let theme_overrides_cfg = cfg.theme_overrides.clone();
+    let telemetry_cfg = cfg.telemetry.clone();
+    let asciinema_cfg = cfg.asciinema.clone();
+    if let Some(b) = cfg.show_fps {
+        show_fps_cfg = b;
     }
-    if let Some(l) = &local_cfg {
-        if let Some(t) = &l.theme {
-            theme_dark_cfg = t.eq_ignore_ascii_case("dark");
-        }
+    if let Some(n) = cfg.scrollback_lines {
+        scrollback_lines_cfg = n;
     }
-    // Telemetry: project overrides global entirely if present
-    if let Some(g) = &global_cfg {
-        if let Some(t) = &g.telemetry {
-            telemetry_cfg = Some(t.clone());
-        }
+    if let Some(d) = cfg.sigint_grace.as_deref().and_then(crate::menu::parse_duration) {
+        stop_grace_cfg.interrupt = d;
     }
-    if let Some(l) = &local_cfg {
-        if let Some(t) = &l.telemetry {
-            telemetry_cfg = Some(t.clone());
-        }
+    if let Some(d) = cfg.sigterm_grace.as_deref().and_then(crate::menu::parse_duration) {
+        stop_grace_cfg.terminate = d;
     }
-    if let Some(g) = &global_cfg {
-        if let Some(a) = &g.asciinema {
-            asciinema_cfg = Some(a.clone());
-        }
+    if let Some(d) = cfg.notify_after.as_deref().and_then(crate::menu::parse_duration) {
+        notify_after_cfg = Some(d);
     }
-    if let Some(l) = &local_cfg {
-        if let Some(a) = &l.asciinema {
-            asciinema_cfg = Some(a.clone());
+    if let Some(m) = cfg.notify.as_deref() {
+        match notify::NotifyMethod::parse(m) {
+            Some(method) => notify_method_cfg = Some(method),
+            None => warn!("unknown notify method '{}'", m),
         }
     }
-    if let Some(g) = &global_cfg {
-        if let Some(b) = g.show_fps {
-            show_fps_cfg = b;
-        }
+    if let Some(c) = cfg.notify_command.as_ref().filter(|c| !c.trim().is_empty()) {
+        notify_method_cfg = Some(notify::NotifyMethod::Command(c.clone()));
     }
-    if let Some(l) = &local_cfg {
-        if let Some(b) = l.show_fps {
-            show_fps_cfg = b;
-        }
+    if let Some(n) = cfg.history_runs {
+        history_runs_cfg = n;
     }
+    let images_cfg = cfg.images.clone();
 
-    // Decide status source
-    let allow_override = global_cfg
-        .as_ref()
-        .map(|c| c.allow_project_override)
-        .unwrap_or(true);
-
-    let chosen_status = if allow_override {
-        // Local status if present, else global
-        local_cfg
-            .as_ref()
-            .and_then(|c| c.status.as_ref())
-            .or_else(|| global_cfg.as_ref().and_then(|c| c.status.as_ref()))
-    } else {
-        // Only global allowed
-        global_cfg.as_ref().and_then(|c| c.status.as_ref())
-    };
-
-    if let Some(status) = chosen_status {
+    // Status and MOTD commands come from the project only with allow_project_override
+    if let Some(status) = &cfg.status {
         if let Some(t) = &status.text {
             status_text = Some(t.clone());
         }
@@ -1778,20 +1722,67 @@ fn cli_main() -> Result<i32> {
         }
     }
 
+    // Templated MOTD: show placeholders now, fill in values as they are evaluated
+    let motd_cfg = cfg.motd.clone().unwrap_or_default();
+    let motd_eval = MotdEval {
+        commands: motd_cfg.vars,
+        timeout: motd_cfg
+            .timeout
+            .as_deref()
+            .and_then(crate::menu::parse_duration)
+            .unwrap_or(Duration::from_secs(2)),
+        refresh: motd_cfg
+            .refresh
+            .as_deref()
+            .and_then(crate::menu::parse_duration),
+    };
+    let mut motd_template: Option<Vec<String>> = None;
+    let mut motd_vars_rx = None;
+    let motd_names = motd::template_names(&motd_eval.commands);
+    if motd::is_template(&motd_lines, &motd_names) {
+        motd_vars_rx = Some(motd_eval.spawn());
+        motd_template = Some(std::mem::take(&mut motd_lines));
+        motd_lines = motd::render_template(
+            motd_template.as_deref().unwrap_or_default(),
+            &motd_names,
+            &HashMap::new(),
+        );
+    }
+
+    let color_depth = color::resolve(color::ColorChoice::parse(&cli.color).unwrap_or_default());
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
     // legacy CPU overlay removed in favor of plugin runtime
-    // Determine theme tokens from config
-    let theme_tokens = if theme_dark_cfg {
-        theme::ThemeTokens::builtin_dark()
+    // Determine theme tokens from config; "auto" asks the terminal for its background
+    let theme_auto = theme_name_cfg
+        .as_deref()
+        .is_some_and(|t| t.eq_ignore_ascii_case("auto"));
+    let theme_file = if theme_auto {
+        theme_dark_cfg = theme::detect_dark_background(theme::BACKGROUND_QUERY_TIMEOUT).unwrap_or(true);
+        let (name, file) =
+            theme::auto_theme(theme_dark_cfg, theme_family_cfg.as_deref(), theme_dir_cfg.as_deref());
+        theme_name_cfg = Some(name);
+        // Focus returns after the system appearance is switched, so check again then
+        execute!(terminal.backend_mut(), EnableFocusChange)?;
+        file
     } else {
-        theme::ThemeTokens::builtin_light()
+        theme::resolve_theme_file(
+            theme_name_cfg.as_deref(),
+            theme_file_cfg.as_deref(),
+            theme_dir_cfg.as_deref(),
+        )
     };
+    let theme_tokens = theme::load_theme_from(
+        theme_name_cfg.as_deref(),
+        theme_file.as_deref().and_then(Path::to_str),
+        theme_overrides_cfg.as_ref(),
+    );
     // Try dynamic plugin load from default path (if exists). Ignored on failure.
     let mut plugin_overlays: Vec<plugin::OverlayRuntime> = Vec::new();
     let env_path = std::env::var("DX_PLUGIN_CPU").ok();
@@ -1873,6 +1864,16 @@ fn cli_main() -> Result<i32> {
             confirm: None,
             needs_clear: true,
             motd_lines,
+            motd_template,
+            motd_vars_rx,
+            motd_vars: HashMap::new(),
+            motd_eval,
+            survivors_rx: None,
+            motd_path,
+            startup_issues,
+            watcher: watch::FileWatcher::default(),
+            reload_error: None,
+            reload_warning: None,
             last_content_area: None,
             status_text,
             status_rx,
@@ -1882,15 +1883,26 @@ fn cli_main() -> Result<i32> {
             motd_color: motd_color_cfg,
             markdown_enabled: markdown_enabled_cfg,
             menu_cmd: None,
+            docs_query: None,
+            menu_chord: Vec::new(),
             output_dim: output_dim_cfg,
             theme_dark: theme_dark_cfg,
             theme: theme_tokens,
+            theme_name: theme_name_cfg.unwrap_or_else(|| "dark".to_string()),
+            theme_file,
+            theme_auto,
+            theme_query: None,
+            theme_queried_at: Instant::now(),
+            theme_family: theme_family_cfg,
+            theme_dir: theme_dir_cfg,
+            theme_overrides: theme_overrides_cfg,
             pty_child: None,
             pty_master: None,
             pty_writer: None,
             selection_mode: false,
             mouse_captured: true,
             startup_cmd,
+            run: None,
             telemetry: telemetry_cfg,
             asciinema: asciinema_cfg,
             asciinema_live: cli.live,
@@ -1905,12 +1917,33 @@ fn cli_main() -> Result<i32> {
             fps_last_instant: Instant::now(),
             fps: 0.0,
             show_fps: show_fps_cfg,
+            scrollback_lines: scrollback_lines_cfg,
+            stop_grace: stop_grace_cfg,
+            stopping: None,
+            notifier: notify_after_cfg.map(|after| notify::Notifier {
+                after,
+                method: notify_method_cfg.unwrap_or_else(notify::NotifyMethod::detect),
+            }),
+            history_runs: history_runs_cfg,
+            // Without colour, images fall back to their alt text
+            image_protocol: match images::ImageProtocol::parse(images_cfg.as_deref().unwrap_or("auto")) {
+                _ if color_depth == color::ColorDepth::Mono => None,
+                Ok(p) => p,
+                Err(v) => {
+                    warn!("unknown images setting '{}', using auto", v);
+                    Some(images::ImageProtocol::detect())
+                }
+            },
+            color_depth,
+            image_overlays: Vec::new(),
+            image_overlays_shown: Vec::new(),
+            settings,
         },
     );
 
     disable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture)?;
+    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, DisableFocusChange)?;
     terminal.show_cursor()?;
 
     match result {
@@ -1925,11 +1958,31 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
 
     loop {
         // Start any deferred alias command once at the beginning
-        if let Some((title, cmd, external)) = app.startup_cmd.take() {
+        if let Some((title, cmd, external, policy)) = app.startup_cmd.take() {
             if external {
                 let _ = passthrough_command(&mut app, &title, &cmd);
             } else {
-                let _ = start_command(&mut app, &title, &cmd);
+                let _ = start_command(&mut app, &title, &cmd, policy);
+            }
+        }
+        // Start the next attempt once its backoff delay has elapsed
+        if app
+            .run
+            .as_ref()
+            .and_then(|r| r.retry_at)
+            .is_some_and(|t| Instant::now() >= t)
+        {
+            if let Err(e) = spawn_attempt(&mut app) {
+                app.run = None;
+                if let Screen::Output(view) = &mut app.screen {
+                    view.running = false;
+                    view.ended_at = Some(Instant::now());
+                    view.lines
+                        .push(format!("\x1b[31m[✖] Retry failed to start: {}\x1b[0m", e));
+                }
+            } else if let (Some(run), Screen::Output(view)) = (&app.run, &mut app.screen) {
+                view.lines.push(run.banner());
+                view.output_from = view.lines.len();
             }
         }
         // Drain any incoming output lines
@@ -1971,7 +2024,11 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                                     } else {
                                         (24, 80)
                                     };
-                                    view.term = Some(term::Emulator::new(rows, cols));
+                                    view.term = Some(term::Emulator::with_scrollback(
+                                        rows,
+                                        cols,
+                                        app.scrollback_lines,
+                                    ));
                                 } else {
                                     // No TUI sequence detected; let line-based path handle output
                                     continue;
@@ -1991,6 +2048,58 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                 app.status_text = Some(line);
             }
         }
+        // Warn about processes the finished command left behind
+        if let Some((pgid, survivors_rx)) = &mut app.survivors_rx
+            && let Ok(survivors) = survivors_rx.try_recv()
+        {
+            let pgid = *pgid;
+            app.survivors_rx = None;
+            if let Screen::Output(view) = &mut app.screen
+                && !view.running
+            {
+                report_survivors(view, pgid, &survivors);
+            }
+        }
+        // Fill the MOTD template with freshly evaluated values
+        if let (Some(vrx), Some(template)) = (&mut app.motd_vars_rx, &app.motd_template) {
+            while let Ok(vars) = vrx.try_recv() {
+                let names = motd::template_names(&app.motd_eval.commands);
+                app.motd_lines = motd::render_template(template, &names, &vars);
+                app.motd_vars = vars;
+            }
+        }
+        // Live reload of the viewed file, MOTD and menu when they change on disk
+        let viewed = match &app.screen {
+            Screen::Output(view) if view.term.is_none() && !view.running => view.file_path.clone(),
+            _ => None,
+        };
+        let watched: Vec<&Path> = [
+            viewed.as_deref(),
+            app.motd_path.as_deref(),
+            app.menu_path.as_deref(),
+            app.theme_file.as_deref(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        app.watcher.set_watched(&watched);
+        for path in app.watcher.poll() {
+            if viewed.as_deref() == Some(path.as_path())
+                && let Screen::Output(view) = &mut app.screen
+            {
+                reload_file_view(view);
+            }
+            if app.motd_path.as_deref() == Some(path.as_path()) {
+                reload_motd(&mut app);
+            }
+            if app.menu_path.as_deref() == Some(path.as_path()) {
+                reload_menu(&mut app);
+            }
+            if app.theme_file.as_deref() == Some(path.as_path()) {
+                let tokens = load_theme_choice(&app, &app.theme_name, app.theme_file.as_deref());
+                apply_theme(&mut app, tokens);
+            }
+        }
 
         // Check if running child has exited
         if let Some(child) = &mut app.child {
@@ -1999,6 +2108,7 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                     view.running = false;
                     view.exit_status = status.code();
                     view.ended_at = Some(Instant::now());
+                    notify_finished(app.notifier.as_ref(), view);
                     // Append colored completion line
                     if let Some(p) = view.pending_line.take() {
                         view.lines.push(p);
@@ -2039,41 +2149,124 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                 app.child_stdin = None;
             }
         }
+        // Enforce per-item timeout: kill the attempt and let exit handling decide on a retry
+        if let (Some(run), Some(child)) = (app.run.as_mut(), app.pty_child.as_mut())
+            && !run.timed_out
+            && run.deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            run.timed_out = true;
+            crate::exec::pty_signal(child, crate::exec::StopStage::Kill);
+        }
+        // Escalate a confirmed kill the process group has not honoured within its grace period
+        if app
+            .stopping
+            .as_ref()
+            .and_then(|s| s.next_at)
+            .is_some_and(|t| Instant::now() >= t)
+        {
+            escalate_stop(&mut app);
+        }
         // Check PTY child
         if let Some(child) = app.pty_child.as_mut() {
-            if child.try_wait()?.is_some() {
+            let leader = child.process_id();
+            if let Some(exit) = crate::exec::pty_try_wait(child)? {
+                let stopped = app.stopping.take();
                 if let Screen::Output(view) = &mut app.screen {
-                    view.running = false;
-                    // Parse exit code marker from last lines if present
-                    let mut exit_code: Option<i32> = None;
-                    for l in view.lines.iter().rev().take(8) {
-                        if let Some(rest) = l.strip_prefix("__DX_EXIT_CODE:") {
-                            if let Ok(n) = rest.trim().parse::<i32>() {
-                                exit_code = Some(n);
-                                break;
-                            }
-                        }
-                    }
-                    // Hide internal marker lines from the final output
-                    view.lines.retain(|l| !l.starts_with("__DX_EXIT_CODE:"));
-                    view.exit_status = exit_code;
-                    view.ended_at = Some(Instant::now());
+                    let exit_code = Some(exit.exit_code());
                     if let Some(p) = view.pending_line.take() {
                         view.lines.push(p);
                     }
-                    view.lines.push(String::new());
-                    let msg = match exit_code {
-                        Some(0) => "\x1b[32m[✔] Completed successfully\x1b[0m".to_string(),
-                        Some(code) => format!("\x1b[31m[✖] Failed (exit {})\x1b[0m", code),
-                        None => String::new(),
-                    };
-                    view.lines.push(msg);
+                    // The run's own output ends here; banners and usage below are dx's
+                    let output_end = view.lines.len();
+                    let timed_out = app.run.as_ref().is_some_and(|r| r.timed_out);
+                    let timeout_label = app
+                        .run
+                        .as_ref()
+                        .and_then(|r| r.policy.timeout)
+                        .map(crate::exec::format_duration)
+                        .unwrap_or_default();
+                    let retry = app.run.as_mut().filter(|r| {
+                        (r.timed_out || exit_code != Some(0)) && r.attempt < r.attempts()
+                    });
+                    if let Some(stop) = stopped {
+                        view.running = false;
+                        view.exit_status = None;
+                        view.stopped_by = Some(stop.stage.signal_name());
+                        view.ended_at = Some(Instant::now());
+                        view.lines.push(String::new());
+                        view.lines.push(format!(
+                            "\x1b[35m[⛔] Stopped by {} after {} ({})\x1b[0m",
+                            stop.stage.signal_name(),
+                            crate::exec::format_duration(stop.requested_at.elapsed()),
+                            exit.describe()
+                        ));
+                        view.lines.push(
+                            "\x1b[97;1mPress Esc or q to return to the main menu\x1b[0m".to_string(),
+                        );
+                    } else if let Some(run) = retry {
+                        // Keep the view "running" while waiting for the next attempt
+                        let reason = if timed_out {
+                            format!("timed out after {}", timeout_label)
+                        } else {
+                            exit.describe()
+                        };
+                        let delay = run.policy.delay_before(run.attempt + 1);
+                        view.lines.push(format!(
+                            "\x1b[33m[↻] Attempt {}/{} failed ({}); retrying in {}\x1b[0m",
+                            run.attempt,
+                            run.attempts(),
+                            reason,
+                            crate::exec::format_duration(delay)
+                        ));
+                        run.retry_at = Some(Instant::now() + delay);
+                    } else {
+                        view.running = false;
+                        // Same code as a timed-out run attached to the terminal
+                        view.exit_status = if timed_out {
+                            Some(crate::exec::TIMEOUT_EXIT_CODE)
+                        } else {
+                            exit_code
+                        };
+                        view.ended_at = Some(Instant::now());
+                        view.lines.push(String::new());
+                        let msg = if timed_out {
+                            format!("\x1b[31m[⏱] Timed out after {}\x1b[0m", timeout_label)
+                        } else if exit.success() {
+                            "\x1b[32m[✔] Completed successfully\x1b[0m".to_string()
+                        } else if exit.signal.is_some() {
+                            format!("\x1b[31m[✖] Failed ({})\x1b[0m", exit.describe())
+                        } else {
+                            format!("\x1b[31m[✖] Failed (exit {})\x1b[0m", exit.exit_code())
+                        };
+                        view.lines.push(msg);
+                        if let Some(usage) = exit.usage {
+                            view.lines.push(format!("\x1b[90m{}\x1b[0m", usage.summary()));
+                        }
+                    }
+                    if let Some(pgid) = leader {
+                        app.survivors_rx = Some((pgid, spawn_survivor_check(pgid)));
+                    }
+                    if !view.running {
+                        notify_finished(app.notifier.as_ref(), view);
+                        if let Some(item) = app.run.as_ref().and_then(|r| r.policy.history.as_deref())
+                        {
+                            let project = app
+                                .menu_path
+                                .as_deref()
+                                .and_then(Path::parent)
+                                .map_or_else(crate::exec::find_project_root, Path::to_path_buf);
+                            let key = history::project_key(&project, item);
+                            record_run(view, &key, output_end, app.history_runs);
+                        }
+                    }
                     let vh = view.viewport_height.max(1);
                     let total = view.lines.len() as u16;
                     view.scroll_y = total.saturating_sub(vh);
                     view.auto_scroll = true;
-                    // Telemetry for PTY: send when non-zero exit code marker present
-                    if let (Some(cfg), Some(code)) = (app.telemetry.as_ref(), view.exit_status) {
+                    // Telemetry for PTY: send on non-zero exit
+                    if let (Some(cfg), Some(code), false) =
+                        (app.telemetry.as_ref(), view.exit_status, view.running)
+                    {
                         if cfg.enabled {
                             if let Some(endpoint) = cfg.endpoint.as_ref() {
                                 if code != 0 {
@@ -2092,6 +2285,9 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                         }
                     }
                 }
+                if app.run.as_ref().is_none_or(|r| r.retry_at.is_none()) {
+                    app.run = None;
+                }
                 app.pty_child = None;
                 app.pty_master = None;
                 app.pty_writer = None;
@@ -2102,7 +2298,9 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
         if app.needs_clear {
             terminal.clear()?;
             app.needs_clear = false;
+            app.image_overlays_shown.clear();
         }
+        app.image_overlays.clear();
         terminal.draw(|f| {
             let area = f.area();
 
@@ -2146,6 +2344,33 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                 (content_area, None)
             };
 
+            // Menu reload failures and warnings stay visible above the content until the
+            // file is fixed
+            let banner = match (&app.reload_error, &app.reload_warning) {
+                (Some(err), _) => {
+                    Some((format!("⚠ {} — fix and save to retry", err), app.theme.accent_danger))
+                }
+                (None, Some(warning)) => Some((format!("⚠ {}", warning), app.theme.accent_warning)),
+                _ => None,
+            };
+            let main_area = match banner {
+                Some((text, color)) if main_area.height > 4 => {
+                    let parts = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(vec![Constraint::Length(1), Constraint::Min(3)])
+                        .split(main_area);
+                    let banner = ratatui::widgets::Paragraph::new(Line::from(Span::styled(
+                        text,
+                        Style::default()
+                            .fg(color)
+                            .add_modifier(ratatui::style::Modifier::BOLD),
+                    )));
+                    f.render_widget(banner, parts[0]);
+                    parts[1]
+                }
+                _ => main_area,
+            };
+
             // Record the actual interactive content area (excludes status bar)
             app.last_content_area = Some(main_area);
 
@@ -2176,7 +2401,7 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                 if let Some(b) = &app.asciinema_badge {
                     if b.starts_with("📡") {
                         let dot = if app.blink_on { "⏺" } else { " " };
-                        spans.push(Span::styled(dot, Style::default().fg(Color::Red)));
+                        spans.push(Span::styled(dot, app.theme.recording));
                         if let Some((_, rest)) = b.split_once(' ') {
                             spans.push(Span::raw(format!(" {}", rest)));
                         }
@@ -2191,7 +2416,8 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                     spans.push(Span::raw(format!("FPS: {}", app.fps.round() as u32)));
                 }
                 if !spans.is_empty() {
-                    frame::render_status_bar(Line::from(spans), area, f);
+                    let style = Style::default().fg(app.theme.status_fg).bg(app.theme.status_bg);
+                    frame::render_status_bar(Line::from(spans), style, area, f);
                 }
             }
 
@@ -2210,7 +2436,10 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
             if let Some(rt) = app.plugin_overlays.get(0) {
                 rt.render(f, overlay_area);
             }
+            // One pass over the finished frame keeps MOTD, Markdown and output consistent
+            color::downsample_buffer(f.buffer_mut(), app.color_depth);
         })?;
+        show_image_overlays(&mut app);
 
         // Update FPS after a successful frame draw
         app.fps_frames = app.fps_frames.saturating_add(1);
@@ -2228,7 +2457,17 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
-        if crossterm::event::poll(timeout)? {
+        // crossterm would read the query replies as keys, so stdin is ours until it finishes
+        let ready = match app.theme_query.as_mut() {
+            Some(query) => {
+                if query.poll(timeout) && finish_auto_theme_check(&mut app)? {
+                    break;
+                }
+                false
+            }
+            None => crossterm::event::poll(timeout)?,
+        };
+        if ready {
             match event::read()? {
                 CEvent::Key(key) => {
                     if handle_key_event(&mut app, key)? {
@@ -2242,25 +2481,35 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
                     }
                 }
                 CEvent::Paste(s) => {
-                    if let Some(w) = &mut app.pty_writer {
-                        let _ = w.write_all(b"\x1b[200~");
-                        let _ = w.write_all(s.as_bytes());
-                        let _ = w.write_all(b"\x1b[201~");
-                        let _ = w.flush();
+                    if app.pty_writer.is_some() {
+                        // Wrap only when the child enabled bracketed paste (mode 2004)
+                        let modes = match &app.screen {
+                            Screen::Output(view) => {
+                                view.term.as_ref().map(|t| t.key_modes()).unwrap_or_default()
+                            }
+                            _ => keys::KeyModes::default(),
+                        };
+                        let bytes = keys::encode_paste(&s, modes);
+                        crate::exec::pty_write(&mut app.pty_writer, &bytes);
+                    } else if paste_as_keys(&mut app, &s)? {
+                        break;
                     }
                 }
                 CEvent::FocusGained => {
-                    if let Some(w) = &mut app.pty_writer {
-                        let _ = w.write_all(b"\x1b[I");
-                        let _ = w.flush();
-                    }
-                }
-                CEvent::FocusLost => {
-                    if let Some(w) = &mut app.pty_writer {
-                        let _ = w.write_all(b"\x1b[O");
-                        let _ = w.flush();
+                    // A program in the PTY may be reading replies of its own, so leave the
+                    // terminal alone while one runs
+                    if app.theme_auto
+                        && app.theme_query.is_none()
+                        && app.pty_writer.is_none()
+                        && app.theme_queried_at.elapsed() >= theme::BACKGROUND_REQUERY_INTERVAL
+                    {
+                        app.theme_query =
+                            theme::BackgroundQuery::start(theme::BACKGROUND_QUERY_TIMEOUT);
+                        app.theme_queried_at = Instant::now();
                     }
+                    forward_focus(&mut app, b"\x1b[I");
                 }
+                CEvent::FocusLost => forward_focus(&mut app, b"\x1b[O"),
                 CEvent::Resize(_, _) => {
                     // Resize PTY to match current content area if available
                     if let Some(area) = app.last_content_area {
@@ -2350,6 +2599,36 @@ fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>, mut app: Ap
     Ok(())
 }
 
+/// Feed pasted text to the active input (form, prompt, piped child) as if it were typed,
+/// which is what it was before bracketed paste was switched on.
+fn paste_as_keys(app: &mut App, text: &str) -> Result<bool> {
+    // Outside a text input the keys would act as shortcuts (a pasted "q" would quit)
+    let takes_text = match &app.screen {
+        Screen::Form(_) => true,
+        Screen::Menu(_) => app.menu_cmd.is_some() || app.docs_query.is_some(),
+        Screen::Output(view) => {
+            app.child.is_some() || view.search.as_ref().is_some_and(|s| s.editing)
+        }
+        Screen::Config(_) => false,
+    };
+    if !takes_text {
+        return Ok(false);
+    }
+    let text = text.replace("\r\n", "\n");
+    for c in text.chars() {
+        let code = match c {
+            '\n' | '\r' => KeyCode::Enter,
+            '\t' => KeyCode::Tab,
+            c if c.is_control() => continue,
+            c => KeyCode::Char(c),
+        };
+        if handle_key_event(app, KeyEvent::from(code))? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
     // If a confirmation modal is open, handle it with priority
     if let Some(Confirm::KillProcess { yes_selected }) = app.confirm {
@@ -2406,34 +2685,35 @@ fn handle_key_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                         }
                     }
                 }
-                if let Some(child) = app.pty_child.as_mut() {
-                    let _ = child.kill();
-                    if let Screen::Output(view) = &mut app.screen {
-                        view.running = false;
-                        view.ended_at = Some(Instant::now());
-                        if let Some(p) = view.pending_line.take() {
-                            view.lines.push(p);
-                        }
-                        view.lines.push(String::new());
-                        view.lines
-                            .push("\x1b[35m[⛔] Terminated\x1b[0m".to_string());
-                        view.lines.push(
-                            "\x1b[97;1mPress Esc or q to return to the main menu\x1b[0m"
-                                .to_string(),
-                        );
-                        view.lines.push(String::new());
-                        let vh = view.viewport_height.max(1);
-                        let total = view.lines.len() as u16;
-                        view.scroll_y = total.saturating_sub(vh);
-                        view.auto_scroll = true;
-                    }
+                // PTY command: SIGINT now, escalating to SIGTERM/SIGKILL; confirming
+                // again skips ahead. Exit handling in the main loop writes the summary.
+                if app.pty_child.is_some() {
+                    escalate_stop(app);
+                }
+                // A confirmed kill also cancels any retry waiting for its backoff delay
+                if app.run.take().is_some_and(|r| r.retry_at.is_some())
+                    && app.pty_child.is_none()
+                    && let Screen::Output(view) = &mut app.screen
+                {
+                    view.running = false;
+                    view.ended_at = Some(Instant::now());
+                    view.lines.push(String::new());
+                    view.lines
+                        .push("\x1b[35m[⛔] Retries cancelled\x1b[0m".to_string());
+                    view.lines.push(
+                        "\x1b[97;1mPress Esc or q to return to the main menu\x1b[0m"
+                            .to_string(),
+                    );
+                    view.lines.push(String::new());
                 }
                 app.child = None;
-                app.rx = None;
                 app.child_stdin = None;
-                app.pty_child = None;
-                app.pty_master = None;
-                app.pty_writer = None;
+                if app.stopping.is_none() {
+                    app.rx = None;
+                    app.pty_child = None;
+                    app.pty_master = None;
+                    app.pty_writer = None;
+                }
                 app.confirm = None;
                 // Stay on Output view to let user read the summary; user can press b/Backspace or Esc/q to return to menu
                 return Ok(false);
@@ -2490,12 +2770,51 @@ use crate::config::open_config_state;
 
 // use crate::config::save_app_config;
 
-use crate::config::load_app_config_file as read_app_config_file;
-
 // Validate AppConfig file and return (errors, warnings). None if file unreadable.
-use crate::config::validate_app_config_file;
+use crate::config::{find_config_file, validate_app_config, validate_app_config_file};
+
+/// Forward a mouse event inside the output pane to a PTY program that enabled mouse
+/// reporting and is shown live. Returns true when the event belongs to the program.
+fn forward_mouse_to_pty(app: &mut App, me: &MouseEvent) -> bool {
+    let Screen::Output(view) = &app.screen else {
+        return false;
+    };
+    let Some(term) = view.term.as_ref().filter(|t| t.wants_mouse()) else {
+        return false;
+    };
+    // Scrolled back, the rows shown are not the program's screen: dx keeps the wheel and
+    // clicks until the view is back at the live bottom
+    if !view.running || app.pty_writer.is_none() || term.scrollback_offset() > 0 {
+        return false;
+    }
+    let Some(area) = app.last_content_area else {
+        return false;
+    };
+    let inner = Rect {
+        x: area.x.saturating_add(1 + PAD_X),
+        y: area.y.saturating_add(1 + PAD_Y),
+        width: area.width.saturating_sub(2 + PAD_X * 2),
+        height: area.height.saturating_sub(2 + PAD_Y * 2),
+    };
+    if me.column < inner.x
+        || me.row < inner.y
+        || me.column >= inner.x + inner.width
+        || me.row >= inner.y + inner.height
+    {
+        return false;
+    }
+    let col = me.column - inner.x;
+    let row = me.row - inner.y;
+    if let Some(bytes) = term.encode_mouse(me.kind, me.modifiers, col, row) {
+        crate::exec::pty_write(&mut app.pty_writer, &bytes);
+    }
+    true
+}
 
 fn handle_mouse_event(app: &mut App, me: MouseEvent) {
+    if forward_mouse_to_pty(app, &me) {
+        return;
+    }
     match &mut app.screen {
         Screen::Menu(menu) => match me.kind {
             MouseEventKind::ScrollUp => {
@@ -2557,18 +2876,23 @@ fn handle_mouse_event(app: &mut App, me: MouseEvent) {
                                             let view = open_file_view(Path::new(&file));
                                             app.screen = Screen::Output(view);
                                             app.needs_clear = true;
-                                        } else if let Some(cmd) = item.cmd {
+                                        } else if let Some(cmd) = item.cmd.clone() {
                                             let external = item.external.unwrap_or(false);
                                             if external {
                                                 let _ = passthrough_command(app, &item.name, &cmd);
                                             } else {
-                                                let _ = start_command(app, &item.name, &cmd);
+                                                let _ = start_command(
+                                                    app,
+                                                    &item.name,
+                                                    &cmd,
+                                                    item.run_policy(),
+                                                );
                                                 app.needs_clear = true;
                                             }
                                         } else if item.name == "Configuration"
                                             || item.alias.as_deref() == Some("config")
                                         {
-                                            app.screen = Screen::Config(open_config_state());
+                                            app.screen = Screen::Config(open_config_state(&app.settings));
                                             app.needs_clear = true;
                                         }
                                     }
@@ -2581,6 +2905,17 @@ fn handle_mouse_event(app: &mut App, me: MouseEvent) {
             _ => {}
         },
         Screen::Output(view) => match me.kind {
+            // The emulator keeps its own scrollback; the wheel moves through it
+            MouseEventKind::ScrollUp if view.term.is_some() => {
+                if let Some(t) = view.term.as_mut() {
+                    t.scroll_up(3);
+                }
+            }
+            MouseEventKind::ScrollDown if view.term.is_some() => {
+                if let Some(t) = view.term.as_mut() {
+                    t.scroll_down(3);
+                }
+            }
             MouseEventKind::ScrollUp => {
                 let step: u16 = 3;
                 for _ in 0..step {
@@ -2625,32 +2960,8 @@ fn handle_mouse_event(app: &mut App, me: MouseEvent) {
                                 let link_start = start.saturating_add(2);
                                 if doc_line >= link_start {
                                     let idx = doc_line - link_start;
-                                    if let Some(dest) = view.md_links.get(idx as usize).cloned() {
-                                        if let Some(base) = view
-                                            .file_path
-                                            .as_ref()
-                                            .and_then(|p| p.parent())
-                                            .map(|p| p.to_path_buf())
-                                        {
-                                            let target = base.join(&dest);
-                                            if target
-                                                .extension()
-                                                .and_then(|s| s.to_str())
-                                                .map(|s| {
-                                                    matches!(
-                                                        s.to_ascii_lowercase().as_str(),
-                                                        "md" | "markdown"
-                                                    )
-                                                })
-                                                .unwrap_or(false)
-                                                && target.exists()
-                                            {
-                                                let mut v = open_file_view(&target);
-                                                v.file_path = Some(target);
-                                                app.screen = Screen::Output(v);
-                                                app.needs_clear = true;
-                                            }
-                                        }
+                                    if (idx as usize) < view.md_links.len() {
+                                        screens::output::follow_link(app, idx as usize);
                                     }
                                 }
                             }
@@ -2675,6 +2986,187 @@ fn handle_mouse_event(app: &mut App, me: MouseEvent) {
 
 // moved to menu.rs
 
+/// Theme tokens for a theme name and optional `.dx-theme` file, with the configured
+/// overrides on top.
+fn load_theme_choice(app: &App, name: &str, file: Option<&Path>) -> theme::ThemeTokens {
+    theme::load_theme_from(
+        Some(name),
+        file.and_then(Path::to_str),
+        app.theme_overrides.as_ref(),
+    )
+}
+
+/// `theme = "auto"`: apply the answer to the focus query, switching when the background
+/// went dark/light, then replay keys typed while it was in flight. True when one of them quit.
+fn finish_auto_theme_check(app: &mut App) -> Result<bool> {
+    let Some(query) = app.theme_query.take() else {
+        return Ok(false);
+    };
+    let (rgb, typed) = query.finish();
+    if let Some(dark) = rgb.map(theme::is_dark_background)
+        && dark != app.theme_dark
+    {
+        let (name, file) =
+            theme::auto_theme(dark, app.theme_family.as_deref(), app.theme_dir.as_deref());
+        let tokens = load_theme_choice(app, &name, file.as_deref());
+        app.theme_dark = dark;
+        app.theme_name = name;
+        app.theme_file = file;
+        apply_theme(app, tokens);
+    }
+    for key in keys::decode_input(&typed) {
+        if handle_key_event(app, key)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Pass a focus report on to a child that enabled focus reporting (mode 1004).
+fn forward_focus(app: &mut App, report: &[u8]) {
+    let wants = match &app.screen {
+        Screen::Output(view) => view.term.as_ref().is_some_and(|t| t.wants_focus()),
+        _ => false,
+    };
+    if wants {
+        crate::exec::pty_write(&mut app.pty_writer, report);
+    }
+}
+
+/// Switch to `tokens` everywhere, dropping text that was coloured with the old theme.
+fn apply_theme(app: &mut App, tokens: theme::ThemeTokens) {
+    app.theme = tokens;
+    for screen in std::iter::once(&mut app.screen).chain(app.screen_stack.iter_mut()) {
+        if let Screen::Output(view) = screen {
+            view.highlighted = None;
+            view.md_cache = None;
+        }
+    }
+    app.needs_clear = true;
+}
+
+/// Re-read a viewed file in place; the scroll position is kept (and clamped if it shrank).
+fn reload_file_view(view: &mut OutputView) {
+    let Some(path) = view.file_path.clone() else {
+        return;
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return;
+    };
+    if view.md_content.is_some() {
+        view.md_content = Some(content);
+        view.md_cache = None;
+    } else {
+        view.syntax = highlight::syntax_for_file(&path, &content);
+        view.lines = content.lines().map(|s| s.to_string()).collect();
+        view.highlighted = None;
+    }
+}
+
+/// Re-read MOTD.md, keeping the startup issue banner and re-evaluating a template.
+fn reload_motd(app: &mut App) {
+    let (lines, raw) = app
+        .motd_path
+        .as_deref()
+        .and_then(motd::read_motd_file)
+        .unwrap_or((Vec::new(), false));
+    let lines = if app.startup_issues.is_empty() {
+        lines
+    } else {
+        motd::prepend_system_banner(lines, "Configuration issues detected:", &app.startup_issues)
+    };
+    app.motd_force_raw = raw;
+    let names = motd::template_names(&app.motd_eval.commands);
+    if motd::is_template(&lines, &names) {
+        app.motd_lines = motd::render_template(&lines, &names, &app.motd_vars);
+        app.motd_template = Some(lines);
+        app.motd_vars_rx = Some(app.motd_eval.spawn());
+    } else {
+        app.motd_lines = lines;
+        app.motd_template = None;
+        app.motd_vars_rx = None;
+    }
+    app.needs_clear = true;
+}
+
+/// Reload the menu file; a menu that no longer loads keeps the current one and explains
+/// why in a banner until the file is fixed. Validation issues are shown but don't block it.
+fn reload_menu(app: &mut App) {
+    let Some(path) = app.menu_path.clone() else {
+        return;
+    };
+    let mut menu = match load_menu(&path) {
+        Ok(menu) => menu,
+        Err(e) => {
+            app.reload_error = Some(format!("{} not reloaded: {:#}", path.display(), e));
+            return;
+        }
+    };
+    let issues = validate_menu(&menu.items);
+    app.reload_warning = issues.first().map(|first| {
+        let more = if issues.len() > 1 {
+            format!(" (+{} more)", issues.len() - 1)
+        } else {
+            String::new()
+        };
+        format!("{} reloaded with issues: {}{}", path.display(), first, more)
+    });
+    app.reload_error = None;
+    prepend_readme_item(&mut menu);
+    crate::menu::append_dx_menu(&mut menu);
+    if let Screen::Menu(current) = &app.screen {
+        // Stay in the same folder and on the same row where they still exist
+        let mut items = menu.items.as_slice();
+        let mut valid = true;
+        for &idx in &current.path {
+            match items.get(idx) {
+                Some(item) if !item.items.is_empty() => items = &item.items,
+                _ => {
+                    valid = false;
+                    break;
+                }
+            }
+        }
+        if valid {
+            menu.path = current.path.clone();
+            menu.selected_index = current.selected_index.min(items.len().saturating_sub(1));
+        }
+        app.screen = Screen::Menu(menu);
+        app.needs_clear = true;
+    }
+}
+
+/// Draw document images placed by the last frame through the terminal's graphics protocol,
+/// only when they changed (graphics survive redraws of unchanged cells).
+fn show_image_overlays(app: &mut App) {
+    let Some(protocol) = app.image_protocol.filter(|p| *p != images::ImageProtocol::Blocks) else {
+        return;
+    };
+    if app.image_overlays == app.image_overlays_shown {
+        return;
+    }
+    let cell_px = crossterm::terminal::window_size()
+        .ok()
+        .filter(|w| w.width > 0 && w.columns > 0 && w.rows > 0)
+        .map(|w| {
+            (
+                u32::from(w.width / w.columns),
+                u32::from(w.height / w.rows),
+            )
+        });
+    let mut out = io::stdout();
+    if protocol == images::ImageProtocol::Kitty {
+        let _ = out.write_all(images::KITTY_CLEAR.as_bytes());
+    }
+    for placement in &app.image_overlays {
+        if let Some(seq) = images::overlay_sequence(protocol, placement, cell_px) {
+            let _ = out.write_all(seq.as_bytes());
+        }
+    }
+    let _ = out.flush();
+    app.image_overlays_shown = app.image_overlays.clone();
+}
+
 fn open_file_view(path: &Path) -> OutputView {
     let mut view = OutputView::new(path.display().to_string());
     if let Ok(content) = fs::read_to_string(path) {
@@ -2690,6 +3182,7 @@ fn open_file_view(path: &Path) -> OutputView {
             view.wrap_enabled = true;
             view.render_markdown = true;
         } else {
+            view.syntax = highlight::syntax_for_file(path, &content);
             view.lines = content.lines().map(|s| s.to_string()).collect();
             view.file_path = Some(path.to_path_buf());
         }
@@ -2780,8 +3273,148 @@ fn centered_rect_fixed(w: u16, h: u16, within: Rect) -> Rect {
     }
 }
 
-fn start_command(app: &mut App, title: &str, cmd_str: &str) -> Result<()> {
-    let spawned = crate::exec::spawn_pty(cmd_str)?;
+/// Send the next stop signal (starting with SIGINT) to the running PTY command's process group.
+fn escalate_stop(app: &mut App) {
+    use crate::exec::StopStage;
+    let stage = match app.stopping.as_ref() {
+        None => StopStage::Interrupt,
+        Some(s) => match s.stage.next() {
+            Some(next) => next,
+            None => return,
+        },
+    };
+    let Some(child) = app.pty_child.as_mut() else {
+        return;
+    };
+    crate::exec::pty_signal(child, stage);
+    let now = Instant::now();
+    let grace = app.stop_grace.after(stage);
+    app.stopping = Some(StopState {
+        stage,
+        requested_at: app.stopping.as_ref().map_or(now, |s| s.requested_at),
+        next_at: grace.map(|d| now + d),
+    });
+    if let Screen::Output(view) = &mut app.screen {
+        let next = match (grace, stage.next()) {
+            (Some(d), Some(next)) => format!(
+                "; {} in {}",
+                next.signal_name(),
+                crate::exec::format_duration(d)
+            ),
+            _ => String::new(),
+        };
+        view.lines.push(format!(
+            "\x1b[33m[…] Sent {} to the process group{}\x1b[0m",
+            stage.signal_name(),
+            next
+        ));
+    }
+}
+
+/// Notify about a finished command that ran past `notify_after`; kills the user asked for are skipped.
+fn notify_finished(notifier: Option<&notify::Notifier>, view: &OutputView) {
+    let (Some(notifier), Some(start), Some(end)) = (notifier, view.started_at, view.ended_at)
+    else {
+        return;
+    };
+    if view.stopped_by.is_some() {
+        return;
+    }
+    notifier.notify(&notify::Completion {
+        title: view.title.clone(),
+        exit_status: view.exit_status,
+        duration: end.saturating_duration_since(start),
+    });
+}
+
+/// Store the finished run (output lines before `output_end`, without dx's banners) under
+/// `key` and remember the previous run for comparison.
+fn record_run(view: &mut OutputView, key: &str, output_end: usize, keep: usize) {
+    let (Some(root), true) = (history::default_root(), keep > 0) else {
+        return;
+    };
+    let end = output_end.min(view.lines.len());
+    let mut output: Vec<String> = match &view.term {
+        Some(term) => term.history_lines(),
+        None => view.lines[view.output_from.min(end)..end]
+            .iter()
+            .map(|l| history::strip_ansi(l))
+            .collect(),
+    };
+    while output.last().is_some_and(|l| l.trim().is_empty()) {
+        output.pop();
+    }
+    let duration = match (view.started_at, view.ended_at) {
+        (Some(s), Some(e)) => e.saturating_duration_since(s),
+        _ => Duration::ZERO,
+    };
+    let now = std::time::SystemTime::now();
+    let record = history::RunRecord {
+        title: view.title.clone(),
+        started_ms: history::unix_ms(now.checked_sub(duration).unwrap_or(now)),
+        duration_ms: duration.as_millis() as u64,
+        exit_status: view.exit_status,
+        lines: output.clone(),
+    };
+    let previous = history::latest_run(&root, key);
+    if let Err(e) = history::save_run(&root, key, &record, keep) {
+        warn!("could not save run history: {}", e);
+    }
+    if let Some(prev) = previous {
+        view.compare = Some((prev, output));
+        view.lines.push(
+            "\x1b[90mPress c to compare with the previous run\x1b[0m".to_string(),
+        );
+    }
+}
+
+/// Look for processes from the command's process group or session that are still running,
+/// on a worker thread so the /proc scan and the grace period don't stall the UI.
+fn spawn_survivor_check(pgid: u32) -> tokio::sync::mpsc::Receiver<Vec<crate::exec::Survivor>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    thread::spawn(move || {
+        let mut survivors = crate::exec::group_survivors(pgid);
+        if !survivors.is_empty() {
+            // Give processes hung up by the session leader's exit a moment to go away
+            thread::sleep(Duration::from_millis(100));
+            survivors = crate::exec::group_survivors(pgid);
+        }
+        let _ = tx.blocking_send(survivors);
+    });
+    rx
+}
+
+/// Warn about processes from the command's process group or session that are still running.
+fn report_survivors(view: &mut OutputView, pgid: u32, survivors: &[crate::exec::Survivor]) {
+    if survivors.is_empty() {
+        return;
+    }
+    let list: Vec<String> = survivors
+        .iter()
+        .take(5)
+        .map(|p| format!("{} {}", p.pid, p.name))
+        .collect();
+    let more = survivors.len().saturating_sub(list.len());
+    let more = if more > 0 {
+        format!(" and {} more", more)
+    } else {
+        String::new()
+    };
+    view.lines.push(format!(
+        "\x1b[33m[!] {} process(es) left running: {}{} (stop with: kill -TERM -- -{})\x1b[0m",
+        survivors.len(),
+        list.join(", "),
+        more,
+        pgid
+    ));
+}
+
+fn start_command(app: &mut App, title: &str, cmd_str: &str, policy: RunPolicy) -> Result<()> {
+    app.run = Some(RunState::new(cmd_str, false, policy));
+    if let Err(e) = spawn_attempt(app) {
+        app.run = None;
+        return Err(e);
+    }
 
     let mut view = OutputView::new(format!("{}: {}", title, cmd_str));
     view.running = true;
@@ -2792,44 +3425,27 @@ fn start_command(app: &mut App, title: &str, cmd_str: &str) -> Result<()> {
     // Terminal emulator will be enabled lazily once we detect TUI/alternate-screen escape
     // sequences in the incoming byte stream (see bytes_look_like_tui()).
     // This prevents suppression of plain line output for simple commands like `ls` or `tail`.
+    if let Some(run) = app.run.as_ref().filter(|r| r.policy.retries > 0) {
+        view.lines.push(run.banner());
+    }
+    view.output_from = view.lines.len();
 
     app.screen = Screen::Output(view);
-    // Route output events
-    app.rx = Some(spawned.rx);
     app.needs_clear = true;
-    // Clear legacy child fields and set PTY handles
-    app.child = None;
-    app.child_stdin = None;
-    // Set PTY handles
-    app.pty_child = Some(spawned.child);
-    app.pty_master = Some(spawned.master);
-    app.pty_writer = Some(spawned.writer);
-    // Apply initial resize to fit content area if known
-    if let (Some(master), Some(area)) = (app.pty_master.as_mut(), app.last_content_area) {
-        let rows = area.height.saturating_sub(2 + PAD_Y * 2);
-        let cols = area.width.saturating_sub(2 + PAD_X * 2);
-        let size = PtySize {
-            rows: rows.max(1),
-            cols: cols.max(1),
-            pixel_width: 0,
-            pixel_height: 0,
-        };
-        let _ = master.resize(size);
-    }
     Ok(())
 }
 
-fn start_command_enhanced(app: &mut App, title: &str, cmd_str: &str) -> Result<()> {
-    // Enhanced terminal mode: use optimal PTY size with all improvements
-    let (rows, cols) = if let Some(area) = app.last_content_area {
-        let rows = area.height.saturating_sub(2 + PAD_Y * 2).max(1);
-        let cols = area.width.saturating_sub(2 + PAD_X * 2).max(1);
-        (rows, cols)
-    } else {
-        (24, 120)
-    };
-
-    let spawned = crate::exec::spawn_pty_with_size(cmd_str, rows, cols)?;
+fn start_command_enhanced(
+    app: &mut App,
+    title: &str,
+    cmd_str: &str,
+    policy: RunPolicy,
+) -> Result<()> {
+    app.run = Some(RunState::new(cmd_str, true, policy));
+    if let Err(e) = spawn_attempt(app) {
+        app.run = None;
+        return Err(e);
+    }
 
     let mut view = OutputView::new(format!("{}: {} [ENHANCED]", title, cmd_str));
     view.running = true;
@@ -2837,19 +3453,55 @@ fn start_command_enhanced(app: &mut App, title: &str, cmd_str: &str) -> Result<(
     view.started_at = Some(Instant::now());
     view.ended_at = None;
     view.md_content = None;
+    if let Some(run) = app.run.as_ref().filter(|r| r.policy.retries > 0) {
+        view.lines.push(run.banner());
+    }
+    view.output_from = view.lines.len();
 
     app.screen = Screen::Output(view);
+    app.needs_clear = true;
+    Ok(())
+}
+
+/// Spawn the next attempt of `app.run` under a PTY and wire its handles into the app.
+fn spawn_attempt(app: &mut App) -> Result<()> {
+    let Some(run) = app.run.as_mut() else {
+        return Ok(());
+    };
+    let spawned = if run.enhanced {
+        // Enhanced terminal mode: use optimal PTY size with all improvements
+        let (rows, cols) = if let Some(area) = app.last_content_area {
+            let rows = area.height.saturating_sub(2 + PAD_Y * 2).max(1);
+            let cols = area.width.saturating_sub(2 + PAD_X * 2).max(1);
+            (rows, cols)
+        } else {
+            (24, 120)
+        };
+        crate::exec::spawn_pty_with_size(&run.cmd, rows, cols)?
+    } else {
+        crate::exec::spawn_pty(&run.cmd)?
+    };
+    run.attempt += 1;
+    run.deadline = run.policy.timeout.map(|t| Instant::now() + t);
+    run.retry_at = None;
+    run.timed_out = false;
+
+    // A retry starts from a clean line/emulator state in the existing view
+    if run.attempt > 1
+        && let Screen::Output(view) = &mut app.screen
+    {
+        view.term = None;
+        view.pending_line = None;
+    }
     // Route output events
     app.rx = Some(spawned.rx);
-    app.needs_clear = true;
     // Clear legacy child fields and set PTY handles
     app.child = None;
     app.child_stdin = None;
-    // Set PTY handles
     app.pty_child = Some(spawned.child);
     app.pty_master = Some(spawned.master);
     app.pty_writer = Some(spawned.writer);
-    // Apply initial resize to fit content area
+    // Apply initial resize to fit content area if known
     if let (Some(master), Some(area)) = (app.pty_master.as_mut(), app.last_content_area) {
         let rows = area.height.saturating_sub(2 + PAD_Y * 2);
         let cols = area.width.saturating_sub(2 + PAD_X * 2);
@@ -2875,7 +3527,7 @@ fn passthrough_command(app: &mut App, title: &str, cmd_str: &str) -> Result<()>
     // Restore terminal to cooked mode and leave alt screen
     disable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture)?;
+    execute!(stdout, LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste, DisableFocusChange)?;
     drop(stdout);
 
     // Run command attached to TTY with a pause prompt to avoid flicker on short commands
@@ -3030,7 +3682,10 @@ fn passthrough_command(app: &mut App, title: &str, cmd_str: &str) -> Result<()>
     // After process, re-enter dx TUI
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
+    if app.theme_auto {
+        execute!(stdout, EnableFocusChange)?;
+    }
     drop(stdout);
 
     // Show summary in output view so user sees result and hint
@@ -3134,18 +3789,6 @@ fn spawn_status_command(cmd_str: &str) -> Result<(Child, tokio::sync::mpsc::Rece
     Ok((child, rx))
 }
 
-fn format_duration(d: Duration) -> String {
-    let secs = d.as_secs();
-    let h = secs / 3600;
-    let m = (secs % 3600) / 60;
-    let s = secs % 60;
-    if h > 0 {
-        format!("{:01}:{:02}:{:02}", h, m, s)
-    } else {
-        format!("{:02}:{:02}", m, s)
-    }
-}
-
 // moved to markdown.rs
 
 // moved helpers into crate::asciinema
@@ -1,9 +1,10 @@
 use pulldown_cmark::{
-    Event as MdEvent, HeadingLevel as MdHeadingLevel, Options as MdOptions, Parser as MdParser,
+    Alignment, CodeBlockKind, Event as MdEvent, HeadingLevel as MdHeadingLevel, Options as MdOptions, Parser as MdParser,
     Tag as MdTag, TagEnd as MdTagEnd,
 };
 use ratatui::style::Style;
 use ratatui::text::{Line, Span, Text};
+use unicode_width::UnicodeWidthChar;
 
 #[allow(dead_code)]
 #[must_use]
@@ -13,11 +14,129 @@ pub fn markdown_to_text(input: &str, theme: &super::theme::ThemeTokens) -> Text<
 }
 
 #[must_use]
-#[allow(clippy::too_many_lines)] // Complex markdown parsing requires extensive logic
 pub fn markdown_to_text_with_links(
     input: &str,
     theme: &super::theme::ThemeTokens,
 ) -> (Text<'static>, Vec<String>) {
+    let r = render_markdown(input, theme, MdLayout::default());
+    (r.text, r.links)
+}
+
+/// Viewport the document is laid out for: tables are fitted to its width and images to
+/// its size.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MdLayout {
+    /// Columns available for text; `None` lays tables out at their natural width
+    pub width: Option<usize>,
+    /// Rows of the viewport; images are kept shorter than this
+    pub height: Option<usize>,
+    /// Wrap wide table cells onto more lines instead of truncating them
+    pub wrap: bool,
+    /// Draw local images (relative to `base_dir`); otherwise only their alt text is shown
+    pub images: bool,
+    pub base_dir: Option<std::path::PathBuf>,
+}
+
+/// A local image in the document: its rows hold half blocks, which terminals with kitty,
+/// iTerm2 or sixel graphics cover with the real image placed over them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdImage {
+    pub path: std::path::PathBuf,
+    pub alt: String,
+    /// First rendered line of the image
+    pub line: usize,
+    pub cols: u16,
+    pub rows: u16,
+}
+
+/// A fenced code block that can be run from the document view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunnableBlock {
+    pub code: String,
+    pub lang: String,
+    /// Line of the rendered text holding the block's run header
+    pub header_line: usize,
+    /// Marked `dx-run=danger`: ask again before running
+    pub dangerous: bool,
+}
+
+/// A heading of the document and the rendered line it starts on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdHeading {
+    pub level: u32,
+    pub title: String,
+    /// Anchor for `#slug` links, de-duplicated like GitHub (`setup`, `setup-1`)
+    pub slug: String,
+    pub line: usize,
+}
+
+/// Headings shown in the outline panel (H1–H3).
+#[must_use]
+pub fn outline(headings: &[MdHeading]) -> Vec<MdHeading> {
+    headings.iter().filter(|h| h.level <= 3).cloned().collect()
+}
+
+/// Index of the outline entry whose section contains line `scroll`.
+#[must_use]
+pub fn current_section(outline: &[MdHeading], scroll: usize) -> Option<usize> {
+    outline.iter().rposition(|h| h.line <= scroll)
+}
+
+/// Where a link is drawn: its line and the spans from `🔗` to its `[n↗]` marker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkSpot {
+    pub line: usize,
+    pub spans: std::ops::Range<usize>,
+}
+
+/// Rendered Markdown plus what the viewer needs to act on it.
+#[derive(Debug, Clone)]
+pub struct MdRender {
+    pub text: Text<'static>,
+    pub links: Vec<String>,
+    /// Position of each link in `text`, by index into `links`
+    pub link_spots: Vec<Option<LinkSpot>>,
+    pub headings: Vec<MdHeading>,
+    pub blocks: Vec<RunnableBlock>,
+    pub images: Vec<MdImage>,
+}
+
+/// Whether a fenced block with `info` is runnable and if so whether it is dangerous.
+/// Shell blocks (`sh`, `bash`, `shell`, `zsh`) run by default; `dx-run` makes any block
+/// runnable, `dx-run=danger` asks for confirmation and `dx-run=no` opts out.
+#[must_use]
+pub fn run_attr(info: &str) -> Option<bool> {
+    let mut tokens = info
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '{' | '}'))
+        .filter(|t| !t.is_empty());
+    let lang = tokens.next().unwrap_or("").to_ascii_lowercase();
+    let mut runnable = matches!(lang.as_str(), "sh" | "bash" | "shell" | "zsh");
+    let mut dangerous = false;
+    for t in std::iter::once(lang.as_str()).chain(tokens) {
+        match t.strip_prefix("dx-run") {
+            Some("") => runnable = true,
+            Some(v) => match v.trim_start_matches('=').trim_matches('"') {
+                "danger" | "dangerous" | "confirm" => {
+                    runnable = true;
+                    dangerous = true;
+                }
+                "no" | "false" | "off" => runnable = false,
+                _ => runnable = true,
+            },
+            None => {}
+        }
+    }
+    runnable.then_some(dangerous)
+}
+
+/// Render Markdown to styled text, collecting link targets and runnable code blocks.
+#[must_use]
+#[allow(clippy::too_many_lines)] // Complex markdown parsing requires extensive logic
+pub fn render_markdown(
+    input: &str,
+    theme: &super::theme::ThemeTokens,
+    layout: MdLayout,
+) -> MdRender {
     let mut opts = MdOptions::empty();
     opts.insert(MdOptions::ENABLE_STRIKETHROUGH);
     opts.insert(MdOptions::ENABLE_TABLES);
@@ -42,6 +161,22 @@ pub fn markdown_to_text_with_links(
         list_depth: u16,
     }
     let mut state = State::default();
+    // Language of the fenced code block being read (from its info string) and its text,
+    // highlighted as a whole when the block ends so multi-line constructs keep their state
+    let mut code_syntax: Option<&'static syntect::parsing::SyntaxReference> = None;
+    let mut code_buf = String::new();
+    let mut blocks: Vec<RunnableBlock> = Vec::new();
+    // Runnable block being read: (lang, dangerous, header line, code)
+    let mut run_block: Option<(String, bool, usize, String)> = None;
+    // Table being read: column alignments, rows of cells (the first is the header)
+    let mut table: Option<(Vec<Alignment>, Vec<TableRow>)> = None;
+    let mut table_row: TableRow = Vec::new();
+    let mut headings: Vec<MdHeading> = Vec::new();
+    let mut heading_text = String::new();
+    let mut images: Vec<MdImage> = Vec::new();
+    // Image being read: its destination and alt text
+    let mut image: Option<(String, String)> = None;
+    let mut slug_counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
 
     #[allow(clippy::items_after_statements)] // Helper function logically placed here
     fn push_line(text_lines: &mut Vec<Line<'static>>, current: &mut Vec<Span<'static>>) {
@@ -73,12 +208,39 @@ pub fn markdown_to_text_with_links(
                             .add_modifier(ratatui::style::Modifier::UNDERLINED),
                     ));
                 }
-                MdTag::CodeBlock(_kind) => {
+                MdTag::CodeBlock(kind) => {
                     state.code_block = true;
+                    code_syntax = match &kind {
+                        CodeBlockKind::Fenced(info) => crate::highlight::syntax_for_token(info),
+                        CodeBlockKind::Indented => None,
+                    };
+                    if !current.is_empty() {
+                        push_line(&mut text_lines, &mut current);
+                    }
+                    if let CodeBlockKind::Fenced(info) = &kind
+                        && let Some(dangerous) = run_attr(info)
+                    {
+                        let lang = info.split_whitespace().next().unwrap_or("sh").to_string();
+                        let (icon, color) = if dangerous {
+                            ("⚠ ", theme.accent_danger)
+                        } else {
+                            ("▶ ", theme.text_muted)
+                        };
+                        text_lines.push(Line::from(Span::styled(
+                            format!("{icon}{lang}"),
+                            Style::default().fg(color),
+                        )));
+                        run_block = Some((lang, dangerous, text_lines.len() - 1, String::new()));
+                    }
+                }
+                MdTag::Table(aligns) => {
                     if !current.is_empty() {
                         push_line(&mut text_lines, &mut current);
                     }
+                    table = Some((aligns, Vec::new()));
                 }
+                MdTag::TableHead => state.strong = true,
+                MdTag::Image { dest_url, .. } => image = Some((dest_url.to_string(), String::new())),
                 MdTag::List(_) => {
                     state.list_depth = state.list_depth.saturating_add(1);
                 }
@@ -108,6 +270,16 @@ pub fn markdown_to_text_with_links(
                         text_lines.push(Line::from(""));
                     }
                     // Heading line
+                    let base_slug = crate::links::slugify(&heading_text);
+                    let dupes = slug_counts.entry(base_slug.clone()).or_insert(0);
+                    let slug = if *dupes == 0 { base_slug } else { format!("{base_slug}-{dupes}") };
+                    *dupes += 1;
+                    headings.push(MdHeading {
+                        level,
+                        title: std::mem::take(&mut heading_text).trim().to_string(),
+                        slug,
+                        line: text_lines.len(),
+                    });
                     push_line(&mut text_lines, &mut current);
                     // Underline only for top levels
                     if heading_width > 0 {
@@ -157,9 +329,54 @@ pub fn markdown_to_text_with_links(
                 }
                 MdTagEnd::CodeBlock => {
                     state.code_block = false;
+                    if let Some((lang, dangerous, header_line, code)) = run_block.take() {
+                        blocks.push(RunnableBlock {
+                            code,
+                            lang,
+                            header_line,
+                            dangerous,
+                        });
+                    }
+                    if let Some(syntax) = code_syntax.take() {
+                        let base = Style::default().fg(theme.code);
+                        let code = std::mem::take(&mut code_buf);
+                        text_lines.extend(crate::highlight::highlight_lines(
+                            &code, syntax, theme, base,
+                        ));
+                    }
                     push_line(&mut text_lines, &mut current);
                     text_lines.push(Line::from(""));
                 }
+                MdTagEnd::TableCell => table_row.push(std::mem::take(&mut current)),
+                MdTagEnd::TableHead | MdTagEnd::TableRow => {
+                    state.strong = false;
+                    if let Some((_, rows)) = table.as_mut() {
+                        rows.push(std::mem::take(&mut table_row));
+                    }
+                }
+                MdTagEnd::Image => {
+                    if let Some((dest, alt)) = image.take() {
+                        match place_image(&dest, &layout) {
+                            Some((path, cols, rows, lines)) => {
+                                if !current.is_empty() {
+                                    push_line(&mut text_lines, &mut current);
+                                }
+                                images.push(MdImage { path, alt, line: text_lines.len(), cols, rows });
+                                text_lines.extend(lines);
+                            }
+                            None => current.push(Span::styled(
+                                format!("🖼 {}", if alt.is_empty() { dest.as_str() } else { alt.as_str() }),
+                                Style::default().fg(theme.text_muted),
+                            )),
+                        }
+                    }
+                }
+                MdTagEnd::Table => {
+                    if let Some((aligns, rows)) = table.take() {
+                        text_lines.extend(layout_table(&aligns, &rows, &layout, theme));
+                    }
+                    text_lines.push(Line::from(""));
+                }
                 MdTagEnd::List(_) => {
                     state.list_depth = state.list_depth.saturating_sub(1);
                 }
@@ -168,8 +385,18 @@ pub fn markdown_to_text_with_links(
                 }
                 _ => {}
             },
+            MdEvent::Text(t) if image.is_some() => {
+                if let Some((_, alt)) = image.as_mut() {
+                    alt.push_str(&t);
+                }
+            }
             MdEvent::Text(t) => {
-                if state.code_block {
+                if let Some((_, _, _, code)) = run_block.as_mut() {
+                    code.push_str(&t);
+                }
+                if state.code_block && code_syntax.is_some() {
+                    code_buf.push_str(&t);
+                } else if state.code_block {
                     for (i, l) in t.split('\n').enumerate() {
                         if i > 0 {
                             push_line(&mut text_lines, &mut current);
@@ -201,6 +428,9 @@ pub fn markdown_to_text_with_links(
                     } else if state.heading == 3 {
                         style = style.fg(theme.md_heading3);
                     }
+                    if state.heading > 0 {
+                        heading_text.push_str(&t);
+                    }
                     let mut text = t.to_string();
                     // Uppercase only for top-level headings for stronger contrast
                     if state.heading == 1 || state.heading == 2 {
@@ -210,6 +440,9 @@ pub fn markdown_to_text_with_links(
                 }
             }
             MdEvent::Code(code) => {
+                if state.heading > 0 {
+                    heading_text.push_str(&code);
+                }
                 current.push(Span::styled(
                     code.to_string(),
                     Style::default().fg(theme.code),
@@ -245,6 +478,9 @@ pub fn markdown_to_text_with_links(
     if !current.is_empty() {
         text_lines.push(Line::from(current));
     }
+    let link_spots = (1..=collected_links.len())
+        .map(|n| find_link_spot(&text_lines, n))
+        .collect();
     if !collected_links.is_empty() {
         text_lines.push(Line::from(""));
         text_lines.push(Line::from(Span::styled(
@@ -266,7 +502,14 @@ pub fn markdown_to_text_with_links(
             text_lines.push(Line::from(spans));
         }
     }
-    (Text::from(text_lines), collected_links)
+    MdRender {
+        text: Text::from(text_lines),
+        links: collected_links,
+        link_spots,
+        headings,
+        blocks,
+        images,
+    }
 }
 
 // Backwards-compat: tests may call old signature without theme; use builtin dark theme
@@ -276,3 +519,204 @@ pub fn markdown_to_text_with_links_compat(input: &str) -> (Text<'static>, Vec<St
     let theme = super::theme::ThemeTokens::builtin_dark();
     markdown_to_text_with_links(input, &theme)
 }
+
+/// Half-block lines for a local image, sized to the viewport; `None` for remote or
+/// unreadable images and ones still being decoded (shown as alt text instead).
+fn place_image(dest: &str, layout: &MdLayout) -> Option<(std::path::PathBuf, u16, u16, Vec<Line<'static>>)> {
+    if !layout.images || dest.contains("://") || dest.starts_with("data:") {
+        return None;
+    }
+    let dest = dest.split(['#', '?']).next().unwrap_or(dest);
+    let path = match &layout.base_dir {
+        Some(dir) => dir.join(dest),
+        None => std::path::PathBuf::from(dest),
+    };
+    let img = crate::images::load(&path)?;
+    let max_cols = layout.width.unwrap_or(80).min(usize::from(u16::MAX)) as u16;
+    // Leave room for a line of context above and below
+    let max_rows = layout.height.map_or(24, |h| h.saturating_sub(2).max(1)).min(usize::from(u16::MAX)) as u16;
+    let (cols, rows) = crate::images::fit_cells(img.width(), img.height(), max_cols, max_rows);
+    let lines = crate::images::half_block_lines(&path, cols, rows)?;
+    Some((path, cols, rows, lines))
+}
+
+/// Find link `n` by its ` [n↗]` marker (which survives table layout) and walk back to its `🔗`.
+fn find_link_spot(lines: &[Line<'static>], n: usize) -> Option<LinkSpot> {
+    let marker = format!("[{n}↗]");
+    lines.iter().enumerate().find_map(|(line, l)| {
+        let end = l.spans.iter().position(|s| s.content.trim() == marker)?;
+        let start = l.spans[..end]
+            .iter()
+            .rposition(|s| s.content.starts_with('🔗'))
+            .unwrap_or(0);
+        Some(LinkSpot {
+            line,
+            spans: start..end + 1,
+        })
+    })
+}
+
+/// Cells of one table row, each a run of styled text.
+type TableRow = Vec<Vec<Span<'static>>>;
+
+fn span_width(spans: &[Span]) -> usize {
+    spans.iter().map(Span::width).sum()
+}
+
+/// Column widths for a table: natural widths, shrinking the widest column until the
+/// table (with its borders) fits `width`.
+#[must_use]
+pub fn table_widths(natural: &[usize], width: Option<usize>) -> Vec<usize> {
+    const MIN_COL: usize = 3;
+    let mut widths: Vec<usize> = natural.iter().map(|w| (*w).max(1)).collect();
+    let Some(width) = width else {
+        return widths;
+    };
+    // "│ a │ b │": three columns of border/padding per cell plus the closing edge
+    let chrome = widths.len() * 3 + 1;
+    let avail = width.saturating_sub(chrome);
+    while widths.iter().sum::<usize>() > avail {
+        let Some((i, w)) = widths.iter().enumerate().max_by_key(|(_, w)| **w) else {
+            break;
+        };
+        if *w <= MIN_COL {
+            break;
+        }
+        widths[i] -= 1;
+    }
+    widths
+}
+
+/// Break a cell into lines of at most `width` columns, at spaces where possible.
+fn wrap_cell(cell: &[Span<'static>], width: usize) -> Vec<Vec<Span<'static>>> {
+    let chars: Vec<(char, Style)> = cell
+        .iter()
+        .flat_map(|s| s.content.chars().map(move |c| (c, s.style)))
+        .collect();
+    let mut lines: Vec<Vec<(char, Style)>> = vec![Vec::new()];
+    let mut line_w = 0;
+    for word in chars.split(|(c, _)| *c == ' ').filter(|w| !w.is_empty()) {
+        let word_w: usize = word.iter().map(|(c, _)| c.width().unwrap_or(0)).sum();
+        if line_w > 0 && line_w + 1 + word_w > width {
+            lines.push(Vec::new());
+            line_w = 0;
+        }
+        let line = lines.last_mut().expect("at least one line");
+        if line_w > 0 {
+            line.push((' ', line.last().map_or_else(Style::default, |(_, st)| *st)));
+            line_w += 1;
+        }
+        for &(c, st) in word {
+            let cw = c.width().unwrap_or(0);
+            if line_w + cw > width && line_w > 0 {
+                lines.push(Vec::new());
+                line_w = 0;
+            }
+            lines.last_mut().expect("at least one line").push((c, st));
+            line_w += cw;
+        }
+    }
+    lines.into_iter().map(|l| styled_spans(&l)).collect()
+}
+
+/// Cut a cell to `width` columns, ending in `…` when something was dropped.
+fn truncate_cell(cell: &[Span<'static>], width: usize) -> Vec<Span<'static>> {
+    if span_width(cell) <= width {
+        return cell.to_vec();
+    }
+    let mut out: Vec<(char, Style)> = Vec::new();
+    let mut used = 0;
+    for s in cell {
+        for c in s.content.chars() {
+            let cw = c.width().unwrap_or(0);
+            if used + cw + 1 > width {
+                out.push(('…', s.style));
+                return styled_spans(&out);
+            }
+            out.push((c, s.style));
+            used += cw;
+        }
+    }
+    styled_spans(&out)
+}
+
+fn styled_spans(chars: &[(char, Style)]) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    for &(c, st) in chars {
+        match spans.last_mut() {
+            Some(last) if last.style == st => last.content.to_mut().push(c),
+            _ => spans.push(Span::styled(c.to_string(), st)),
+        }
+    }
+    spans
+}
+
+/// Lay out a table with box-drawing borders; the first row is the header.
+fn layout_table(
+    aligns: &[Alignment],
+    rows: &[TableRow],
+    layout: &MdLayout,
+    theme: &super::theme::ThemeTokens,
+) -> Vec<Line<'static>> {
+    let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if cols == 0 {
+        return Vec::new();
+    }
+    let natural: Vec<usize> = (0..cols)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|r| r.get(i))
+                .map(|c| span_width(c))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let widths = table_widths(&natural, layout.width);
+    let border = Style::default().fg(theme.border);
+    let rule = |left: &str, mid: &str, right: &str| {
+        let parts: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        Line::from(Span::styled(
+            format!("{left}{}{right}", parts.join(mid)),
+            border,
+        ))
+    };
+
+    let mut out = vec![rule("┌", "┬", "┐")];
+    for (r, row) in rows.iter().enumerate() {
+        let cells: Vec<Vec<Vec<Span<'static>>>> = widths
+            .iter()
+            .enumerate()
+            .map(|(i, &w)| {
+                let cell = row.get(i).map_or(&[][..], Vec::as_slice);
+                if layout.wrap {
+                    wrap_cell(cell, w)
+                } else {
+                    vec![truncate_cell(cell, w)]
+                }
+            })
+            .collect();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+        for l in 0..height {
+            let mut spans = vec![Span::styled("│", border)];
+            for (i, &w) in widths.iter().enumerate() {
+                let content = cells[i].get(l).cloned().unwrap_or_default();
+                let pad = w.saturating_sub(span_width(&content));
+                let (before, after) = match aligns.get(i) {
+                    Some(Alignment::Right) => (pad, 0),
+                    Some(Alignment::Center) => (pad / 2, pad - pad / 2),
+                    _ => (0, pad),
+                };
+                spans.push(Span::raw(" ".repeat(before + 1)));
+                spans.extend(content);
+                spans.push(Span::raw(" ".repeat(after + 1)));
+                spans.push(Span::styled("│", border));
+            }
+            out.push(Line::from(spans));
+        }
+        if r == 0 && rows.len() > 1 {
+            out.push(rule("├", "┼", "┤"));
+        }
+    }
+    out.push(rule("└", "┴", "┘"));
+    out
+}
@@ -5,6 +5,9 @@ use anyhow::Result;
 use serde::Deserialize;
 use std::fs;
 use std::path::Path;
+use std::time::Duration;
+
+use crate::config::ConfigSection;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct MenuItem {
@@ -30,6 +33,27 @@ pub struct MenuItem {
     pub form: Option<FormSpec>,
     #[serde(default)]
     pub plugin_list: bool,
+    /// Kill the command when it runs longer than this (e.g. "30s", "5m", "1m30s")
+    #[serde(default)]
+    pub timeout: Option<String>,
+    /// Re-run the command up to N more times when it fails or times out
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Delay before the first retry; doubled for every following attempt (default "1s")
+    #[serde(default)]
+    pub backoff: Option<String>,
+    /// Shortcut that activates this item: a single key ("b") or a chord ("g s")
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Make `key` work from every folder instead of only the item's own folder
+    #[serde(default)]
+    pub key_global: bool,
+    /// Directory of Markdown documents shown as a browsable, searchable folder
+    #[serde(default)]
+    pub docs: Option<String>,
+    /// Set on search hits: the query and which of its matches to reveal when the file opens
+    #[serde(skip)]
+    pub reveal: Option<(String, usize)>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,8 +66,7 @@ pub struct MenuConfig {
 #[derive(Debug, Deserialize)]
 pub struct DxFile {
     #[serde(default)]
-    #[allow(dead_code)]
-    pub config: Option<serde_json::Value>, // TODO: Implement proper config parsing instead of JSON Value
+    pub config: Option<ConfigSection>, // same keys as config.yaml; overrides global and project config
     #[serde(default)]
     pub menu: Vec<MenuItem>,
 }
@@ -77,6 +100,172 @@ pub struct FormField {
     pub help: Option<String>,
 }
 
+/// Timeout and retry settings resolved from a menu item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunPolicy {
+    pub timeout: Option<Duration>,
+    pub retries: u32,
+    pub backoff: Duration,
+    /// Key under which finished runs are kept for comparison (alias, else item name)
+    pub history: Option<String>,
+}
+
+impl Default for RunPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            retries: 0,
+            backoff: Duration::from_secs(1),
+            history: None,
+        }
+    }
+}
+
+impl RunPolicy {
+    /// Delay to wait before starting `attempt` (1-based; attempt 1 never waits).
+    #[must_use]
+    pub fn delay_before(&self, attempt: u32) -> Duration {
+        if attempt <= 1 {
+            return Duration::ZERO;
+        }
+        let factor = 1u32 << (attempt - 2).min(16);
+        self.backoff.saturating_mul(factor)
+    }
+}
+
+impl MenuItem {
+    /// Timeout/retry policy for this item; unparsable durations fall back to defaults.
+    #[must_use]
+    pub fn run_policy(&self) -> RunPolicy {
+        let mut policy = RunPolicy {
+            timeout: self.timeout.as_deref().and_then(parse_duration),
+            retries: self.retries.unwrap_or(0),
+            history: Some(self.alias.clone().unwrap_or_else(|| self.name.clone())),
+            ..RunPolicy::default()
+        };
+        if let Some(b) = self.backoff.as_deref().and_then(parse_duration) {
+            policy.backoff = b;
+        }
+        policy
+    }
+}
+
+/// Parse a human duration such as "500ms", "30s", "5m", "1h" or "1m30s".
+/// A bare number is read as seconds.
+#[must_use]
+pub fn parse_duration(spec: &str) -> Option<Duration> {
+    let s = spec.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if let Ok(secs) = s.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let mut total = Duration::ZERO;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let n: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit_len = rest.find(|c: char| c.is_ascii_digit()).unwrap_or(rest.len());
+        let part = match &rest[..unit_len] {
+            "ms" => Duration::from_millis(n),
+            "s" => Duration::from_secs(n),
+            "m" => Duration::from_secs(n.saturating_mul(60)),
+            "h" => Duration::from_secs(n.saturating_mul(3600)),
+            _ => return None,
+        };
+        total = total.saturating_add(part);
+        rest = &rest[unit_len..];
+    }
+    Some(total)
+}
+
+/// Keys the menu screen already handles; a shortcut may not start with one of these.
+pub const BUILTIN_MENU_KEYS: &[char] = &['q', 'j', 'k', ':', '/'];
+
+/// Parse a shortcut spec: a single key ("b") or space-separated chord keys ("g s").
+#[must_use]
+pub fn parse_key_chord(spec: &str) -> Option<Vec<char>> {
+    let mut keys = Vec::new();
+    for part in spec.split_whitespace() {
+        let mut chars = part.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return None;
+        };
+        keys.push(c);
+    }
+    if keys.is_empty() { None } else { Some(keys) }
+}
+
+/// Result of feeding the keys typed so far to the menu's shortcut table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShortcutMatch {
+    /// No shortcut starts with the typed keys
+    NoMatch,
+    /// The typed keys are the start of at least one chord
+    Pending,
+    /// A shortcut matched; index path of the target item from the menu root
+    Item(Vec<usize>),
+}
+
+/// Shortcuts active in the folder at `path`: that folder's own keys, then every global key.
+#[must_use]
+pub fn shortcuts_at(root: &[MenuItem], path: &[usize]) -> Vec<(Vec<char>, Vec<usize>)> {
+    fn globals(items: &[MenuItem], at: &mut Vec<usize>, out: &mut Vec<(Vec<char>, Vec<usize>)>) {
+        for (i, it) in items.iter().enumerate() {
+            at.push(i);
+            if it.key_global {
+                if let Some(keys) = it.key.as_deref().and_then(parse_key_chord) {
+                    out.push((keys, at.clone()));
+                }
+            }
+            globals(&it.items, at, out);
+            at.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    for (i, it) in submenu_at(root, path).iter().enumerate() {
+        if it.key_global {
+            continue;
+        }
+        if let Some(keys) = it.key.as_deref().and_then(parse_key_chord) {
+            let mut target = path.to_vec();
+            target.push(i);
+            out.push((keys, target));
+        }
+    }
+    globals(root, &mut Vec::new(), &mut out);
+    out
+}
+
+/// Match the keys typed so far against the shortcuts active in the folder at `path`.
+#[must_use]
+pub fn match_shortcut(root: &[MenuItem], path: &[usize], typed: &[char]) -> ShortcutMatch {
+    let mut pending = false;
+    for (keys, target) in shortcuts_at(root, path) {
+        // Built-in keys keep their meaning; validate_menu reports the clash
+        if keys.first().is_some_and(|k| BUILTIN_MENU_KEYS.contains(k)) {
+            continue;
+        }
+        if keys == typed {
+            return ShortcutMatch::Item(target);
+        }
+        if keys.starts_with(typed) {
+            pending = true;
+        }
+    }
+    if pending {
+        ShortcutMatch::Pending
+    } else {
+        ShortcutMatch::NoMatch
+    }
+}
+
 #[derive(Debug)]
 pub struct MenuState {
     pub items: Vec<MenuItem>,
@@ -97,6 +286,35 @@ pub fn submenu_at<'a>(root: &'a [MenuItem], path: &[usize]) -> &'a [MenuItem] {
     items
 }
 
+/// Mutable access to the items of the folder at `path`.
+pub fn submenu_at_mut<'a>(root: &'a mut Vec<MenuItem>, path: &[usize]) -> &'a mut Vec<MenuItem> {
+    let mut items = root;
+    for &idx in path {
+        if idx >= items.len() {
+            break;
+        }
+        items = &mut items[idx].items;
+    }
+    items
+}
+
+/// The innermost `docs` item on `path`: its own path and directory.
+#[must_use]
+pub fn docs_root(root: &[MenuItem], path: &[usize]) -> Option<(Vec<usize>, String)> {
+    let mut items = root;
+    let mut found = None;
+    for (depth, &idx) in path.iter().enumerate() {
+        let Some(it) = items.get(idx) else {
+            break;
+        };
+        if let Some(dir) = &it.docs {
+            found = Some((path[..=depth].to_vec(), dir.clone()));
+        }
+        items = &it.items;
+    }
+    found
+}
+
 #[must_use]
 pub fn find_item_by_alias<'a>(root: &'a [MenuItem], alias: &str) -> Option<&'a MenuItem> {
     // First try explicit aliases (legacy behavior)
@@ -343,6 +561,7 @@ pub fn validate_menu(root: &[MenuItem]) -> Vec<String> {
 
             // Structural checks
             let has_items = !it.items.is_empty();
+            let has_docs = it.docs.is_some();
             let has_cmd = it
                 .cmd
                 .as_ref()
@@ -362,12 +581,54 @@ pub fn validate_menu(root: &[MenuItem]) -> Vec<String> {
                     "Menu item '{here}' cannot specify both 'cmd' and 'file'"
                 ));
             }
-            if !has_items && !has_cmd && !has_file {
+            if has_docs && (has_items || has_cmd || has_file) {
+                out.push(format!(
+                    "Menu item '{here}' cannot have 'docs' together with 'items', 'cmd' or 'file'"
+                ));
+            }
+            if let Some(dir) = &it.docs {
+                if !Path::new(dir).is_dir() {
+                    out.push(format!("Menu item '{here}' has 'docs' path '{dir}' that is not a directory"));
+                }
+            }
+            if !has_items && !has_cmd && !has_file && !has_docs {
                 out.push(format!(
                     "Menu item '{here}' has no action ('cmd'/'file') and no 'items'"
                 ));
             }
 
+            // Timeout/retry checks
+            for (key, val) in [("timeout", &it.timeout), ("backoff", &it.backoff)] {
+                if let Some(v) = val {
+                    if parse_duration(v).is_none() {
+                        out.push(format!(
+                            "Menu item '{here}' has invalid '{key}' value '{v}' (use e.g. 30s, 5m, 1m30s)"
+                        ));
+                    }
+                }
+            }
+            if (it.timeout.is_some() || it.retries.is_some()) && !has_cmd {
+                out.push(format!(
+                    "Menu item '{here}' sets 'timeout'/'retries' but has no 'cmd'"
+                ));
+            }
+
+            // Shortcut checks (conflicts between items are checked per folder below)
+            if let Some(k) = &it.key {
+                match parse_key_chord(k) {
+                    None => out.push(format!(
+                        "Menu item '{here}' has invalid 'key' value '{k}' (use a single key like \"b\" or a chord like \"g s\")"
+                    )),
+                    Some(keys) if BUILTIN_MENU_KEYS.contains(&keys[0]) => out.push(format!(
+                        "Menu item '{here}' key '{k}' conflicts with built-in key '{}'",
+                        keys[0]
+                    )),
+                    Some(_) => {}
+                }
+            } else if it.key_global {
+                out.push(format!("Menu item '{here}' sets 'key_global' but has no 'key'"));
+            }
+
             // Alias checks (single and multi)
             if let Some(a) = it.alias.as_ref().filter(|a| !a.trim().is_empty()) {
                 let key = a.trim().to_string();
@@ -406,6 +667,54 @@ pub fn validate_menu(root: &[MenuItem]) -> Vec<String> {
         issues.push(format!("Duplicate aliases: {}", v.join(", ")));
     }
 
+    // Shortcut conflicts: within every folder, no chord may equal or be a prefix of another
+    #[allow(clippy::items_after_statements)]
+    fn folders(items: &[MenuItem], at: &mut Vec<usize>, out: &mut Vec<Vec<usize>>) {
+        for (i, it) in items.iter().enumerate() {
+            if !it.items.is_empty() {
+                at.push(i);
+                out.push(at.clone());
+                folders(&it.items, at, out);
+                at.pop();
+            }
+        }
+    }
+    let label = |target: &[usize]| {
+        let mut names = Vec::new();
+        let mut items = root;
+        for &i in target {
+            if let Some(it) = items.get(i) {
+                names.push(it.name.as_str());
+                items = &it.items;
+            }
+        }
+        names.join(" > ")
+    };
+    let mut all_folders = vec![Vec::new()];
+    folders(root, &mut Vec::new(), &mut all_folders);
+    let mut reported: HashSet<(Vec<usize>, Vec<usize>)> = HashSet::new();
+    for folder in &all_folders {
+        let shortcuts = shortcuts_at(root, folder);
+        for (i, (a_keys, a)) in shortcuts.iter().enumerate() {
+            for (b_keys, b) in &shortcuts[i + 1..] {
+                if a == b || !(a_keys.starts_with(b_keys) || b_keys.starts_with(a_keys)) {
+                    continue;
+                }
+                let pair = if a < b { (a.clone(), b.clone()) } else { (b.clone(), a.clone()) };
+                if reported.insert(pair) {
+                    let show = |k: &[char]| k.iter().map(char::to_string).collect::<Vec<_>>().join(" ");
+                    issues.push(format!(
+                        "Shortcut '{}' on '{}' conflicts with '{}' on '{}'",
+                        show(a_keys),
+                        label(a),
+                        show(b_keys),
+                        label(b)
+                    ));
+                }
+            }
+        }
+    }
+
     issues
 }
 
@@ -437,6 +746,13 @@ pub fn prepend_readme_item(menu: &mut MenuState) {
             enhanced_terminal: None,
             form: None,
             plugin_list: false,
+            timeout: None,
+            retries: None,
+            backoff: None,
+            key: None,
+            key_global: false,
+            docs: None,
+            reveal: None,
         };
         menu.items.insert(0, readme_item);
         menu.selected_index = 0;
@@ -458,6 +774,13 @@ pub fn append_configuration_item(menu: &mut MenuState) {
         enhanced_terminal: None,
         form: None,
         plugin_list: false,
+        timeout: None,
+        retries: None,
+        backoff: None,
+        key: None,
+        key_global: false,
+        docs: None,
+        reveal: None,
     };
     menu.items.push(config_item);
 }
@@ -490,6 +813,13 @@ pub fn append_dx_menu(menu: &mut MenuState) {
             enhanced_terminal: None,
             form: None,
             plugin_list: false,
+            timeout: None,
+            retries: None,
+            backoff: None,
+            key: None,
+            key_global: false,
+            docs: None,
+            reveal: None,
         },
         MenuItem {
             name: "Doctor (full)".to_string(),
@@ -504,6 +834,13 @@ pub fn append_dx_menu(menu: &mut MenuState) {
             enhanced_terminal: None,
             form: None,
             plugin_list: false,
+            timeout: None,
+            retries: None,
+            backoff: None,
+            key: None,
+            key_global: false,
+            docs: None,
+            reveal: None,
         },
     ];
     let dx_folder = MenuItem {
@@ -519,10 +856,31 @@ pub fn append_dx_menu(menu: &mut MenuState) {
         enhanced_terminal: None,
         form: None,
         plugin_list: false,
+        timeout: None,
+        retries: None,
+        backoff: None,
+        key: None,
+        key_global: false,
+        docs: None,
+        reveal: None,
     };
     menu.items.push(dx_folder);
 }
 
+/// Menu file names looked up in the project root, most preferred first.
+pub const MENU_FILE_NAMES: [&str; 16] = [
+    "dx.yaml", "dx.yml", "dx.toml", "dx.json",
+    "DX.yaml", "DX.yml", "DX.toml", "DX.json",
+    "menu.yaml", "menu.yml", "menu.toml", "menu.json",
+    "Menu.yaml", "Menu.yml", "Menu.toml", "Menu.json",
+];
+
+/// The first menu file present in `dir`.
+#[must_use]
+pub fn find_menu_file(dir: &Path) -> Option<std::path::PathBuf> {
+    MENU_FILE_NAMES.iter().map(|n| dir.join(n)).find(|p| p.exists())
+}
+
 // == NEW UNIFIED FORMAT LOADING ==
 /// Loads DX configuration file from the specified path.
 /// 
@@ -537,9 +895,13 @@ pub fn load_dx_file(path: &Path) -> Result<DxFile> {
     {
         match serde_yaml::from_str::<DxFile>(&contents) {
             Ok(dx_file) => return Ok(dx_file),
-            Err(_) => {
-                // If unified format fails, try old menu format as fallback
+            Err(unified) => {
+                // If unified format fails, try old menu format as fallback; a file
+                // without `items` is a unified one with a bad `config:` or `menu:`
                 match serde_yaml::from_str::<MenuConfig>(&contents) {
+                    Ok(menu_config) if menu_config.items.is_empty() => {
+                        return Err(unified.into());
+                    }
                     Ok(menu_config) => {
                         return Ok(DxFile {
                             config: None,
@@ -553,9 +915,13 @@ pub fn load_dx_file(path: &Path) -> Result<DxFile> {
     } else if path.extension().and_then(|s| s.to_str()) == Some("toml") {
         match toml::from_str::<DxFile>(&contents) {
             Ok(dx_file) => return Ok(dx_file),
-            Err(_) => {
-                // If unified format fails, try old menu format as fallback
+            Err(unified) => {
+                // If unified format fails, try old menu format as fallback; a file
+                // without `items` is a unified one with a bad `config:` or `menu:`
                 match toml::from_str::<MenuConfig>(&contents) {
+                    Ok(menu_config) if menu_config.items.is_empty() => {
+                        return Err(unified.into());
+                    }
                     Ok(menu_config) => {
                         return Ok(DxFile {
                             config: None,
@@ -569,9 +935,13 @@ pub fn load_dx_file(path: &Path) -> Result<DxFile> {
     } else if path.extension().and_then(|s| s.to_str()) == Some("json") {
         match serde_json::from_str::<DxFile>(&contents) {
             Ok(dx_file) => return Ok(dx_file),
-            Err(_) => {
-                // If unified format fails, try old menu format as fallback
+            Err(unified) => {
+                // If unified format fails, try old menu format as fallback; a file
+                // without `items` is a unified one with a bad `config:` or `menu:`
                 match serde_json::from_str::<MenuConfig>(&contents) {
+                    Ok(menu_config) if menu_config.items.is_empty() => {
+                        return Err(unified.into());
+                    }
                     Ok(menu_config) => {
                         return Ok(DxFile {
                             config: None,
@@ -4,8 +4,11 @@ use ratatui::layout::Rect;
 use ratatui::style::{Color, Style};
 use ratatui::text::Text;
 use ratatui::widgets::{Paragraph, Wrap};
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 #[must_use]
 pub fn read_motd_file(path: &Path) -> Option<(Vec<String>, bool)> {
@@ -96,3 +99,205 @@ pub fn render_motd(
         f.render_widget(motd_para, area);
     }
 }
+
+/// Shown in place of a template value that has not been evaluated yet.
+pub const PENDING: &str = "…";
+
+/// Template names filled in by dx itself (see [`builtin_vars`]).
+pub const BUILTIN_NAMES: [&str; 5] = ["user", "date", "project.name", "git.branch", "git.dirty"];
+
+/// Names that are placeholders in MOTD.md: the built-in ones and the `[motd] vars` keys.
+/// Any other `{{…}}` (say, a Handlebars snippet) is shown as written.
+#[must_use]
+pub fn template_names(commands: &HashMap<String, String>) -> Vec<String> {
+    BUILTIN_NAMES
+        .iter()
+        .map(|n| (*n).to_string())
+        .chain(commands.keys().cloned())
+        .collect()
+}
+
+/// Fill the placeholders of one line; also returns whether it had any.
+fn render_line(line: &str, names: &[String], vars: &HashMap<String, String>) -> (String, bool) {
+    let mut out = String::with_capacity(line.len());
+    let mut found = false;
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let end = start + 2 + len + 2;
+        let name = rest[start + 2..start + 2 + len].trim();
+        out.push_str(&rest[..start]);
+        if names.iter().any(|n| n == name) {
+            found = true;
+            out.push_str(vars.get(name).map_or(PENDING, String::as_str));
+        } else {
+            out.push_str(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    (out, found)
+}
+
+/// Whether MOTD lines contain a `{{name}}` placeholder for one of `names`.
+#[must_use]
+pub fn is_template(lines: &[String], names: &[String]) -> bool {
+    lines
+        .iter()
+        .any(|l| l.contains("{{") && render_line(l, names, &HashMap::new()).1)
+}
+
+/// Replace `{{name}}` (spaces inside the braces allowed) for each of `names` with its value
+/// in `vars`; names without a value yet become [`PENDING`].
+#[must_use]
+pub fn render_template(
+    lines: &[String],
+    names: &[String],
+    vars: &HashMap<String, String>,
+) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| render_line(line, names, vars).0)
+        .collect()
+}
+
+fn git_output(args: &[&str]) -> Option<String> {
+    let out = Command::new("git")
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim_end().to_string())
+}
+
+/// Built-in template values: `user`, `date`, `project.name`, `git.branch` and `git.dirty`
+/// (`dirty` or `clean`; both git values are empty outside a repository).
+#[must_use]
+pub fn builtin_vars() -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_default();
+    vars.insert("user".to_string(), user);
+    vars.insert("date".to_string(), today());
+    let root = git_output(&["rev-parse", "--show-toplevel"])
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_dir().ok());
+    let project = root
+        .as_deref()
+        .and_then(Path::file_name)
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    vars.insert("project.name".to_string(), project);
+    let branch = git_output(&["rev-parse", "--abbrev-ref", "HEAD"]).unwrap_or_default();
+    let dirty = match git_output(&["status", "--porcelain"]) {
+        Some(s) if s.is_empty() => "clean",
+        Some(_) => "dirty",
+        None => "",
+    };
+    vars.insert("git.branch".to_string(), branch);
+    vars.insert("git.dirty".to_string(), dirty.to_string());
+    vars
+}
+
+/// Today's date as `YYYY-MM-DD` in local time.
+#[must_use]
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    #[cfg(unix)]
+    {
+        // SAFETY: localtime_r only writes into the zeroed `tm` we own
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        let t = secs as libc::time_t;
+        if !unsafe { libc::localtime_r(&t, &mut tm) }.is_null() {
+            return format!(
+                "{:04}-{:02}-{:02}",
+                tm.tm_year + 1900,
+                tm.tm_mon + 1,
+                tm.tm_mday
+            );
+        }
+    }
+    // UTC civil date from days since the epoch (Howard Hinnant's algorithm)
+    let z = secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Run the configured template commands concurrently; each value is the first non-empty
+/// line of stdout, or `?` when the command fails or outlives `timeout`.
+pub async fn command_vars(
+    commands: &HashMap<String, String>,
+    timeout: Duration,
+) -> HashMap<String, String> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (name, cmd) in commands {
+        let (name, cmd) = (name.clone(), cmd.clone());
+        tasks.spawn(async move {
+            let run = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(&cmd)
+                .stdin(Stdio::null())
+                .stderr(Stdio::null())
+                .kill_on_drop(true)
+                .output();
+            let value = match tokio::time::timeout(timeout, run).await {
+                Ok(Ok(out)) if out.status.success() => String::from_utf8_lossy(&out.stdout)
+                    .lines()
+                    .map(str::trim)
+                    .find(|l| !l.is_empty())
+                    .unwrap_or("")
+                    .to_string(),
+                _ => "?".to_string(),
+            };
+            (name, value)
+        });
+    }
+    let mut vars = HashMap::new();
+    while let Some(res) = tasks.join_next().await {
+        if let Ok((name, value)) = res {
+            vars.insert(name, value);
+        }
+    }
+    vars
+}
+
+/// Evaluate built-in and command values in the background, again every `refresh` if set.
+/// Each complete set of values is sent on the returned channel.
+#[must_use]
+pub fn spawn_template_refresh(
+    commands: HashMap<String, String>,
+    timeout: Duration,
+    refresh: Option<Duration>,
+) -> tokio::sync::mpsc::Receiver<HashMap<String, String>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(4);
+    tokio::spawn(async move {
+        loop {
+            let mut vars = tokio::task::spawn_blocking(builtin_vars)
+                .await
+                .unwrap_or_default();
+            vars.extend(command_vars(&commands, timeout).await);
+            if tx.send(vars).await.is_err() {
+                break;
+            }
+            match refresh {
+                Some(every) => tokio::time::sleep(every).await,
+                None => break,
+            }
+        }
+    });
+    rx
+}
@@ -0,0 +1,144 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+use crate::exec::format_duration;
+
+/// How a finished command is announced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyMethod {
+    /// OSC 9 desktop notification (iTerm2, WezTerm, Windows Terminal, kitty)
+    Osc9,
+    /// OSC 777 `notify` (VTE terminals, foot, urxvt)
+    Osc777,
+    /// Plain terminal bell
+    Bell,
+    /// Shell command run with `DX_NOTIFY_*` variables set
+    Command(String),
+}
+
+impl NotifyMethod {
+    /// Parse `osc9`, `osc777`, `bell` or `auto` (picked from the environment).
+    #[must_use]
+    pub fn parse(spec: &str) -> Option<Self> {
+        match spec.trim().to_ascii_lowercase().as_str() {
+            "osc9" => Some(Self::Osc9),
+            "osc777" => Some(Self::Osc777),
+            "bell" => Some(Self::Bell),
+            "auto" | "" => Some(Self::detect()),
+            _ => None,
+        }
+    }
+
+    /// OSC 777 on terminals known to support it, OSC 9 elsewhere.
+    #[must_use]
+    pub fn detect() -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        if std::env::var_os("VTE_VERSION").is_some()
+            || term.starts_with("foot")
+            || term.starts_with("rxvt")
+        {
+            Self::Osc777
+        } else {
+            Self::Osc9
+        }
+    }
+}
+
+/// Notification settings: commands running at least `after` trigger `method` when done.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notifier {
+    pub after: Duration,
+    pub method: NotifyMethod,
+}
+
+/// What is reported about a finished command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub title: String,
+    /// Exit code; `None` when the command was terminated
+    pub exit_status: Option<i32>,
+    pub duration: Duration,
+}
+
+impl Completion {
+    /// "succeeded", "failed (exit 2)" or "terminated".
+    #[must_use]
+    pub fn status(&self) -> String {
+        match self.exit_status {
+            Some(0) => "succeeded".to_string(),
+            Some(code) => format!("failed (exit {code})"),
+            None => "terminated".to_string(),
+        }
+    }
+
+    /// Notification body, e.g. "succeeded in 12m 3s".
+    #[must_use]
+    pub fn body(&self) -> String {
+        format!("{} in {}", self.status(), format_duration(self.duration))
+    }
+}
+
+impl Notifier {
+    #[must_use]
+    pub fn should_notify(&self, duration: Duration) -> bool {
+        duration >= self.after
+    }
+
+    /// Announce `done` if it ran long enough. Escape sequences go to stdout; a notifier
+    /// command runs in the background so a slow notifier never blocks the UI.
+    pub fn notify(&self, done: &Completion) {
+        if !self.should_notify(done.duration) {
+            return;
+        }
+        if let NotifyMethod::Command(cmd) = &self.method {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(cmd)
+                .env("DX_NOTIFY_TITLE", &done.title)
+                .env("DX_NOTIFY_STATUS", done.status())
+                .env("DX_NOTIFY_BODY", done.body())
+                .env("DX_NOTIFY_DURATION", format_duration(done.duration))
+                .env(
+                    "DX_NOTIFY_EXIT_CODE",
+                    done.exit_status.map(|c| c.to_string()).unwrap_or_default(),
+                )
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null());
+            if let Ok(mut child) = command.spawn() {
+                std::thread::spawn(move || {
+                    let _ = child.wait();
+                });
+            }
+            return;
+        }
+        if let Some(seq) = escape_sequence(&self.method, done) {
+            let mut out = std::io::stdout();
+            let _ = out.write_all(&seq);
+            let _ = out.flush();
+        }
+    }
+}
+
+/// Terminal escape sequence announcing `done`; `None` for command notifiers.
+#[must_use]
+pub fn escape_sequence(method: &NotifyMethod, done: &Completion) -> Option<Vec<u8>> {
+    let title = sanitize(&done.title);
+    let body = sanitize(&done.body());
+    match method {
+        NotifyMethod::Osc9 => Some(format!("\x1b]9;{title}: {body}\x07").into_bytes()),
+        // Fields are `;`-separated, so the title cannot contain one
+        NotifyMethod::Osc777 => {
+            Some(format!("\x1b]777;notify;{};{body}\x07", title.replace(';', ",")).into_bytes())
+        }
+        NotifyMethod::Bell => Some(b"\x07".to_vec()),
+        NotifyMethod::Command(_) => None,
+    }
+}
+
+/// Drop control characters that would end or corrupt an OSC string.
+fn sanitize(s: &str) -> String {
+    s.chars().filter(|c| !c.is_control()).collect()
+}
@@ -3,13 +3,131 @@
 
 use crate::App;
 use crate::Screen;
+use crate::config::{AppConfig, ConfigLayer, ConfigSource, ThemePicker};
+use crate::markdown::{MdLayout, render_markdown};
+use crate::theme::{ThemeChoice, ThemeTokens, list_themes};
 use ratatui::Frame;
-use ratatui::layout::Rect;
-use ratatui::text::{Line, Text};
-use ratatui::widgets::{Block, Borders, Padding, Paragraph};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Padding, Paragraph};
+use std::path::Path;
+
+const PREVIEW_MD: &str = "# Deploy\n\n## Checklist\n\n- Run `make check` first\n- Read the [runbook](#deploy)\n\n```sh\n# ship it\ndeploy --env prod --replicas 3\n```\n";
+
+/// Theme list on the left, a sample of the menu, Markdown and command output on the right.
+fn render_picker(f: &mut Frame, area: Rect, picker: &ThemePicker, theme: &ThemeTokens) {
+    let cols = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Length(28), Constraint::Min(20)])
+        .split(area);
+    let items: Vec<ListItem> = picker
+        .choices
+        .iter()
+        .map(|c| {
+            let mut spans = vec![Span::raw(c.name.clone())];
+            if c.file.is_some() {
+                spans.push(Span::styled(
+                    "  .dx-theme",
+                    Style::default().fg(theme.text_muted),
+                ));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(theme.border))
+                .title("Theme (Enter save, Esc cancel)"),
+        )
+        .highlight_style(
+            Style::default()
+                .fg(theme.selection_fg)
+                .bg(theme.selection_bg),
+        );
+    f.render_stateful_widget(
+        list,
+        cols[0],
+        &mut ListState::default().with_selected(Some(picker.selected)),
+    );
+
+    let bar = |c| Span::styled("│ ", Style::default().fg(c));
+    let mut lines: Vec<Line> = vec![
+        Line::from(vec![
+            bar(theme.selection_fg),
+            Span::styled("Build \u{2630}", Style::default().fg(theme.menu_title)),
+        ]),
+        Line::from(vec![
+            bar(theme.selection_fg),
+            Span::styled(
+                "cargo build --release",
+                Style::default()
+                    .fg(theme.menu_action)
+                    .add_modifier(Modifier::DIM),
+            ),
+        ]),
+        Line::from(vec![
+            bar(theme.selection_fg),
+            Span::styled(
+                "Compile the project",
+                Style::default()
+                    .fg(theme.menu_desc)
+                    .add_modifier(Modifier::DIM),
+            ),
+        ]),
+        Line::from(""),
+    ];
+    let layout = MdLayout {
+        width: Some(usize::from(cols[1].width.saturating_sub(4))),
+        ..MdLayout::default()
+    };
+    lines.extend(render_markdown(PREVIEW_MD, theme, layout).text.lines);
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "$ cargo test",
+        Style::default()
+            .fg(theme.text_primary)
+            .add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        "running 12 tests",
+        Style::default().fg(theme.text_muted),
+    )));
+    lines.push(Line::from(Span::styled(
+        "warning: unused variable `x`",
+        Style::default().fg(theme.accent_warning),
+    )));
+    lines.push(Line::from(Span::styled(
+        "error: test `deploy` failed",
+        Style::default().fg(theme.accent_danger),
+    )));
+    lines.push(Line::from(Span::styled(
+        "test result: ok. 11 passed",
+        Style::default().fg(theme.accent_success),
+    )));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        " dx · main · 12 tests ",
+        Style::default().fg(theme.status_fg).bg(theme.status_bg),
+    )));
+    let preview = Paragraph::new(Text::from(lines)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .title("Preview")
+            .padding(Padding::new(1, 1, 0, 0)),
+    );
+    f.render_widget(preview, cols[1]);
+}
 
 pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
     if let Screen::Config(cfg) = &mut app.screen {
+        if let Some(picker) = &cfg.picker {
+            render_picker(f, area, picker, &app.theme);
+            return;
+        }
         let title = "Configuration (Save: s, Toggle: keys, Esc/q back)";
         let mut lines: Vec<Line> = Vec::new();
         lines.push(Line::from(format!(
@@ -62,15 +180,32 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                 .clone()
                 .unwrap_or_else(|| "<inherit>".into())
         );
-        lines.push(Line::from(s1));
-        lines.push(Line::from(s2));
-        lines.push(Line::from(s3));
-        lines.push(Line::from(s4));
-        lines.push(Line::from(s5));
-        lines.push(Line::from(s6));
-        lines.push(Line::from(s7));
+        // A layer above the edited file (dx file `config:`, DX_* or --set) wins on start
+        let saved_to = if cfg.is_global {
+            ConfigSource::Global
+        } else {
+            ConfigSource::Project
+        };
+        let warn = Style::default().fg(app.theme.accent_warning);
+        let row = |text: String, is_set: &dyn Fn(&AppConfig) -> bool| {
+            let mut spans = vec![Span::raw(text)];
+            if let Some(source) = app.settings.overridden_by(saved_to, is_set) {
+                spans.push(Span::styled(
+                    format!("  (overridden by {})", source.label()),
+                    warn,
+                ));
+            }
+            Line::from(spans)
+        };
+        lines.push(row(s1, &|c| c.motd_wrap.is_some()));
+        lines.push(row(s2, &|c| c.markdown_enabled.is_some()));
+        lines.push(row(s3, &|c| c.output_dim.is_some()));
+        lines.push(row(s4, &|c| c.theme.is_some() || c.theme_file.is_some()));
+        lines.push(row(s5, &|c| c.telemetry.is_some()));
+        lines.push(row(s6, &|c| c.asciinema.is_some()));
+        lines.push(row(s7, &|c| c.motd_color.is_some()));
         lines.push(Line::from(""));
-        lines.push(Line::from("Keys: 1-3 toggle booleans, t cycle theme, e toggle telemetry, a toggle asciinema, s save"));
+        lines.push(Line::from("Keys: 1-3 toggle booleans, t pick theme, e toggle telemetry, a toggle asciinema, s save"));
         if let Some(msg) = &cfg.message {
             lines.push(Line::from(msg.clone()));
         }
@@ -91,7 +226,81 @@ use crossterm::event::{KeyCode, KeyEvent};
 // use crate::config::open_config_state;
 // use std::path::PathBuf;
 
+/// Keys for the open theme picker; returns false when it is closed.
+fn handle_picker(app: &mut App, key: KeyEvent) -> bool {
+    let Screen::Config(cfg) = &mut app.screen else {
+        return false;
+    };
+    let Some(picker) = &mut cfg.picker else {
+        return false;
+    };
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            picker.selected = picker.selected.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            if picker.selected + 1 < picker.choices.len() {
+                picker.selected += 1;
+            }
+        }
+        KeyCode::Enter => {
+            if let Some(choice) = picker.choices.get(picker.selected).cloned() {
+                cfg.picker = None;
+                cfg.cfg.theme = Some(choice.name.clone());
+                cfg.cfg.theme_file = choice.file.as_ref().map(|p| p.display().to_string());
+                let saved_to = if cfg.is_global {
+                    ConfigSource::Global
+                } else {
+                    ConfigSource::Project
+                };
+                let overridden = app
+                    .settings
+                    .overridden_by(saved_to, |c| c.theme.is_some() || c.theme_file.is_some());
+                let saved = save_app_config(&cfg.path, &cfg.cfg);
+                if saved.is_ok() {
+                    app.settings.set_layer(ConfigLayer {
+                        source: saved_to,
+                        path: Some(cfg.path.clone()),
+                        cfg: cfg.cfg.clone(),
+                    });
+                }
+                cfg.message = Some(match (saved, overridden) {
+                    (Ok(()), None) => format!("Theme '{}' saved.", choice.name),
+                    (Ok(()), Some(source)) => format!(
+                        "Theme '{}' saved, but the {} setting overrides it on the next start.",
+                        choice.name,
+                        source.label()
+                    ),
+                    (Err(e), _) => format!("Save failed: {e}"),
+                });
+                app.theme_dark = !choice.name.to_ascii_lowercase().ends_with("light");
+                app.theme_auto = false;
+                app.theme_name = choice.name;
+                app.theme_file = choice.file;
+            }
+            return true;
+        }
+        KeyCode::Esc | KeyCode::Char('q') => {
+            let original = picker.original;
+            cfg.picker = None;
+            crate::apply_theme(app, original);
+            return true;
+        }
+        _ => return true,
+    }
+    // Live preview of the highlighted theme
+    let choice: Option<ThemeChoice> = picker.choices.get(picker.selected).cloned();
+    if let Some(choice) = choice {
+        let tokens = crate::load_theme_choice(app, &choice.name, choice.file.as_deref());
+        crate::apply_theme(app, tokens);
+    }
+    true
+}
+
 pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
+    if handle_picker(app, key) {
+        return Ok(false);
+    }
     if let Screen::Config(cfg) = &mut app.screen {
         match (key.code, key.modifiers) {
             (KeyCode::Esc, _) | (KeyCode::Char('q'), _) => {
@@ -109,6 +318,16 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                     cfg.message = Some(format!("Save failed: {e}"));
                 } else {
                     cfg.message = Some("Saved.".to_string());
+                    // Opening the screen again shows what was saved
+                    app.settings.set_layer(ConfigLayer {
+                        source: if cfg.is_global {
+                            ConfigSource::Global
+                        } else {
+                            ConfigSource::Project
+                        },
+                        path: Some(cfg.path.clone()),
+                        cfg: cfg.cfg.clone(),
+                    });
                 }
             }
             (KeyCode::Char('1'), _) => {
@@ -121,13 +340,31 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                 cfg.cfg.output_dim = Some(!cfg.cfg.output_dim.unwrap_or(true));
             }
             (KeyCode::Char('t'), _) => {
-                cfg.cfg.theme = Some(match cfg.cfg.theme.as_deref() {
-                    Some("dark") => "light".into(),
-                    _ => "dark".into(),
+                let mut choices = list_themes(app.theme_dir.as_deref().map(Path::new));
+                if let Some(file) = &app.theme_file
+                    && !choices.iter().any(|c| c.file.as_ref() == Some(file))
+                {
+                    choices.push(ThemeChoice {
+                        name: app.theme_name.clone(),
+                        file: Some(file.clone()),
+                    });
+                }
+                let selected = choices
+                    .iter()
+                    .position(|c| match (&c.file, &app.theme_file) {
+                        (Some(a), Some(b)) => a == b,
+                        (None, None) => c.name.eq_ignore_ascii_case(&app.theme_name),
+                        _ => false,
+                    })
+                    .unwrap_or(0);
+                cfg.picker = Some(ThemePicker {
+                    choices,
+                    selected,
+                    original: app.theme,
                 });
             }
             (KeyCode::Char('e'), _) => {
-                let mut t = cfg.cfg.telemetry.clone().unwrap_or(crate::TelemetryConfig {
+                let mut t = cfg.cfg.telemetry.clone().unwrap_or(crate::config::TelemetryConfig {
                     enabled: false,
                     endpoint: None,
                 });
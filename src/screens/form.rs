@@ -102,7 +102,12 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                     // run command
                     let title = state.title.clone();
                     let _ = &state;
-                    let _ = crate::start_command(app, &title, &cmd);
+                    let _ = crate::start_command(
+                        app,
+                        &title,
+                        &cmd,
+                        crate::menu::RunPolicy::default(),
+                    );
                     app.needs_clear = true;
                 }
             }
@@ -10,7 +10,10 @@ use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, List, ListItem, Padding};
 use std::path::Path;
 
-use crate::menu::{MenuItem, submenu_at, build_terminal_alias};
+use crate::menu::{
+    MenuItem, ShortcutMatch, build_terminal_alias, docs_root, match_shortcut, parse_key_chord,
+    submenu_at, submenu_at_mut,
+};
 use crate::theme::ThemeTokens;
 use crate::{App, PAD_X, PAD_Y, Screen};
 // use crate::menu::MenuItem;
@@ -18,20 +21,23 @@ use crate::{
     open_config_state, open_file_view, passthrough_command, start_command, start_command_enhanced,
 };
 
-fn count_folders_and_cmds(item: &MenuItem) -> (usize, usize) {
+fn count_folders_and_cmds(item: &MenuItem) -> (usize, usize, usize) {
     // Count recursively: folders = nodes with non-empty children; cmds = nodes with Some(cmd)
-    fn dfs(it: &MenuItem, acc: &mut (usize, usize)) {
+    fn dfs(it: &MenuItem, acc: &mut (usize, usize, usize)) {
         if !it.items.is_empty() {
             acc.0 = acc.0.saturating_add(1);
         }
         if it.cmd.is_some() {
             acc.1 = acc.1.saturating_add(1);
         }
+        if it.file.is_some() {
+            acc.2 = acc.2.saturating_add(1);
+        }
         for c in &it.items {
             dfs(c, acc);
         }
     }
-    let mut acc = (0usize, 0usize);
+    let mut acc = (0usize, 0usize, 0usize);
     for c in &item.items {
         dfs(c, &mut acc);
     }
@@ -63,16 +69,28 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                 break;
             }
         }
-        let title = if crumbs.is_empty() {
-            "Menu".to_string()
-        } else {
-            format!("Menu — {}", crumbs.join(" > "))
-        };
+        let mut title = String::new();
+        // Show a half-typed chord so the user knows dx is waiting for the next key
+        if !app.menu_chord.is_empty() {
+            let typed: Vec<String> = app.menu_chord.iter().map(char::to_string).collect();
+            title.push_str(&format!(" [{} …]", typed.join(" ")));
+        }
+        if let Some(query) = &app.docs_query {
+            title.push_str(&format!(" — search docs: /{}▏", query));
+        } else if docs_root(&menu.items, &menu.path).is_some() {
+            title.push_str(" · / search");
+        }
+        let mut title_spans = vec![Span::raw("Menu")];
+        if !crumbs.is_empty() {
+            title_spans.push(Span::raw(" — "));
+            title_spans.push(Span::styled(crumbs.join(" > "), app.theme.breadcrumbs));
+        }
+        title_spans.push(Span::raw(title));
         let list = List::new(list_items)
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(title)
+                    .title(Line::from(title_spans))
                     .padding(Padding::new(PAD_X, PAD_X, PAD_Y, PAD_Y)),
             )
             .highlight_style(Style::default().bg(highlight_bg));
@@ -115,6 +133,13 @@ fn make_menu_list_item<'a>(
     if !item.items.is_empty() {
         title_spans.push(Span::styled(" \u{2630}", Style::default().fg(title_color)));
     }
+    if let Some(keys) = item.key.as_deref().and_then(parse_key_chord) {
+        let hint: Vec<String> = keys.iter().map(char::to_string).collect();
+        title_spans.push(Span::styled(
+            format!("  [{}]", hint.join(" ")),
+            Style::default().fg(action_color),
+        ));
+    }
 
     let mut action_text = if let Some(cmd) = &item.cmd {
         cmd.clone()
@@ -122,9 +147,15 @@ fn make_menu_list_item<'a>(
         format!("file: {file}")
     } else if item.form.is_some() {
         "form".to_string()
+    } else if let Some(dir) = &item.docs {
+        format!("docs: {dir}")
     } else if !item.items.is_empty() {
-        let (folders, cmds) = count_folders_and_cmds(item);
-        format!("{} folders, {} commands", folders, cmds)
+        let (folders, cmds, files) = count_folders_and_cmds(item);
+        if cmds == 0 && files > 0 {
+            format!("{} folders, {} files", folders, files)
+        } else {
+            format!("{} folders, {} commands", folders, cmds)
+        }
     } else {
         String::new()
     };
@@ -170,8 +201,177 @@ fn make_menu_list_item<'a>(
     ListItem::new(Text::from(lines))
 }
 
+/// Activate the selected item: enter a folder, open a file/form, or run its command.
+fn activate_selected(app: &mut App) -> Result<()> {
+    let Screen::Menu(menu) = &mut app.screen else {
+        return Ok(());
+    };
+    let current = submenu_at(&menu.items, &menu.path);
+    if let Some(item) = current.get(menu.selected_index).cloned() {
+        if let Some(dir) = &item.docs {
+            // Re-scan on every visit so new and renamed documents show up
+            let children = crate::docs::docs_items(Path::new(dir));
+            if !children.is_empty() {
+                submenu_at_mut(&mut menu.items, &menu.path)[menu.selected_index].items = children;
+                menu.path.push(menu.selected_index);
+                menu.selected_index = 0;
+            }
+        } else if !item.items.is_empty() {
+            menu.path.push(menu.selected_index);
+            menu.selected_index = 0;
+        } else if let Some(form) = item.form.clone() {
+            let state = crate::screens::form::from_spec(&form);
+            app.screen_stack
+                .push(std::mem::replace(&mut app.screen, Screen::Form(state)));
+            app.needs_clear = true;
+        } else if let Some(file) = item.file {
+            let mut view = open_file_view(Path::new(&file));
+            if let Some((query, nth)) = item.reveal {
+                view.search = Some(crate::search::SearchState::reveal(query, nth));
+            }
+            app.screen_stack
+                .push(std::mem::replace(&mut app.screen, Screen::Output(view)));
+            app.needs_clear = true;
+        } else if let Some(cmd) = item.cmd.clone() {
+            let external = item.external.unwrap_or(false);
+            let enhanced_terminal = item.enhanced_terminal.unwrap_or(false);
+            if external {
+                passthrough_command(app, &item.name, &cmd)?;
+            } else if enhanced_terminal {
+                start_command_enhanced(app, &item.name, &cmd, item.run_policy())?;
+                app.needs_clear = true;
+            } else {
+                start_command(app, &item.name, &cmd, item.run_policy())?;
+                app.needs_clear = true;
+            }
+        } else if item.plugin_list {
+            // Build dynamic submenu of running plugins
+            let mut children: Vec<crate::menu::MenuItem> = Vec::new();
+            for rt in &app.plugin_overlays {
+                let m = rt.meta();
+                children.push(crate::menu::MenuItem {
+                    name: m.name.to_string(),
+                    desc: Some(format!("{} ({})", m.id, m.version)),
+                    alias: None,
+                    aliases: None,
+                    cmd: None,
+                    file: None,
+                    items: Vec::new(),
+                    capture: None,
+                    external: None,
+                    enhanced_terminal: None,
+                    form: None,
+                    plugin_list: false,
+                    timeout: None,
+                    retries: None,
+                    backoff: None,
+                    key: None,
+                    key_global: false,
+                    docs: None,
+                    reveal: None,
+                });
+            }
+            if !children.is_empty() {
+                let mut folder = item.clone();
+                folder.items = children;
+                menu.path.push(menu.selected_index);
+                menu.selected_index = 0;
+            }
+        } else if item.name == "Configuration"
+            || item.alias.as_deref() == Some("config")
+        {
+            app.screen_stack.push(std::mem::replace(
+                &mut app.screen,
+                Screen::Config(open_config_state(&app.settings)),
+            ));
+            app.needs_clear = true;
+        }
+    }
+    Ok(())
+}
+
+/// Search every document of the enclosing docs folder and show the hits as its first entry.
+fn search_docs(app: &mut App, query: &str) {
+    let Screen::Menu(menu) = &mut app.screen else {
+        return;
+    };
+    let Some((docs_path, dir)) = docs_root(&menu.items, &menu.path) else {
+        return;
+    };
+    // Lay documents out as the viewer will, in the area the menu is drawn in
+    let layout = app
+        .last_content_area
+        .map(|area| crate::screens::output::doc_layout(area, true, app.image_protocol.is_some(), None))
+        .unwrap_or_default();
+    let hits = crate::docs::search_docs(Path::new(&dir), query, &layout);
+    let mut children = vec![crate::docs::results_item(query, &hits)];
+    children.extend(crate::docs::docs_items(Path::new(&dir)));
+    let Some((&last, parent)) = docs_path.split_last() else {
+        return;
+    };
+    submenu_at_mut(&mut menu.items, parent)[last].items = children;
+    menu.path = docs_path;
+    if !hits.is_empty() {
+        menu.path.push(0);
+    }
+    menu.selected_index = 0;
+}
+
 pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
+    // Docs search prompt takes all keys while open
+    if let Some(query) = &mut app.docs_query {
+        match key.code {
+            KeyCode::Esc => app.docs_query = None,
+            KeyCode::Enter => {
+                let query = app.docs_query.take().unwrap_or_default();
+                if !query.is_empty() {
+                    search_docs(app, &query);
+                }
+            }
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => query.push(c),
+            _ => {}
+        }
+        return Ok(false);
+    }
     if let Screen::Menu(menu) = &mut app.screen {
+        // Item shortcuts (single keys and chords); unbound keys fall through to the built-ins
+        if app.menu_cmd.is_none() {
+            if let KeyCode::Char(ch) = key.code
+                && !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+            {
+                app.menu_chord.push(ch);
+                let mut found = match_shortcut(&menu.items, &menu.path, &app.menu_chord);
+                if found == ShortcutMatch::NoMatch && app.menu_chord.len() > 1 {
+                    // A broken chord may still start a new one
+                    app.menu_chord = vec![ch];
+                    found = match_shortcut(&menu.items, &menu.path, &app.menu_chord);
+                }
+                match found {
+                    ShortcutMatch::Item(target) => {
+                        app.menu_chord.clear();
+                        if let Some((&last, parent)) = target.split_last() {
+                            menu.path = parent.to_vec();
+                            menu.selected_index = last;
+                        }
+                        activate_selected(app)?;
+                        return Ok(false);
+                    }
+                    ShortcutMatch::Pending => return Ok(false),
+                    ShortcutMatch::NoMatch => app.menu_chord.clear(),
+                }
+            } else if !app.menu_chord.is_empty() {
+                // Any other key abandons a half-typed chord; Esc does nothing else
+                app.menu_chord.clear();
+                if key.code == KeyCode::Esc {
+                    return Ok(false);
+                }
+            }
+        }
         match (key.code, key.modifiers) {
             // Esc/q always go back; at root they exit. Ctrl+C/Ctrl+Q exit immediately.
             (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Ok(true),
@@ -189,6 +389,12 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                 }
             }
 
+            (KeyCode::Char('/'), _)
+                if app.menu_cmd.is_none() && docs_root(&menu.items, &menu.path).is_some() =>
+            {
+                app.docs_query = Some(String::new());
+            }
+
             // Vim-style :q Enter
             (KeyCode::Char(':'), _) => {
                 app.menu_cmd = Some(String::new());
@@ -209,70 +415,7 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                         return Ok(true);
                     }
                 } else {
-                    // Enter submenu, run item, or open form
-                    let current = submenu_at(&menu.items, &menu.path);
-                    if let Some(item) = current.get(menu.selected_index).cloned() {
-                        if !item.items.is_empty() {
-                            menu.path.push(menu.selected_index);
-                            menu.selected_index = 0;
-                        } else if let Some(form) = item.form.clone() {
-                            let state = crate::screens::form::from_spec(&form);
-                            app.screen_stack
-                                .push(std::mem::replace(&mut app.screen, Screen::Form(state)));
-                            app.needs_clear = true;
-                        } else if let Some(file) = item.file {
-                            let view = open_file_view(Path::new(&file));
-                            app.screen_stack
-                                .push(std::mem::replace(&mut app.screen, Screen::Output(view)));
-                            app.needs_clear = true;
-                        } else if let Some(cmd) = item.cmd {
-                            let external = item.external.unwrap_or(false);
-                            let enhanced_terminal = item.enhanced_terminal.unwrap_or(false);
-                            if external {
-                                passthrough_command(app, &item.name, &cmd)?;
-                            } else if enhanced_terminal {
-                                start_command_enhanced(app, &item.name, &cmd)?;
-                                app.needs_clear = true;
-                            } else {
-                                start_command(app, &item.name, &cmd)?;
-                                app.needs_clear = true;
-                            }
-                        } else if item.plugin_list {
-                            // Build dynamic submenu of running plugins
-                            let mut children: Vec<crate::menu::MenuItem> = Vec::new();
-                            for rt in &app.plugin_overlays {
-                                let m = rt.meta();
-                                children.push(crate::menu::MenuItem {
-                                    name: m.name.to_string(),
-                                    desc: Some(format!("{} ({})", m.id, m.version)),
-                                    alias: None,
-                                    aliases: None,
-                                    cmd: None,
-                                    file: None,
-                                    items: Vec::new(),
-                                    capture: None,
-                                    external: None,
-                                    enhanced_terminal: None,
-                                    form: None,
-                                    plugin_list: false,
-                                });
-                            }
-                            if !children.is_empty() {
-                                let mut folder = item.clone();
-                                folder.items = children;
-                                menu.path.push(menu.selected_index);
-                                menu.selected_index = 0;
-                            }
-                        } else if item.name == "Configuration"
-                            || item.alias.as_deref() == Some("config")
-                        {
-                            app.screen_stack.push(std::mem::replace(
-                                &mut app.screen,
-                                Screen::Config(open_config_state()),
-                            ));
-                            app.needs_clear = true;
-                        }
-                    }
+                    activate_selected(app)?;
                 }
             }
 
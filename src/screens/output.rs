@@ -5,20 +5,31 @@
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::Frame;
-use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, Borders, Clear, Padding, Paragraph};
 use std::io::Write;
+use std::path::Path;
 use std::time::Instant;
 
 use crate::frame;
-use crate::markdown::markdown_to_text_with_links;
-use crate::menu::load_menu;
-use crate::{App, PAD_X, PAD_Y, SPINNER_FRAMES, Screen};
-use crate::{centered_rect_fixed, format_duration};
+use crate::highlight::highlight_lines;
+use crate::history::{self, DiffLine};
+use crate::images::{ImageProtocol, Placement};
+use crate::links::{DocHistory, DocPos, LinkTarget, resolve_link};
+use crate::markdown::{MdImage, MdLayout, current_section, outline, render_markdown};
+use crate::menu::{RunPolicy, load_menu};
+use crate::search::{self, SearchState};
+use crate::theme::ThemeTokens;
+use crate::{App, MdCache, OutputView, PAD_X, PAD_Y, SPINNER_FRAMES, Screen};
+use crate::centered_rect_fixed;
+use crate::exec::format_duration;
 use ansi_to_tui::IntoText;
 
+/// Shown next to a running command; every other key goes to the command.
+const STOP_HINT: &str = " · Ctrl+\\ stop";
+
 pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
     if let Screen::Output(view) = &mut app.screen {
         // Update viewport height and scroller
@@ -32,8 +43,47 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
         if let Some(md) = &view.md_content {
             let _vh = view.viewport_height.max(1);
             if app.markdown_enabled && view.render_markdown {
-                let (text, links) = markdown_to_text_with_links(md, &app.theme);
-                view.md_links = links;
+                // Outline panel on the left; the document keeps the rest
+                let (outline_area, area) = if view.outline.is_some() {
+                    let cols = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Length((area.width / 3).min(32)), Constraint::Min(10)])
+                        .split(area);
+                    app.last_content_area = Some(cols[1]);
+                    (Some(cols[0]), cols[1])
+                } else {
+                    (None, area)
+                };
+                let inner_w = area.width.saturating_sub(2 + PAD_X * 2);
+                let layout = doc_layout(area, view.wrap_enabled, app.image_protocol.is_some(), view.file_path.as_deref());
+                // Lay out again only when something it depends on changed; a document with
+                // images is also looked at once per image recheck to pick up edited files
+                let image_generation = crate::images::generation();
+                let stale = view.md_cache.as_ref().is_none_or(|c| {
+                    c.content != *md
+                        || c.layout != layout
+                        || c.image_generation != image_generation
+                        || (layout.images && md.contains("![") && c.built.elapsed() >= crate::images::RECHECK_INTERVAL)
+                });
+                if stale {
+                    view.md_cache = None;
+                }
+                let cache = view.md_cache.get_or_insert_with(|| MdCache {
+                    content: md.clone(),
+                    layout: layout.clone(),
+                    image_generation,
+                    built: Instant::now(),
+                    render: render_markdown(md, &app.theme, layout),
+                });
+                let rendered = cache.render.clone();
+                let mut text = rendered.text;
+                view.md_links = rendered.links;
+                view.md_link_spots = rendered.link_spots;
+                view.md_headings = rendered.headings;
+                view.runbook.blocks = rendered.blocks;
+                let doc_images = rendered.images;
+                decorate_blocks(view, &mut text, &app.theme);
+                highlight_link(view, &mut text, &app.theme);
                 let total_lines = text.lines.len() as u16;
                 view.md_footnote_start = if !view.md_links.is_empty() {
                     Some(total_lines.saturating_sub((view.md_links.len() as u16).saturating_add(2)))
@@ -44,7 +94,18 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                 if view.auto_scroll {
                     view.scroller.end();
                 }
+                apply_pending_jump(view);
                 view.scroll_y = view.scroller.scroll_y;
+                apply_search(view, &mut text, &app.theme);
+                if app.confirm.is_none() && app.image_protocol.is_some_and(|p| p != ImageProtocol::Blocks) {
+                    let inner = Rect {
+                        x: area.x + 1 + PAD_X,
+                        y: area.y + 1 + PAD_Y,
+                        width: inner_w,
+                        height: inner_h,
+                    };
+                    app.image_overlays = place_images(&doc_images, &text, inner, view.scroll_y, view.wrap_enabled);
+                }
                 let paragraph = if view.wrap_enabled {
                     Paragraph::new(text)
                         .wrap(ratatui::widgets::Wrap { trim: false })
@@ -52,26 +113,37 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                 } else {
                     Paragraph::new(text).scroll((view.scroll_y, 0))
                 };
+                let title = if view.runbook.blocks.is_empty() {
+                    view.title.clone()
+                } else {
+                    format!("{} · {{/}} block · r run", view.title)
+                };
                 let paragraph = paragraph.block(
                     Block::default()
                         .borders(Borders::ALL)
-                        .title(view.title.clone())
+                        .title(title)
                         .padding(Padding::new(PAD_X, PAD_X, PAD_Y, PAD_Y)),
                 );
                 f.render_widget(paragraph, area);
+                if let Some(outline_area) = outline_area {
+                    render_outline(f, outline_area, view, &app.theme);
+                }
             } else {
+                view.runbook.blocks.clear();
                 let total_lines = md.lines().count() as u16;
                 view.scroller.set_total(total_lines);
                 if view.auto_scroll {
                     view.scroller.end();
                 }
                 view.scroll_y = view.scroller.scroll_y;
+                let mut text = Text::from(md.clone());
+                apply_search(view, &mut text, &app.theme);
                 let paragraph = if view.wrap_enabled {
-                    Paragraph::new(Text::from(md.clone()))
+                    Paragraph::new(text)
                         .wrap(ratatui::widgets::Wrap { trim: false })
                         .scroll((view.scroll_y, 0))
                 } else {
-                    Paragraph::new(Text::from(md.clone())).scroll((view.scroll_y, 0))
+                    Paragraph::new(text).scroll((view.scroll_y, 0))
                 };
                 let paragraph = paragraph.block(
                     Block::default()
@@ -81,44 +153,49 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                 );
                 f.render_widget(paragraph, area);
             }
+        } else if view.diff.is_some() {
+            render_diff(f, area, view, &app.theme);
         } else {
             if let Some(term) = &view.term {
-                let lines = term.render_lines();
-                let total_lines = lines.len() as u16;
+                let mut colored_text = term.render_text(view.running);
+                let behind = term.scrollback_offset();
+                let total_lines = colored_text.lines.len() as u16;
                 view.scroller.set_total(total_lines);
                 if view.auto_scroll {
                     view.scroller.end();
                 }
                 view.scroll_y = view.scroller.scroll_y;
-                let colored_text: Text = match lines.join("\n").into_text() {
-                    Ok(t) => t,
-                    Err(_) => Text::from(lines.join("\n")),
-                };
+                apply_search(view, &mut colored_text, &app.theme);
                 let mut title_spans = vec![Span::raw(view.title.clone())];
                 if view.running {
                     title_spans.push(Span::raw(" "));
-                    title_spans.push(Span::styled(
-                        "● running",
-                        Style::default().fg(Color::Yellow),
-                    ));
+                    title_spans.push(Span::styled("● running", app.theme.status_running));
+                    title_spans.push(Span::styled(STOP_HINT, Style::default().fg(app.theme.text_muted)));
                 } else if let Some(code) = view.exit_status {
                     title_spans.push(Span::raw(" "));
                     if code == 0 {
-                        title_spans.push(Span::styled(
-                            format!("✔ {}", code),
-                            Style::default().fg(Color::Green),
-                        ));
+                        title_spans.push(Span::styled(format!("✔ {}", code), app.theme.status_ok));
                     } else {
-                        title_spans.push(Span::styled(
-                            format!("✖ {}", code),
-                            Style::default().fg(Color::Red),
-                        ));
+                        title_spans.push(Span::styled(format!("✖ {}", code), app.theme.status_fail));
                     }
                 } else {
                     title_spans.push(Span::raw(" "));
-                    title_spans.push(Span::styled("⛔", Style::default().fg(Color::Magenta)));
+                    let label = match view.stopped_by {
+                        Some(sig) => format!("⛔ {}", sig),
+                        None => "⛔".to_string(),
+                    };
+                    title_spans.push(Span::styled(label, app.theme.status_stopped));
                 }
-                if view.auto_scroll {
+                if behind > 0 {
+                    // Not at the live bottom: new output keeps arriving below
+                    title_spans.push(Span::raw(" "));
+                    title_spans.push(Span::styled(
+                        format!("[SCROLLBACK ↑{}]", behind),
+                        Style::default()
+                            .fg(app.theme.accent_warning)
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                } else if view.auto_scroll {
                     title_spans.push(Span::raw(" [AUTO]"));
                 }
                 let title_line = Line::from(title_spans);
@@ -148,33 +225,44 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                     view.scroller.end();
                 }
                 view.scroll_y = view.scroller.scroll_y;
-                let colored_text: Text = match combined.join("\n").into_text() {
-                    Ok(t) => t,
-                    Err(_) => Text::from(combined.join("\n")),
+                let mut colored_text: Text = if let Some(syntax) = view.syntax {
+                    // Source file: highlight once, then reuse until the theme changes
+                    view.highlighted
+                        .get_or_insert_with(|| {
+                            Text::from(highlight_lines(
+                                &view.lines.join("\n"),
+                                syntax,
+                                &app.theme,
+                                Style::default().fg(app.theme.text_primary),
+                            ))
+                        })
+                        .clone()
+                } else {
+                    match combined.join("\n").into_text() {
+                        Ok(t) => t,
+                        Err(_) => Text::from(combined.join("\n")),
+                    }
                 };
+                apply_search(view, &mut colored_text, &app.theme);
                 let mut title_spans = vec![Span::raw(view.title.clone())];
                 if view.running {
                     title_spans.push(Span::raw(" "));
-                    title_spans.push(Span::styled(
-                        "● running",
-                        Style::default().fg(Color::Yellow),
-                    ));
+                    title_spans.push(Span::styled("● running", app.theme.status_running));
+                    title_spans.push(Span::styled(STOP_HINT, Style::default().fg(app.theme.text_muted)));
                 } else if let Some(code) = view.exit_status {
                     title_spans.push(Span::raw(" "));
                     if code == 0 {
-                        title_spans.push(Span::styled(
-                            format!("✔ {}", code),
-                            Style::default().fg(Color::Green),
-                        ));
+                        title_spans.push(Span::styled(format!("✔ {}", code), app.theme.status_ok));
                     } else {
-                        title_spans.push(Span::styled(
-                            format!("✖ {}", code),
-                            Style::default().fg(Color::Red),
-                        ));
+                        title_spans.push(Span::styled(format!("✖ {}", code), app.theme.status_fail));
                     }
                 } else {
                     title_spans.push(Span::raw(" "));
-                    title_spans.push(Span::styled("⛔", Style::default().fg(Color::Magenta)));
+                    let label = match view.stopped_by {
+                        Some(sig) => format!("⛔ {}", sig),
+                        None => "⛔".to_string(),
+                    };
+                    title_spans.push(Span::styled(label, app.theme.status_stopped));
                 }
                 if view.auto_scroll {
                     title_spans.push(Span::raw(" [AUTO]"));
@@ -188,7 +276,7 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
                             .padding(Padding::new(PAD_X, PAD_X, PAD_Y, PAD_Y)),
                     )
                     .scroll((view.scroll_y, 0));
-                if app.output_dim {
+                if app.output_dim && view.syntax.is_none() {
                     paragraph = paragraph.style(Style::default().add_modifier(Modifier::DIM));
                 }
                 f.render_widget(paragraph, area);
@@ -203,15 +291,15 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
             };
             let timer_str = format_duration(elapsed);
             let status_span = if view.running {
-                Span::styled("●", Style::default().fg(Color::Yellow))
+                Span::styled("●", app.theme.status_running)
             } else if let Some(code) = view.exit_status {
                 if code == 0 {
-                    Span::styled("✔", Style::default().fg(Color::Green))
+                    Span::styled("✔", app.theme.status_ok)
                 } else {
-                    Span::styled("✖", Style::default().fg(Color::Red))
+                    Span::styled("✖", app.theme.status_fail)
                 }
             } else {
-                Span::styled("⛔", Style::default().fg(Color::Magenta))
+                Span::styled("⛔", app.theme.status_stopped)
             };
             let spinner = if view.running {
                 SPINNER_FRAMES.get(view.spinner_idx).copied().unwrap_or("⠋")
@@ -221,7 +309,7 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
             let mut overlay_spans = vec![status_span, Span::raw(" "), Span::raw(timer_str)];
             if !spinner.is_empty() {
                 overlay_spans.push(Span::raw(" "));
-                overlay_spans.push(Span::raw(spinner));
+                overlay_spans.push(Span::styled(spinner, app.theme.spinner));
             }
             let line = Line::from(overlay_spans);
             let overlay_text = Text::from(line.clone());
@@ -248,6 +336,35 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
             }
         }
 
+        if let Some(search) = &view.search {
+            let cursor = if search.editing { "▏" } else { "" };
+            let mut spans = vec![
+                Span::styled(
+                    format!("/{}{}", search.query, cursor),
+                    Style::default().fg(app.theme.text_primary),
+                ),
+                Span::raw("  "),
+            ];
+            if !search.query.is_empty() {
+                spans.push(Span::styled(
+                    search.position_label(),
+                    Style::default().fg(app.theme.text_muted),
+                ));
+            }
+            let line = Line::from(spans);
+            let w = (line.width() as u16).min(area.width.saturating_sub(2 + PAD_X * 2));
+            if w > 0 && area.height > 2 {
+                let r = Rect {
+                    x: area.x.saturating_add(1 + PAD_X),
+                    y: area.y + area.height - 1,
+                    width: w,
+                    height: 1,
+                };
+                f.render_widget(Clear, r);
+                f.render_widget(Paragraph::new(line), r);
+            }
+        }
+
         if let Some(crate::Confirm::KillProcess { yes_selected }) = app.confirm {
             let title = "Confirm";
             let line1 = "Stop the running process?";
@@ -265,14 +382,489 @@ pub fn render(f: &mut Frame, area: Rect, app: &mut App) {
             let h: u16 = 4;
             let marea = centered_rect_fixed(w, h, area);
             let msg = format!("{}\n{}\n{}", line1, line2, line3);
-            frame::render_modal(title, &msg, marea, f);
+            frame::render_modal(title, &msg, app.theme.modal, marea, f);
+        }
+    }
+}
+
+/// Comparison mode: the previous run's output with removed lines in red and added lines in green.
+/// Layout of a document viewed in `area` (inside the border and padding); docs search uses
+/// it too so that it counts the same matches the viewer finds.
+pub fn doc_layout(area: Rect, wrap: bool, images: bool, file: Option<&Path>) -> MdLayout {
+    MdLayout {
+        width: Some(usize::from(area.width.saturating_sub(2 + PAD_X * 2))),
+        height: Some(usize::from(area.height.saturating_sub(2 + PAD_Y * 2))),
+        wrap,
+        images,
+        base_dir: file.and_then(Path::parent).map(Path::to_path_buf),
+    }
+}
+
+fn render_diff(f: &mut Frame, area: Rect, view: &mut OutputView, theme: &ThemeTokens) {
+    let Some(diff) = &view.diff else {
+        return;
+    };
+    let (mut added, mut removed) = (0usize, 0usize);
+    let lines: Vec<Line<'static>> = diff
+        .iter()
+        .map(|d| match d {
+            DiffLine::Same(l) => Line::from(Span::styled(
+                format!("  {}", l),
+                Style::default().fg(theme.text_muted),
+            )),
+            DiffLine::Added(l) => {
+                added += 1;
+                Line::from(Span::styled(
+                    format!("+ {}", l),
+                    Style::default().fg(theme.accent_success),
+                ))
+            }
+            DiffLine::Removed(l) => {
+                removed += 1;
+                Line::from(Span::styled(
+                    format!("- {}", l),
+                    Style::default().fg(theme.accent_danger),
+                ))
+            }
+        })
+        .collect();
+    let mut text = Text::from(lines);
+    view.scroller.set_total(text.lines.len() as u16);
+    view.scroll_y = view.scroller.scroll_y;
+    apply_search(view, &mut text, theme);
+    let age = view
+        .compare
+        .as_ref()
+        .map(|(prev, _)| {
+            let now = history::unix_ms(std::time::SystemTime::now());
+            let ago = std::time::Duration::from_millis(now.saturating_sub(prev.started_ms));
+            format!(" vs run {} ago", format_duration(ago))
+        })
+        .unwrap_or_default();
+    let title = Line::from(vec![
+        Span::raw(view.title.clone()),
+        Span::styled(
+            format!(" [DIFF{}]", age),
+            Style::default()
+                .fg(theme.accent_warning)
+                .add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(
+            format!(" +{}", added),
+            Style::default().fg(theme.accent_success),
+        ),
+        Span::styled(
+            format!(" -{}", removed),
+            Style::default().fg(theme.accent_danger),
+        ),
+    ]);
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .padding(Padding::new(PAD_X, PAD_X, PAD_Y, PAD_Y)),
+        )
+        .scroll((view.scroll_y, 0));
+    f.render_widget(paragraph, area);
+}
+
+/// Mark the selected runnable block and show each block's last result on its header line.
+fn decorate_blocks(view: &OutputView, text: &mut Text<'static>, theme: &ThemeTokens) {
+    let runbook = &view.runbook;
+    for (i, block) in runbook.blocks.iter().enumerate() {
+        let Some(line) = text.lines.get_mut(block.header_line) else {
+            continue;
+        };
+        let selected = runbook.selected == Some(i);
+        if selected {
+            for span in &mut line.spans {
+                span.style = span.style.patch(theme.block_selected);
+            }
+        }
+        if let Some((code, at)) = runbook.status.get(&i) {
+            let (label, color) = match code {
+                Some(0) => ("✔ exit 0".to_string(), theme.accent_success),
+                Some(c) => (format!("✖ exit {}", c), theme.accent_danger),
+                None => ("⛔ stopped".to_string(), theme.accent_warning),
+            };
+            line.spans.push(Span::styled(
+                format!("  {} · {} ago", label, format_duration(at.elapsed())),
+                Style::default().fg(color),
+            ));
+        }
+        if runbook.armed == Some(i) {
+            line.spans.push(Span::styled(
+                "  dangerous: press r again to run",
+                Style::default()
+                    .fg(theme.accent_danger)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else if selected {
+            line.spans.push(Span::styled(
+                "  r: run",
+                Style::default().fg(theme.text_muted),
+            ));
+        }
+    }
+}
+
+/// List H1–H3 with their line offsets; the section being read is bold, the selection highlighted.
+fn render_outline(f: &mut Frame, area: Rect, view: &mut OutputView, theme: &ThemeTokens) {
+    let entries = outline(&view.md_headings);
+    if let Some(sel) = view.outline.as_mut() {
+        *sel = (*sel).min(entries.len().saturating_sub(1));
+    }
+    let current = current_section(&entries, view.scroll_y as usize);
+    let inner_w = area.width.saturating_sub(2) as usize;
+    let mut lines: Vec<Line> = Vec::new();
+    for (i, h) in entries.iter().enumerate() {
+        let indent = "  ".repeat(h.level.saturating_sub(1) as usize);
+        let offset = format!("{}", h.line + 1);
+        let room = inner_w.saturating_sub(indent.len() + offset.len() + 1);
+        let mut title: String = h.title.chars().take(room).collect();
+        let pad = room.saturating_sub(title.chars().count());
+        title.push_str(&" ".repeat(pad));
+        let mut style = Style::default().fg(theme.text_primary);
+        if current == Some(i) {
+            style = style.fg(theme.md_heading1).add_modifier(Modifier::BOLD);
+        }
+        if view.outline == Some(i) {
+            style = style.fg(theme.selection_fg).bg(theme.selection_bg);
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("{indent}{title} "), style),
+            Span::styled(offset, Style::default().fg(theme.text_muted)),
+        ]));
+    }
+    if entries.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "No headings",
+            Style::default().fg(theme.text_muted),
+        )));
+    }
+    // Keep the selection visible in long outlines
+    let height = area.height.saturating_sub(2) as usize;
+    let skip = view.outline.unwrap_or(0).saturating_sub(height.saturating_sub(1));
+    let paragraph = Paragraph::new(Text::from(lines)).scroll((skip as u16, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .title("Outline"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+}
+
+/// Screen positions of the document images that are fully visible in `inner`.
+fn place_images(
+    doc_images: &[MdImage],
+    text: &Text<'static>,
+    inner: Rect,
+    scroll_y: u16,
+    wrap: bool,
+) -> Vec<Placement> {
+    doc_images
+        .iter()
+        .filter_map(|img| {
+            // Wrapped lines above the image push it down by more than one row each
+            let row = if wrap && img.line > 0 {
+                Paragraph::new(Text::from(text.lines[..img.line].to_vec()))
+                    .wrap(ratatui::widgets::Wrap { trim: false })
+                    .line_count(inner.width)
+            } else {
+                img.line
+            };
+            let top = row.checked_sub(scroll_y as usize)?;
+            (top + img.rows as usize <= inner.height as usize).then(|| Placement {
+                path: img.path.clone(),
+                x: inner.x,
+                y: inner.y + top as u16,
+                cols: img.cols.min(inner.width),
+                rows: img.rows,
+            })
+        })
+        .collect()
+}
+
+/// Show the Tab link cursor in the theme's `link_cursor` style.
+fn highlight_link(view: &OutputView, text: &mut Text<'static>, theme: &ThemeTokens) {
+    let Some(spot) = view
+        .link_cursor
+        .and_then(|i| view.md_link_spots.get(i))
+        .and_then(Option::as_ref)
+    else {
+        return;
+    };
+    if let Some(line) = text.lines.get_mut(spot.line) {
+        for span in line.spans.iter_mut().take(spot.spans.end).skip(spot.spans.start) {
+            span.style = span.style.patch(theme.link_cursor);
+        }
+    }
+}
+
+/// Scroll to a heading anchor or a remembered position once the document is laid out.
+fn apply_pending_jump(view: &mut OutputView) {
+    if let Some(anchor) = view.pending_anchor.take() {
+        let slug = crate::links::slugify(&anchor);
+        if let Some(h) = view
+            .md_headings
+            .iter()
+            .find(|h| h.slug == anchor || h.slug == slug)
+        {
+            view.pending_scroll = Some(h.line as u16);
+        }
+    }
+    if let Some(line) = view.pending_scroll.take() {
+        view.scroller.scroll_y = line;
+        view.scroller.clamp();
+        view.auto_scroll = false;
+    }
+}
+
+/// Move the Tab link cursor and scroll the link into view.
+fn cycle_link(view: &mut OutputView, forward: bool) {
+    let count = view.md_links.len();
+    if count == 0 {
+        return;
+    }
+    let next = match (view.link_cursor, forward) {
+        (None, true) => 0,
+        (None, false) => count - 1,
+        (Some(i), true) => (i + 1) % count,
+        (Some(i), false) => (i + count - 1) % count,
+    };
+    view.link_cursor = Some(next);
+    if let Some(Some(spot)) = view.md_link_spots.get(next) {
+        view.scroller.reveal(spot.line as u16);
+        view.scroll_y = view.scroller.scroll_y;
+        view.auto_scroll = false;
+    }
+}
+
+fn doc_pos(view: &OutputView) -> Option<DocPos> {
+    Some(DocPos {
+        path: view.file_path.clone()?,
+        scroll: view.scroll_y,
+    })
+}
+
+/// Show `pos`, reusing the current view when it is the same document.
+fn show_doc(app: &mut App, pos: DocPos, anchor: Option<String>, history: DocHistory) {
+    if let Screen::Output(view) = &mut app.screen
+        && view.file_path.as_deref() == Some(pos.path.as_path())
+    {
+        view.doc_history = history;
+        view.pending_anchor = anchor;
+        view.pending_scroll = Some(pos.scroll);
+        return;
+    }
+    let mut view = crate::open_file_view(&pos.path);
+    view.doc_history = history;
+    view.pending_anchor = anchor;
+    view.pending_scroll = Some(pos.scroll);
+    app.screen = Screen::Output(view);
+    app.needs_clear = true;
+}
+
+/// Follow link `index` of the current document: headings and local files open in the
+/// viewer (recorded for Backspace), web and mail links go to the system browser.
+pub fn follow_link(app: &mut App, index: usize) {
+    let Screen::Output(view) = &mut app.screen else {
+        return;
+    };
+    let Some(dest) = view.md_links.get(index) else {
+        return;
+    };
+    let current = doc_pos(view);
+    match resolve_link(dest, view.file_path.as_deref()) {
+        Some(LinkTarget::External(url)) => {
+            let _ = crate::open_default_browser(&url);
         }
+        Some(LinkTarget::Anchor(anchor)) => {
+            if let Some(current) = current {
+                view.doc_history.visit(current);
+            }
+            view.pending_anchor = Some(anchor);
+        }
+        Some(LinkTarget::Document { path, anchor }) => {
+            let mut history = std::mem::take(&mut view.doc_history);
+            if let Some(current) = current {
+                history.visit(current);
+            }
+            show_doc(app, DocPos { path, scroll: 0 }, anchor, history);
+        }
+        None => {}
     }
 }
 
+/// Go back (or forward) through the documents and anchors visited by following links.
+fn navigate(app: &mut App, forward: bool) {
+    let Screen::Output(view) = &mut app.screen else {
+        return;
+    };
+    let Some(current) = doc_pos(view) else {
+        return;
+    };
+    let mut history = std::mem::take(&mut view.doc_history);
+    let target = if forward {
+        history.go_forward(current)
+    } else {
+        history.go_back(current)
+    };
+    match target {
+        Some(pos) => show_doc(app, pos, None, history),
+        None => view.doc_history = history,
+    }
+}
+
+/// Move the runnable block selection forwards or backwards and scroll it into view.
+fn select_block(view: &mut OutputView, forward: bool) {
+    let count = view.runbook.blocks.len();
+    if count == 0 {
+        return;
+    }
+    let next = match (view.runbook.selected, forward) {
+        (None, true) => 0,
+        (None, false) => count - 1,
+        (Some(i), true) => (i + 1) % count,
+        (Some(i), false) => (i + count - 1) % count,
+    };
+    view.runbook.selected = Some(next);
+    view.runbook.armed = None;
+    view.scroller.reveal(view.runbook.blocks[next].header_line as u16);
+    view.scroll_y = view.scroller.scroll_y;
+    view.auto_scroll = false;
+}
+
+/// Hand the result of a command started from a runbook block back to its document.
+fn report_to_origin(view: &OutputView, prev: &mut Screen) {
+    if let (Some(block), Screen::Output(doc)) = (view.origin_block, prev) {
+        doc.runbook
+            .status
+            .insert(block, (view.exit_status, view.ended_at.unwrap_or_else(Instant::now)));
+    }
+}
+
+/// Run a document's code block in a PTY; the document is restored when the output is closed.
+fn run_block(app: &mut App, index: usize, code: &str) -> Result<()> {
+    let Screen::Output(doc) = &app.screen else {
+        return Ok(());
+    };
+    let doc_title = doc.title.clone();
+    let placeholder = Screen::Output(OutputView::new(String::new()));
+    app.screen_stack
+        .push(std::mem::replace(&mut app.screen, placeholder));
+    if let Err(e) = crate::start_command(app, &doc_title, code, RunPolicy::default()) {
+        if let Some(doc) = app.screen_stack.pop() {
+            app.screen = doc;
+        }
+        return Err(e);
+    }
+    if let Screen::Output(view) = &mut app.screen {
+        let first = code.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        let more = if code.trim().lines().count() > 1 { " …" } else { "" };
+        view.title = format!("{}: {}{}", doc_title, first.trim(), more);
+        view.origin_block = Some(index);
+    }
+    Ok(())
+}
+
+/// Run the active search over `text`, scroll to a newly selected match and highlight all hits.
+fn apply_search(view: &mut OutputView, text: &mut Text<'static>, theme: &ThemeTokens) {
+    let Some(search) = view.search.as_mut() else {
+        return;
+    };
+    if let Some(line) = search.update(text, view.scroll_y as usize) {
+        view.scroller.reveal(line as u16);
+        view.scroll_y = view.scroller.scroll_y;
+        view.auto_scroll = false;
+    }
+    search::highlight(
+        text,
+        &search.matches,
+        Some(search.current),
+        theme.search_match,
+        theme.search_current,
+    );
+}
+
 pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
+    let mut to_run: Option<(usize, String)> = None;
+    // Link to follow, or history direction (true = forward), applied once `view` is released
+    let mut follow: Option<usize> = None;
+    let mut history_step: Option<bool> = None;
     if let Screen::Output(view) = &mut app.screen {
+        // The `/` prompt takes every key until Enter (keep) or Esc (cancel)
+        if let Some(search) = view.search.as_mut().filter(|s| s.editing) {
+            match key.code {
+                KeyCode::Esc => view.search = None,
+                KeyCode::Enter => search.editing = false,
+                KeyCode::Backspace => {
+                    search.query.pop();
+                    search.jump = true;
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    search.query.push(c);
+                    search.jump = true;
+                }
+                _ => {}
+            }
+            return Ok(false);
+        }
+        // Keys go to the child while it runs; search keys only apply to finished/static output
+        let forwarding = view.running && (app.pty_writer.is_some() || app.child.is_some());
+        let scroll_key = key.modifiers.contains(KeyModifiers::SHIFT)
+            && matches!(key.code, KeyCode::PageUp | KeyCode::PageDown);
+        // Scrolled back in a running program's output, search keys stay with dx
+        let scrolled_back = view.term.as_ref().is_some_and(|t| t.scrollback_offset() > 0);
+        let search_key = (matches!(key.code, KeyCode::Char('/' | 'n' | 'N'))
+            && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT))
+            || (key.code == KeyCode::Esc && view.search.is_some());
+        let forwarding = forwarding && !(scrolled_back && search_key);
+        let running = view.running && (app.child.is_some() || app.pty_child.is_some() || app.run.is_some());
+        // The outline takes the arrows and Enter only while it is shown next to the document
+        let outlining = !forwarding
+            && app.markdown_enabled
+            && view.render_markdown
+            && view.outline.is_some();
+        if forwarding && !scroll_key {
+            // Typing into the child jumps back to the live screen
+            if let Some(t) = view.term.as_mut() {
+                t.scroll_to_bottom();
+            }
+        }
         match (key.code, key.modifiers) {
+            (KeyCode::PageUp, m) if m.contains(KeyModifiers::SHIFT) && view.term.is_some() => {
+                if let Some(t) = view.term.as_mut() {
+                    t.scroll_up(t.page_rows());
+                }
+            }
+            (KeyCode::PageDown, m) if m.contains(KeyModifiers::SHIFT) && view.term.is_some() => {
+                if let Some(t) = view.term.as_mut() {
+                    t.scroll_down(t.page_rows());
+                }
+            }
+            (KeyCode::Char('/'), _) if !forwarding => {
+                view.search = Some(SearchState::new());
+            }
+            (KeyCode::Char('n'), _) if !forwarding && view.search.is_some() => {
+                if let Some(search) = view.search.as_mut() {
+                    search.step(true);
+                }
+            }
+            (KeyCode::Char('N'), _) if !forwarding && view.search.is_some() => {
+                if let Some(search) = view.search.as_mut() {
+                    search.step(false);
+                }
+            }
+            (KeyCode::Esc, _) if view.search.is_some() => {
+                view.search = None;
+            }
+            (KeyCode::Esc, _) if !forwarding && view.outline.is_some() => {
+                view.outline = None;
+                app.needs_clear = true;
+            }
             (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
                 app.selection_mode = !app.selection_mode;
                 if app.selection_mode && app.mouse_captured {
@@ -285,10 +877,16 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                     app.mouse_captured = true;
                 }
             }
-            (KeyCode::Esc, _) => {
-                if (app.child.is_some() || app.pty_child.is_some()) && view.running {
+            // Ctrl+\ (sent as Ctrl+4 by most terminals) asks to stop the command; Esc and q
+            // go to the command like any other key
+            (KeyCode::Char('\\' | '4'), m) if m.contains(KeyModifiers::CONTROL) && running => {
+                app.confirm = Some(crate::Confirm::KillProcess { yes_selected: false });
+            }
+            (KeyCode::Esc, _) if !forwarding => {
+                if running {
                     app.confirm = Some(crate::Confirm::KillProcess { yes_selected: false });
-                } else if let Some(prev) = app.screen_stack.pop() {
+                } else if let Some(mut prev) = app.screen_stack.pop() {
+                    report_to_origin(view, &mut prev);
                     app.screen = prev;
                     app.needs_clear = true;
                 } else if let Some(menu_path) = &app.menu_path {
@@ -300,10 +898,11 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                     return Ok(true);
                 }
             }
-            (KeyCode::Char('q'), _) => {
-                if (app.child.is_some() || app.pty_child.is_some()) && view.running {
+            (KeyCode::Char('q'), _) if !forwarding => {
+                if running {
                     app.confirm = Some(crate::Confirm::KillProcess { yes_selected: false });
-                } else if let Some(prev) = app.screen_stack.pop() {
+                } else if let Some(mut prev) = app.screen_stack.pop() {
+                    report_to_origin(view, &mut prev);
                     app.screen = prev;
                     app.needs_clear = true;
                 } else if let Some(menu_path) = &app.menu_path {
@@ -315,12 +914,91 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                     return Ok(true);
                 }
             }
-            (KeyCode::Char('d'), _) => {
+            (KeyCode::Char('d'), _) if !forwarding => {
                 app.output_dim = !app.output_dim;
             }
-            (KeyCode::Char('b'), _) | (KeyCode::Backspace, _) => {
+            (KeyCode::Char('c'), KeyModifiers::NONE) if !forwarding && view.compare.is_some() => {
+                view.diff = match (view.diff.take(), &view.compare) {
+                    (None, Some((prev, current))) => Some(history::diff_lines(&prev.lines, current)),
+                    _ => None,
+                };
+                view.scroller.home();
+                view.scroll_y = view.scroller.scroll_y;
+                view.auto_scroll = view.diff.is_none();
+            }
+            (KeyCode::Char('}'), _) if !forwarding && !view.runbook.blocks.is_empty() => {
+                select_block(view, true);
+            }
+            (KeyCode::Char('{'), _) if !forwarding && !view.runbook.blocks.is_empty() => {
+                select_block(view, false);
+            }
+            (KeyCode::Char('o'), KeyModifiers::NONE)
+                if !forwarding && view.md_content.is_some() && view.render_markdown =>
+            {
+                view.outline = match view.outline {
+                    Some(_) => None,
+                    None => Some(
+                        current_section(&outline(&view.md_headings), view.scroll_y as usize)
+                            .unwrap_or(0),
+                    ),
+                };
+                app.needs_clear = true;
+            }
+            (KeyCode::Up, _) if outlining => {
+                if let Some(sel) = view.outline.as_mut() {
+                    *sel = sel.saturating_sub(1);
+                }
+            }
+            (KeyCode::Down, _) if outlining => {
+                if let Some(sel) = view.outline.as_mut() {
+                    *sel += 1;
+                }
+            }
+            (KeyCode::Enter, _) if outlining => {
+                let entries = outline(&view.md_headings);
+                if let Some(h) = view.outline.and_then(|i| entries.get(i)) {
+                    view.pending_scroll = Some(h.line as u16);
+                }
+            }
+            (KeyCode::Tab, _) if !forwarding && !view.md_links.is_empty() => {
+                cycle_link(view, true);
+            }
+            (KeyCode::BackTab, _) if !forwarding && !view.md_links.is_empty() => {
+                cycle_link(view, false);
+            }
+            (KeyCode::Enter, _) if !forwarding && view.link_cursor.is_some() => {
+                follow = view.link_cursor;
+            }
+            (KeyCode::Backspace | KeyCode::Char('['), _)
+                if !forwarding && !view.doc_history.back.is_empty() =>
+            {
+                history_step = Some(false);
+            }
+            (KeyCode::Char(']'), _) if !forwarding && !view.doc_history.forward.is_empty() => {
+                history_step = Some(true);
+            }
+            (KeyCode::Char('r'), KeyModifiers::NONE)
+                if !forwarding && view.runbook.selected.is_some() =>
+            {
+                let runbook = &mut view.runbook;
+                if let Some(i) = runbook.selected
+                    && let Some(block) = runbook.blocks.get(i)
+                {
+                    if block.dangerous && runbook.armed != Some(i) {
+                        runbook.armed = Some(i);
+                    } else {
+                        runbook.armed = None;
+                        to_run = Some((i, block.code.clone()));
+                    }
+                }
+            }
+            (KeyCode::Char('b'), _) | (KeyCode::Backspace, _) if !forwarding => {
                 if !view.input_buffer.is_empty() {
                     view.input_buffer.pop();
+                } else if let Some(mut prev) = app.screen_stack.pop() {
+                    report_to_origin(view, &mut prev);
+                    app.screen = prev;
+                    app.needs_clear = true;
                 } else if let Some(menu_path) = &app.menu_path {
                     if let Ok(menu) = load_menu(menu_path) {
                         app.screen = Screen::Menu(menu);
@@ -336,124 +1014,55 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
                 }
                 crate::exec::pty_write(&mut app.pty_writer, &[0x03]);
             }
-            (KeyCode::Enter, _) => {
-                if app.child.is_some() {
-                    if let Some(stdin) = &mut app.child_stdin {
-                        let to_send = if view.input_buffer.is_empty() {
-                            "\n".to_string()
-                        } else {
-                            format!("{}\n", view.input_buffer)
-                        };
-                        let _ = stdin.write_all(to_send.as_bytes());
-                        let _ = stdin.flush();
-                    }
-                    view.input_buffer.clear();
-                }
-                crate::exec::pty_write(&mut app.pty_writer, b"\n");
-            }
-            (KeyCode::Char(c), m) if m.is_empty() => {
-                if app.child.is_some() {
-                    view.input_buffer.push(c);
-                }
-                crate::exec::pty_write(&mut app.pty_writer, &[c as u8]);
-            }
-            // handled above with (KeyCode::Char('b'), _) | (KeyCode::Backspace, _)
-            (KeyCode::Tab, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\t");
-            }
-            (KeyCode::Delete, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[3~");
-            }
-            (KeyCode::Up, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[A");
-            }
-            (KeyCode::Down, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[B");
-            }
-            (KeyCode::Left, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[D");
-            }
-            (KeyCode::Right, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[C");
-            }
-            (KeyCode::Home, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[H");
-            }
-            (KeyCode::End, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[F");
-            }
-            (KeyCode::PageUp, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[5~");
-            }
-            (KeyCode::PageDown, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[6~");
-            }
-            (KeyCode::Insert, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[2~");
-            }
-            (KeyCode::BackTab, _) => {
-                crate::exec::pty_write(&mut app.pty_writer, b"\x1b[Z");
-            }
-            (KeyCode::F(n), _) => {
-                if app.pty_writer.is_some() {
-                    let seq: Option<&'static [u8]> = match n {
-                        1 => Some(b"\x1bOP"),
-                        2 => Some(b"\x1bOQ"),
-                        3 => Some(b"\x1bOR"),
-                        4 => Some(b"\x1bOS"),
-                        5 => Some(b"\x1b[15~"),
-                        6 => Some(b"\x1b[17~"),
-                        7 => Some(b"\x1b[18~"),
-                        8 => Some(b"\x1b[19~"),
-                        9 => Some(b"\x1b[20~"),
-                        10 => Some(b"\x1b[21~"),
-                        11 => Some(b"\x1b[23~"),
-                        12 => Some(b"\x1b[24~"),
-                        _ => None,
+            // Piped (non-PTY) child: collect a line locally and send it on Enter
+            (KeyCode::Enter, _) if app.child.is_some() => {
+                if let Some(stdin) = &mut app.child_stdin {
+                    let to_send = if view.input_buffer.is_empty() {
+                        "\n".to_string()
+                    } else {
+                        format!("{}\n", view.input_buffer)
                     };
-                    if let Some(s) = seq {
-                        crate::exec::pty_write(&mut app.pty_writer, s);
-                    }
+                    let _ = stdin.write_all(to_send.as_bytes());
+                    let _ = stdin.flush();
                 }
+                view.input_buffer.clear();
             }
-            (KeyCode::Char(c), m)
-                if m.contains(KeyModifiers::ALT) && !m.contains(KeyModifiers::CONTROL) =>
-            {
-                let buf = vec![0x1b, c as u8];
-                crate::exec::pty_write(&mut app.pty_writer, &buf);
+            (KeyCode::Backspace, _) if app.child.is_some() => {
+                view.input_buffer.pop();
             }
-            (KeyCode::Char(c), m)
-                if m.contains(KeyModifiers::CONTROL) && !m.contains(KeyModifiers::ALT) =>
-            {
-                let mut code = c as u8;
-                if c.is_ascii_lowercase() || c.is_ascii_uppercase() {
-                    code = (c.to_ascii_lowercase() as u8) & 0x1f;
+            (KeyCode::Char(c), m) if app.child.is_some() && !m.contains(KeyModifiers::CONTROL) => {
+                view.input_buffer.push(c);
+            }
+            // PTY child: encode the key exactly as a terminal would
+            _ if app.pty_writer.is_some() => {
+                let modes = view.term.as_ref().map(|t| t.key_modes()).unwrap_or_default();
+                if let Some(bytes) = crate::keys::encode_key(&key, modes) {
+                    crate::exec::pty_write(&mut app.pty_writer, &bytes);
                 }
-                crate::exec::pty_write(&mut app.pty_writer, &[code]);
             }
             (KeyCode::Char('k'), _) => {
-                if view.term.is_none() {
+                if view.term.is_none() || view.diff.is_some() {
                     view.scroller.line_up();
                     view.scroll_y = view.scroller.scroll_y;
                     view.auto_scroll = false;
                 }
             }
             (KeyCode::Char('j'), _) => {
-                if view.term.is_none() {
+                if view.term.is_none() || view.diff.is_some() {
                     view.scroller.line_down();
                     view.scroll_y = view.scroller.scroll_y;
                     view.auto_scroll = false;
                 }
             }
             (KeyCode::Char('g'), _) => {
-                if view.term.is_none() {
+                if view.term.is_none() || view.diff.is_some() {
                     view.scroller.home();
                     view.scroll_y = view.scroller.scroll_y;
                     view.auto_scroll = false;
                 }
             }
             (KeyCode::Char('G'), _) => {
-                if view.term.is_none() {
+                if view.term.is_none() || view.diff.is_some() {
                     view.scroller.end();
                     view.scroll_y = view.scroller.scroll_y;
                     view.auto_scroll = true;
@@ -467,11 +1076,24 @@ pub fn handle_event(app: &mut App, key: KeyEvent) -> Result<bool> {
             (KeyCode::Char('m'), _) => {
                 if view.md_content.is_some() {
                     view.render_markdown = !view.render_markdown;
+                    // The outline is only drawn next to the rendered document
+                    if !view.render_markdown {
+                        view.outline = None;
+                    }
                 }
             }
             _ => {}
         }
     }
+    if let Some((index, code)) = to_run {
+        run_block(app, index, &code)?;
+    }
+    if let Some(index) = follow {
+        follow_link(app, index);
+    }
+    if let Some(forward) = history_step {
+        navigate(app, forward);
+    }
     Ok(false)
 }
 
@@ -0,0 +1,191 @@
+use ratatui::style::Style;
+use ratatui::text::{Line, Span, Text};
+
+/// A match of the search query: line index and char range of the visible text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Incremental search state for the output view.
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    pub query: String,
+    /// True while the `/` prompt is taking input
+    pub editing: bool,
+    pub matches: Vec<SearchMatch>,
+    pub current: usize,
+    /// Set when the view should scroll to the current match on the next render
+    pub jump: bool,
+}
+
+impl SearchState {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            editing: true,
+            ..Self::default()
+        }
+    }
+
+    /// A finished search for `query` that jumps to its `nth` match once text is shown.
+    #[must_use]
+    pub fn reveal(query: String, nth: usize) -> Self {
+        Self {
+            query,
+            current: nth,
+            jump: true,
+            ..Self::default()
+        }
+    }
+
+    /// Move to the next (`forward`) or previous match, wrapping around.
+    pub fn step(&mut self, forward: bool) {
+        let n = self.matches.len();
+        if n == 0 {
+            return;
+        }
+        self.current = if forward {
+            (self.current + 1) % n
+        } else {
+            (self.current + n - 1) % n
+        };
+        self.jump = true;
+    }
+
+    /// Recompute matches for `text`; when a jump is pending, pick the first match at or
+    /// below `from_line`. Returns the line to reveal, if any.
+    pub fn update(&mut self, text: &Text, from_line: usize) -> Option<usize> {
+        let prev = self.matches.get(self.current).copied();
+        self.matches = find_matches(text, &self.query);
+        if self.matches.is_empty() {
+            self.current = 0;
+            self.jump = false;
+            return None;
+        }
+        if self.jump && self.editing {
+            // Typing: stay on the first hit from the top of the viewport
+            self.current = self
+                .matches
+                .iter()
+                .position(|m| m.line >= from_line)
+                .unwrap_or(0);
+        } else if let Some(p) = prev {
+            // Streaming output: keep the same match selected as lines are appended
+            self.current = self.matches.iter().position(|m| *m == p).unwrap_or(
+                self.current.min(self.matches.len() - 1),
+            );
+        }
+        self.current = self.current.min(self.matches.len() - 1);
+        if std::mem::take(&mut self.jump) {
+            Some(self.matches[self.current].line)
+        } else {
+            None
+        }
+    }
+
+    /// "3/12"-style position label for the prompt.
+    #[must_use]
+    pub fn position_label(&self) -> String {
+        if self.matches.is_empty() {
+            "no matches".to_string()
+        } else {
+            format!("{}/{}", self.current + 1, self.matches.len())
+        }
+    }
+}
+
+/// Find all occurrences of `query` in the visible text of each line.
+/// Searching runs on span contents, so escape sequences parsed by `ansi-to-tui`
+/// never match. The search is case-insensitive unless the query has an uppercase letter.
+#[must_use]
+pub fn find_matches(text: &Text, query: &str) -> Vec<SearchMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let smart_case = query.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if smart_case {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    let mut out = Vec::new();
+    for (idx, line) in text.lines.iter().enumerate() {
+        let hay: Vec<char> = line
+            .spans
+            .iter()
+            .flat_map(|s| s.content.chars())
+            .map(fold)
+            .collect();
+        let mut i = 0;
+        while i + needle.len() <= hay.len() {
+            if hay[i..i + needle.len()] == needle[..] {
+                out.push(SearchMatch {
+                    line: idx,
+                    start: i,
+                    end: i + needle.len(),
+                });
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Patch `style` onto the matched ranges of `text`; `current` gets `current_style` instead.
+pub fn highlight(
+    text: &mut Text<'static>,
+    matches: &[SearchMatch],
+    current: Option<usize>,
+    style: Style,
+    current_style: Style,
+) {
+    for (i, m) in matches.iter().enumerate() {
+        let Some(line) = text.lines.get_mut(m.line) else {
+            continue;
+        };
+        let patch = if Some(i) == current {
+            current_style
+        } else {
+            style
+        };
+        *line = restyle_range(std::mem::take(line), m.start, m.end, patch);
+    }
+}
+
+fn restyle_range(line: Line<'static>, start: usize, end: usize, patch: Style) -> Line<'static> {
+    let mut spans: Vec<Span<'static>> = Vec::with_capacity(line.spans.len() + 2);
+    let mut pos = 0usize;
+    for span in line.spans {
+        let len = span.content.chars().count();
+        let (s, e) = (pos, pos + len);
+        pos = e;
+        if e <= start || s >= end {
+            spans.push(span);
+            continue;
+        }
+        let chars: Vec<char> = span.content.chars().collect();
+        let a = start.saturating_sub(s).min(len);
+        let b = (end - s).min(len);
+        let piece = |r: std::ops::Range<usize>| chars[r].iter().collect::<String>();
+        if a > 0 {
+            spans.push(Span::styled(piece(0..a), span.style));
+        }
+        spans.push(Span::styled(piece(a..b), span.style.patch(patch)));
+        if b < len {
+            spans.push(Span::styled(piece(b..len), span.style));
+        }
+    }
+    Line {
+        spans,
+        style: line.style,
+        alignment: line.alignment,
+    }
+}
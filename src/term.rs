@@ -1,7 +1,40 @@
-use vt100::Parser;
+use crate::keys::KeyModes;
+use crossterm::event::{KeyModifiers, MouseButton, MouseEventKind};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use vt100::{Callbacks, MouseProtocolEncoding, MouseProtocolMode, Parser};
+
+/// Rows kept above the live screen when no `scrollback_lines` is configured.
+pub const DEFAULT_SCROLLBACK: usize = 5000;
+
+/// Modes vt100 leaves to the embedding terminal, picked up from sequences it does not handle.
+#[derive(Debug, Default)]
+struct ModeTracker {
+    /// Mode 1004: the program wants `CSI I` / `CSI O` when the terminal gains or loses focus
+    focus_reporting: bool,
+}
+
+impl Callbacks for ModeTracker {
+    fn unhandled_csi(
+        &mut self,
+        _: &mut vt100::Screen,
+        i1: Option<u8>,
+        _i2: Option<u8>,
+        params: &[&[u16]],
+        c: char,
+    ) {
+        if i1 == Some(b'?') && params.iter().any(|p| p.first() == Some(&1004)) {
+            match c {
+                'h' => self.focus_reporting = true,
+                'l' => self.focus_reporting = false,
+                _ => {}
+            }
+        }
+    }
+}
 
 pub struct Emulator {
-    parser: Parser,
+    parser: Parser<ModeTracker>,
     rows: u16,
     cols: u16,
 }
@@ -13,12 +46,41 @@ impl std::fmt::Debug for Emulator {
 }
 
 impl Emulator {
+    /// Create an emulator that keeps up to `scrollback` rows that scrolled off the top.
     #[must_use]
-    pub fn new(rows: u16, cols: u16) -> Self {
-        let parser = Parser::new(rows, cols, 0);
+    pub fn with_scrollback(rows: u16, cols: u16, scrollback: usize) -> Self {
+        let parser = Parser::new_with_callbacks(rows, cols, scrollback, ModeTracker::default());
         Self { parser, rows, cols }
     }
 
+    /// Rows the view is scrolled back from the live bottom (0 = live).
+    #[must_use]
+    pub fn scrollback_offset(&self) -> usize {
+        self.parser.screen().scrollback()
+    }
+
+    /// Scroll towards older output; clamped to the rows actually kept.
+    pub fn scroll_up(&mut self, rows: usize) {
+        let target = self.scrollback_offset().saturating_add(rows);
+        self.parser.screen_mut().set_scrollback(target);
+    }
+
+    /// Scroll towards the live bottom.
+    pub fn scroll_down(&mut self, rows: usize) {
+        let target = self.scrollback_offset().saturating_sub(rows);
+        self.parser.screen_mut().set_scrollback(target);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.parser.screen_mut().set_scrollback(0);
+    }
+
+    /// Rows per page when scrolling with Shift+PgUp/PgDn.
+    #[must_use]
+    pub fn page_rows(&self) -> usize {
+        usize::from(self.rows.saturating_sub(1).max(1))
+    }
+
     pub fn process_bytes(&mut self, bytes: &[u8]) {
         self.parser.process(bytes);
     }
@@ -29,31 +91,208 @@ impl Emulator {
         }
         self.rows = rows.max(1);
         self.cols = cols.max(1);
-        self.parser.set_size(self.rows, self.cols);
+        self.parser.screen_mut().set_size(self.rows, self.cols);
     }
 
+    /// Plain text of every row kept, oldest scrollback first and the live screen last
+    /// (no colours), trailing blanks trimmed.
     #[must_use]
-    pub fn render_lines(&self) -> Vec<String> {
-        let screen = self.parser.screen().clone();
-        let mut out: Vec<String> = Vec::with_capacity(self.rows as usize);
-        for r in 0..self.rows {
-            let mut line = String::with_capacity(self.cols as usize);
-            for c in 0..self.cols {
-                if let Some(cell) = screen.cell(r, c) {
-                    let s = cell.contents();
-                    if s.is_empty() {
-                        line.push(' ');
+    pub fn history_lines(&self) -> Vec<String> {
+        let mut screen = self.parser.screen().clone();
+        screen.set_scrollback(usize::MAX);
+        let kept = screen.scrollback();
+        let rows = usize::from(self.rows);
+        let mut out: Vec<String> = Vec::with_capacity(kept + rows);
+        // A page at a time: scrolled back by `offset`, the top rows are the oldest not yet taken
+        let mut offset = kept;
+        while offset > 0 {
+            screen.set_scrollback(offset);
+            out.extend(screen.rows(0, self.cols).take(offset.min(rows)));
+            offset = offset.saturating_sub(rows);
+        }
+        screen.set_scrollback(0);
+        out.extend(screen.rows(0, self.cols));
+        for line in &mut out {
+            line.truncate(line.trim_end().len());
+        }
+        out
+    }
+
+    /// Key encoding modes the program switched on (DECCKM, bracketed paste).
+    #[must_use]
+    pub fn key_modes(&self) -> KeyModes {
+        let screen = self.parser.screen();
+        KeyModes {
+            application_cursor: screen.application_cursor(),
+            bracketed_paste: screen.bracketed_paste(),
+        }
+    }
+
+    /// True when the program enabled mouse reporting (modes 9/1000/1002/1003).
+    #[must_use]
+    pub fn wants_mouse(&self) -> bool {
+        self.parser.screen().mouse_protocol_mode() != MouseProtocolMode::None
+    }
+
+    /// True when the program enabled focus reporting (mode 1004).
+    #[must_use]
+    pub fn wants_focus(&self) -> bool {
+        self.parser.callbacks().focus_reporting
+    }
+
+    /// Encode a mouse event at `col`/`row` (0-based, emulator cells) the way the program
+    /// asked for it: X10/normal or SGR (1006) encoding. Returns `None` when the active
+    /// mode does not report this kind of event.
+    #[must_use]
+    pub fn encode_mouse(
+        &self,
+        kind: MouseEventKind,
+        modifiers: KeyModifiers,
+        col: u16,
+        row: u16,
+    ) -> Option<Vec<u8>> {
+        let screen = self.parser.screen();
+        let mode = screen.mouse_protocol_mode();
+        let button_code = |b: MouseButton| match b {
+            MouseButton::Left => 0u16,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+        };
+        // (button code, is release)
+        let (mut code, release) = match kind {
+            MouseEventKind::Down(b) => (button_code(b), false),
+            MouseEventKind::Up(b) if mode != MouseProtocolMode::Press => (button_code(b), true),
+            MouseEventKind::Drag(b)
+                if matches!(
+                    mode,
+                    MouseProtocolMode::ButtonMotion | MouseProtocolMode::AnyMotion
+                ) =>
+            {
+                (button_code(b) + 32, false)
+            }
+            MouseEventKind::Moved if mode == MouseProtocolMode::AnyMotion => (3 + 32, false),
+            MouseEventKind::ScrollUp => (64, false),
+            MouseEventKind::ScrollDown => (65, false),
+            MouseEventKind::ScrollLeft => (66, false),
+            MouseEventKind::ScrollRight => (67, false),
+            _ => return None,
+        };
+        if mode == MouseProtocolMode::None {
+            return None;
+        }
+        if mode != MouseProtocolMode::Press {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                code += 4;
+            }
+            if modifiers.contains(KeyModifiers::ALT) {
+                code += 8;
+            }
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                code += 16;
+            }
+        }
+        let (x, y) = (u32::from(col) + 1, u32::from(row) + 1);
+        match screen.mouse_protocol_encoding() {
+            MouseProtocolEncoding::Sgr => {
+                let end = if release { 'm' } else { 'M' };
+                Some(format!("\x1b[<{code};{x};{y}{end}").into_bytes())
+            }
+            encoding => {
+                // Legacy encodings cannot say which button was released
+                let code = if release { 3 + (code & !3) } else { code };
+                let mut out = b"\x1b[M".to_vec();
+                for v in [u32::from(code) + 32, x + 32, y + 32] {
+                    if encoding == MouseProtocolEncoding::Utf8 {
+                        let c = char::from_u32(v)?;
+                        let mut buf = [0u8; 4];
+                        out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
                     } else {
-                        line.push_str(&s);
+                        out.push(u8::try_from(v).ok()?);
                     }
+                }
+                Some(out)
+            }
+        }
+    }
+
+    /// Render the visible rows as styled spans, keeping colours and attributes.
+    /// With `show_cursor`, the cursor cell is drawn reversed unless the program hid it
+    /// or the view is scrolled back.
+    #[must_use]
+    pub fn render_text(&self, show_cursor: bool) -> Text<'static> {
+        let screen = self.parser.screen();
+        let cursor = (show_cursor && !screen.hide_cursor() && self.scrollback_offset() == 0)
+            .then(|| screen.cursor_position());
+        let mut lines: Vec<Line<'static>> = Vec::with_capacity(self.rows as usize);
+        for r in 0..self.rows {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            let mut run = String::new();
+            let mut run_style = Style::default();
+            for c in 0..self.cols {
+                let Some(cell) = screen.cell(r, c) else {
+                    continue;
+                };
+                if cell.is_wide_continuation() {
+                    continue;
+                }
+                let mut style = cell_style(cell);
+                if cursor == Some((r, c)) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                if style != run_style && !run.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut run), run_style));
+                }
+                run_style = style;
+                let s = cell.contents();
+                if s.is_empty() {
+                    run.push(' ');
                 } else {
-                    line.push(' ');
+                    run.push_str(s);
                 }
             }
-            // Trim trailing spaces for nicer rendering
-            let trimmed = line.trim_end().to_string();
-            out.push(trimmed);
+            // Trailing blanks in the default style carry nothing worth drawing
+            if run_style == Style::default() {
+                run.truncate(run.trim_end_matches(' ').len());
+            }
+            if !run.is_empty() {
+                spans.push(Span::styled(run, run_style));
+            }
+            lines.push(Line::from(spans));
         }
-        out
+        Text::from(lines)
+    }
+}
+
+fn cell_style(cell: &vt100::Cell) -> Style {
+    let mut style = Style::default();
+    if let Some(fg) = map_color(cell.fgcolor()) {
+        style = style.fg(fg);
+    }
+    if let Some(bg) = map_color(cell.bgcolor()) {
+        style = style.bg(bg);
+    }
+    if cell.bold() {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if cell.dim() {
+        style = style.add_modifier(Modifier::DIM);
+    }
+    if cell.italic() {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if cell.underline() {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    if cell.inverse() {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    style
+}
+
+fn map_color(color: vt100::Color) -> Option<Color> {
+    match color {
+        vt100::Color::Default => None,
+        vt100::Color::Idx(i) => Some(Color::Indexed(i)),
+        vt100::Color::Rgb(r, g, b) => Some(Color::Rgb(r, g, b)),
     }
 }
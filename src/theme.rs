@@ -2,10 +2,11 @@
 #![allow(clippy::collapsible_if)] // TODO: Simplify nested if statements
 #![allow(clippy::uninlined_format_args)] // TODO: Use {var} format syntax
 
-use ratatui::style::Color;
+use ratatui::style::{Color, Modifier, Style};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, Copy)]
 pub struct ThemeTokens {
@@ -35,6 +36,27 @@ pub struct ThemeTokens {
 
     pub selection_fg: Color,
     pub selection_bg: Color,
+
+    pub syntax_keyword: Color,
+    pub syntax_string: Color,
+    pub syntax_comment: Color,
+    pub syntax_number: Color,
+    pub syntax_function: Color,
+    pub syntax_type: Color,
+
+    // Style tokens (fg, bg and modifiers); `.dx-theme` values like "bold yellow on #202020"
+    pub status_running: Style,
+    pub status_ok: Style,
+    pub status_fail: Style,
+    pub status_stopped: Style,
+    pub spinner: Style,
+    pub modal: Style,
+    pub breadcrumbs: Style,
+    pub search_match: Style,
+    pub search_current: Style,
+    pub link_cursor: Style,
+    pub block_selected: Style,
+    pub recording: Style,
 }
 
 impl ThemeTokens {
@@ -81,6 +103,29 @@ impl ThemeTokens {
 
             selection_fg: Color::White,
             selection_bg: Color::Rgb(24, 24, 24),
+
+            syntax_keyword: Color::LightMagenta,
+            syntax_string: Color::LightGreen,
+            syntax_comment: Color::DarkGray,
+            syntax_number: Color::LightYellow,
+            syntax_function: Color::LightBlue,
+            syntax_type: Color::LightCyan,
+
+            status_running: Style::new().fg(Color::Yellow),
+            status_ok: Style::new().fg(Color::Green),
+            status_fail: Style::new().fg(Color::Red),
+            status_stopped: Style::new().fg(Color::Magenta),
+            spinner: Style::new().fg(Color::Yellow),
+            modal: Style::new().fg(Color::Black).bg(Color::Gray),
+            breadcrumbs: Style::new().fg(Color::Gray),
+            search_match: Style::new().add_modifier(Modifier::REVERSED),
+            search_current: Style::new()
+                .fg(Color::Rgb(16, 16, 16))
+                .bg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+            link_cursor: Style::new().add_modifier(Modifier::REVERSED),
+            block_selected: Style::new().add_modifier(Modifier::REVERSED),
+            recording: Style::new().fg(Color::Red),
         }
     }
 
@@ -98,25 +143,72 @@ impl ThemeTokens {
             menu_desc: Color::Gray,
 
             link: Color::Blue,
-            code: Color::Cyan,
-            md_heading1: Color::Yellow,
-            md_heading2: Color::LightYellow,
-            md_heading3: Color::Green,
+            code: Color::Rgb(0, 110, 120),
+            md_heading1: Color::Rgb(20, 70, 150),
+            md_heading2: Color::Rgb(110, 50, 140),
+            md_heading3: Color::Rgb(30, 110, 50),
             rule: Color::DarkGray,
 
             status_fg: Color::Black,
             status_bg: Color::Rgb(230, 230, 230),
 
-            accent_success: Color::Green,
-            accent_warning: Color::Yellow,
-            accent_danger: Color::Red,
+            accent_success: Color::Rgb(30, 120, 40),
+            accent_warning: Color::Rgb(150, 100, 0),
+            accent_danger: Color::Rgb(180, 30, 30),
 
             selection_fg: Color::Black,
             selection_bg: Color::Rgb(230, 230, 230),
+
+            syntax_keyword: Color::Magenta,
+            syntax_string: Color::Green,
+            syntax_comment: Color::Gray,
+            syntax_number: Color::Red,
+            syntax_function: Color::Blue,
+            syntax_type: Color::Cyan,
+
+            status_running: Style::new().fg(Color::Rgb(150, 100, 0)),
+            status_ok: Style::new().fg(Color::Rgb(30, 120, 40)),
+            status_fail: Style::new().fg(Color::Rgb(180, 30, 30)),
+            status_stopped: Style::new().fg(Color::Rgb(140, 40, 140)),
+            spinner: Style::new().fg(Color::Rgb(150, 100, 0)),
+            modal: Style::new().fg(Color::White).bg(Color::Rgb(60, 60, 60)),
+            breadcrumbs: Style::new().fg(Color::DarkGray),
+            search_match: Style::new().bg(Color::Rgb(255, 235, 150)),
+            search_current: Style::new()
+                .fg(Color::White)
+                .bg(Color::Rgb(150, 100, 0))
+                .add_modifier(Modifier::BOLD),
+            link_cursor: Style::new().add_modifier(Modifier::REVERSED),
+            block_selected: Style::new().add_modifier(Modifier::REVERSED),
+            recording: Style::new().fg(Color::Rgb(180, 30, 30)),
         }
     }
 
+    fn style_token(&mut self, key: &str) -> Option<&mut Style> {
+        Some(match key {
+            "status_running" => &mut self.status_running,
+            "status_ok" => &mut self.status_ok,
+            "status_fail" => &mut self.status_fail,
+            "status_stopped" => &mut self.status_stopped,
+            "spinner" => &mut self.spinner,
+            "modal" => &mut self.modal,
+            "breadcrumbs" => &mut self.breadcrumbs,
+            "search_match" => &mut self.search_match,
+            "search_current" => &mut self.search_current,
+            "link_cursor" => &mut self.link_cursor,
+            "block_selected" => &mut self.block_selected,
+            "recording" => &mut self.recording,
+            _ => return None,
+        })
+    }
+
     fn apply_token(&mut self, key: &str, value: &str) {
+        if let Some(slot) = self.style_token(key) {
+            if let Some(style) = parse_style(value) {
+                *slot = style;
+            }
+            return;
+        }
         if let Some(color) = parse_color(value) {
             match key {
                 "text_primary" => self.text_primary = color,
@@ -140,6 +232,12 @@ impl ThemeTokens {
                 "accent_danger" => self.accent_danger = color,
                 "selection_fg" => self.selection_fg = color,
                 "selection_bg" => self.selection_bg = color,
+                "syntax_keyword" => self.syntax_keyword = color,
+                "syntax_string" => self.syntax_string = color,
+                "syntax_comment" => self.syntax_comment = color,
+                "syntax_number" => self.syntax_number = color,
+                "syntax_function" => self.syntax_function = color,
+                "syntax_type" => self.syntax_type = color,
                 _ => {}
             }
         }
@@ -152,7 +250,6 @@ struct FileTheme {
     tokens: HashMap<String, String>,
 }
 
-#[allow(dead_code)]
 fn expand_tilde(path: &str) -> String {
     if let Some(rest) = path.strip_prefix("~/") {
         if let Ok(home) = std::env::var("HOME") {
@@ -162,7 +259,6 @@ fn expand_tilde(path: &str) -> String {
     path.to_string()
 }
 
-#[allow(dead_code)]
 #[must_use]
 pub fn load_theme_from<S: ::std::hash::BuildHasher>(
     name: Option<&str>,
@@ -170,8 +266,9 @@ pub fn load_theme_from<S: ::std::hash::BuildHasher>(
     overrides: Option<&HashMap<String, String, S>>,
 ) -> ThemeTokens {
     // Base: builtin by name (default dark)
+    // Family members such as `solar-light` start from the light theme too
     let mut tokens = match name.map(str::to_ascii_lowercase) {
-        Some(n) if n == "light" => ThemeTokens::builtin_light(),
+        Some(n) if n == "light" || n.ends_with("-light") => ThemeTokens::builtin_light(),
         _ => ThemeTokens::builtin_dark(),
     };
 
@@ -199,6 +296,308 @@ pub fn load_theme_from<S: ::std::hash::BuildHasher>(
     tokens
 }
 
+/// A theme offered by the picker: a built-in one, or a `.dx-theme` file named after its stem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeChoice {
+    pub name: String,
+    pub file: Option<PathBuf>,
+}
+
+/// The built-in themes followed by every `.dx-theme` file in `dir`, sorted by name.
+#[must_use]
+pub fn list_themes(dir: Option<&Path>) -> Vec<ThemeChoice> {
+    let mut out: Vec<ThemeChoice> = ["dark", "light"]
+        .iter()
+        .map(|n| ThemeChoice {
+            name: (*n).to_string(),
+            file: None,
+        })
+        .collect();
+    let mut files: Vec<PathBuf> = dir
+        .map(|d| PathBuf::from(expand_tilde(&d.to_string_lossy())))
+        .and_then(|d| fs::read_dir(d).ok())
+        .map(|rd| {
+            rd.flatten()
+                .map(|e| e.path())
+                .filter(|p| p.to_string_lossy().ends_with(".dx-theme"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    for path in files {
+        let name = path
+            .file_name()
+            .map(|n| {
+                n.to_string_lossy()
+                    .trim_end_matches(".dx-theme")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        out.push(ThemeChoice {
+            name,
+            file: Some(path),
+        });
+    }
+    out
+}
+
+/// The `.dx-theme` file to load: `theme_file` when set, else `<theme_dir>/<theme>.dx-theme`
+/// for a theme that is not built in.
+#[must_use]
+pub fn resolve_theme_file(
+    name: Option<&str>,
+    file: Option<&str>,
+    dir: Option<&str>,
+) -> Option<PathBuf> {
+    if let Some(f) = file {
+        return Some(PathBuf::from(expand_tilde(f)));
+    }
+    let name =
+        name.filter(|n| !n.eq_ignore_ascii_case("dark") && !n.eq_ignore_ascii_case("light"))?;
+    let path = PathBuf::from(expand_tilde(dir?)).join(format!("{name}.dx-theme"));
+    path.is_file().then_some(path)
+}
+
+/// How long to wait for the terminal to report its background colour.
+pub const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Least time between two background queries when focus keeps coming back.
+pub const BACKGROUND_REQUERY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The theme for `theme = "auto"`: `<family>-dark`/`<family>-light` from `theme_dir` when
+/// both a family and its file exist, else the matching built-in theme.
+#[must_use]
+pub fn auto_theme(
+    dark: bool,
+    family: Option<&str>,
+    dir: Option<&str>,
+) -> (String, Option<PathBuf>) {
+    let base = if dark { "dark" } else { "light" };
+    if let Some(f) = family {
+        let name = format!("{f}-{base}");
+        if let Some(file) = resolve_theme_file(Some(&name), None, dir) {
+            return (name, Some(file));
+        }
+    }
+    (base.to_string(), None)
+}
+
+/// Background colour from an OSC 11 reply (`ESC ] 11 ; rgb:RRRR/GGGG/BBBB` ending in BEL
+/// or ST), scaled to 8 bits per channel.
+#[must_use]
+pub fn parse_osc11_reply(reply: &[u8]) -> Option<(u8, u8, u8)> {
+    let text = String::from_utf8_lossy(reply);
+    let start = text.find("]11;rgb:")? + "]11;rgb:".len();
+    let body = &text[start..];
+    let end = body.find(['\x07', '\x1b']).unwrap_or(body.len());
+    let mut channels = body[..end].split('/').map(|c| {
+        let digits = c.len();
+        if !(1..=4).contains(&digits) {
+            return None;
+        }
+        let value = u32::from_str_radix(c, 16).ok()?;
+        let max = (1u32 << (4 * digits)) - 1;
+        u8::try_from(value * 255 / max).ok()
+    });
+    let rgb = (channels.next()??, channels.next()??, channels.next()??);
+    channels.next().is_none().then_some(rgb)
+}
+
+/// Whether text needs a dark-background theme on this colour (perceived brightness).
+#[must_use]
+pub fn is_dark_background((r, g, b): (u8, u8, u8)) -> bool {
+    u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114 < 128_000
+}
+
+/// Dark or light from `COLORFGBG` (`"15;0"`: foreground;background palette indexes).
+#[must_use]
+pub fn colorfgbg_is_dark(value: &str) -> Option<bool> {
+    match value.rsplit(';').next()?.trim().parse::<u8>().ok()? {
+        0..=6 | 8 => Some(true),
+        7 | 9..=15 => Some(false),
+        _ => None,
+    }
+}
+
+/// True when a DA1 reply (`ESC [ ? … c`) has arrived; terminals answer queries in order,
+/// so nothing more is coming for the OSC 11 query sent before it.
+fn has_da1_reply(buf: &[u8]) -> bool {
+    buf.windows(3)
+        .position(|w| w == b"\x1b[?")
+        .is_some_and(|p| {
+            buf[p + 3..]
+                .iter()
+                .find(|b| !(b.is_ascii_digit() || **b == b';'))
+                == Some(&b'c')
+        })
+}
+
+/// Remove OSC 11 and DA1 replies from terminal input, keeping whatever else arrived
+/// around them (keys typed while a query was in flight).
+#[must_use]
+pub fn strip_query_replies(buf: &[u8]) -> Vec<u8> {
+    let mut rest = Vec::with_capacity(buf.len());
+    let mut i = 0;
+    while i < buf.len() {
+        let tail = &buf[i..];
+        let reply_len = if tail.starts_with(b"\x1b]11;") {
+            let bel = tail.iter().position(|b| *b == 0x07).map(|p| p + 1);
+            let st = tail.windows(2).position(|w| w == b"\x1b\\").map(|p| p + 2);
+            bel.into_iter().chain(st).min()
+        } else if tail.starts_with(b"\x1b[?") {
+            tail[3..]
+                .iter()
+                .position(|b| !(b.is_ascii_digit() || *b == b';'))
+                .filter(|p| tail[3 + p] == b'c')
+                .map(|p| p + 4)
+        } else {
+            None
+        };
+        match reply_len {
+            Some(n) => i += n,
+            None => {
+                rest.push(buf[i]);
+                i += 1;
+            }
+        }
+    }
+    rest
+}
+
+/// An OSC 11 query in flight. Replies are read a little at a time so the caller's event
+/// loop keeps running; poll it only while nothing else reads stdin.
+///
+/// This reads the stdin descriptor directly although crossterm owns it. That is sound only
+/// because crossterm reads stdin solely inside `event::poll`/`event::read` (dx does not use
+/// its `event-stream` reader thread), so a caller that stops calling those while a query is
+/// pending has the descriptor to itself. Input crossterm already buffered stays there.
+#[derive(Debug)]
+pub struct BackgroundQuery {
+    buf: Vec<u8>,
+    deadline: Instant,
+}
+
+impl BackgroundQuery {
+    /// Ask the terminal for its background colour with OSC 11. A DA1 query follows so that
+    /// terminals without OSC 11 support answer at once instead of running into the timeout.
+    /// `None` when stdin is not a terminal. Must be sent in raw mode.
+    #[cfg(unix)]
+    #[must_use]
+    pub fn start(timeout: Duration) -> Option<Self> {
+        use std::io::Write;
+
+        // SAFETY: isatty only inspects the descriptor number and has no memory effects.
+        if unsafe { libc::isatty(libc::STDIN_FILENO) } != 1 {
+            return None;
+        }
+        let mut out = std::io::stdout();
+        out.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+        out.flush().ok()?;
+        Some(Self {
+            buf: Vec::new(),
+            deadline: Instant::now() + timeout,
+        })
+    }
+
+    #[cfg(not(unix))]
+    #[must_use]
+    pub fn start(_timeout: Duration) -> Option<Self> {
+        None
+    }
+
+    /// Read what arrives within `wait`. True once the terminal has answered, or the
+    /// timeout passed without an answer.
+    #[cfg(unix)]
+    pub fn poll(&mut self, wait: Duration) -> bool {
+        if has_da1_reply(&self.buf) {
+            return true;
+        }
+        let left = self.deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return true;
+        }
+        let mut pfd = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ms = i32::try_from(wait.min(left).as_millis()).unwrap_or(i32::MAX);
+        // SAFETY: `pfd` is a live, initialised pollfd and the count of 1 matches it.
+        match unsafe { libc::poll(&raw mut pfd, 1, ms) } {
+            0 => return Instant::now() >= self.deadline,
+            n if n < 0 => return true,
+            _ => {}
+        }
+        let mut chunk = [0u8; 256];
+        // SAFETY: the pointer and length describe `chunk`, which outlives the call.
+        let n = unsafe { libc::read(libc::STDIN_FILENO, chunk.as_mut_ptr().cast(), chunk.len()) };
+        match usize::try_from(n) {
+            Ok(n) if n > 0 => self.buf.extend_from_slice(&chunk[..n]),
+            _ => return true,
+        }
+        has_da1_reply(&self.buf)
+    }
+
+    #[cfg(not(unix))]
+    pub fn poll(&mut self, _wait: Duration) -> bool {
+        true
+    }
+
+    /// The reported background colour, if any, and the other input that was read while
+    /// waiting for it.
+    #[must_use]
+    pub fn finish(self) -> (Option<(u8, u8, u8)>, Vec<u8>) {
+        (parse_osc11_reply(&self.buf), strip_query_replies(&self.buf))
+    }
+}
+
+/// Ask the terminal for its background colour and wait up to `timeout` for the answer.
+/// Must be called in raw mode while nothing else reads stdin.
+#[must_use]
+pub fn query_background(timeout: Duration) -> Option<(u8, u8, u8)> {
+    let mut query = BackgroundQuery::start(timeout)?;
+    while !query.poll(timeout) {}
+    query.finish().0
+}
+
+/// Whether the terminal background is dark: OSC 11 first, then `COLORFGBG`.
+#[must_use]
+pub fn detect_dark_background(timeout: Duration) -> Option<bool> {
+    query_background(timeout)
+        .map(is_dark_background)
+        .or_else(|| colorfgbg_is_dark(&std::env::var("COLORFGBG").ok()?))
+}
+
+/// Parse a style token: an optional foreground colour, `on <colour>` for the background and
+/// modifier names, in any order (`"bold yellow on #202020"`, `"reversed"`, `"none"`).
+#[must_use]
+pub fn parse_style(spec: &str) -> Option<Style> {
+    let mut style = Style::new();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next() {
+        let modifier = match word.to_ascii_lowercase().as_str() {
+            "none" => Modifier::empty(),
+            "bold" => Modifier::BOLD,
+            "dim" => Modifier::DIM,
+            "italic" => Modifier::ITALIC,
+            "underlined" | "underline" => Modifier::UNDERLINED,
+            "reversed" | "reverse" => Modifier::REVERSED,
+            "blink" => Modifier::SLOW_BLINK,
+            "crossed_out" | "strikethrough" => Modifier::CROSSED_OUT,
+            "on" => {
+                style = style.bg(parse_color(words.next()?)?);
+                continue;
+            }
+            _ => {
+                style = style.fg(parse_color(word)?);
+                continue;
+            }
+        };
+        style = style.add_modifier(modifier);
+    }
+    Some(style)
+}
+
 #[must_use]
 pub fn parse_color(spec: &str) -> Option<Color> {
     let s = spec.trim();
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How often watched files are checked when no interval is given.
+pub const DEFAULT_POLL: Duration = Duration::from_millis(500);
+
+/// What identifies a file's contents without reading it: modification time and size.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Polls the modification time and size of a changing set of files; editors that save by
+/// renaming a new file into place are picked up as well.
+#[derive(Debug)]
+pub struct FileWatcher {
+    files: HashMap<PathBuf, Stamp>,
+    interval: Duration,
+    last_poll: Option<Instant>,
+}
+
+impl FileWatcher {
+    #[must_use]
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            files: HashMap::new(),
+            interval,
+            last_poll: None,
+        }
+    }
+
+    /// Watch exactly `paths`: new ones start from their current state, others are dropped.
+    pub fn set_watched(&mut self, paths: &[&Path]) {
+        self.files.retain(|p, _| paths.contains(&p.as_path()));
+        for path in paths {
+            if !self.files.contains_key(*path) {
+                self.files.insert(path.to_path_buf(), stamp(path));
+            }
+        }
+    }
+
+    /// Files changed (or created/removed) since the last check; checks at most once per interval.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.is_some_and(|t| t.elapsed() < self.interval) {
+            return Vec::new();
+        }
+        self.last_poll = Some(Instant::now());
+        self.check_now()
+    }
+
+    /// Files changed since the last check, regardless of the interval.
+    pub fn check_now(&mut self) -> Vec<PathBuf> {
+        let mut changed: Vec<PathBuf> = Vec::new();
+        for (path, last) in &mut self.files {
+            let now = stamp(path);
+            if now != *last {
+                *last = now;
+                changed.push(path.clone());
+            }
+        }
+        changed.sort();
+        changed
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new(DEFAULT_POLL)
+    }
+}
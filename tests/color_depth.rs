@@ -0,0 +1,90 @@
+use dx::color::{
+    ColorChoice, ColorDepth, detect_from, downsample, downsample_buffer, index_to_rgb, rgb_to_16,
+    rgb_to_256,
+};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+
+#[test]
+fn depth_is_detected_from_the_environment() {
+    let d = |ct, term, no, force| detect_from(ct, term, no, force);
+    assert_eq!(
+        d(Some("truecolor"), Some("xterm-256color"), None, None),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        d(None, Some("xterm-256color"), None, None),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(d(None, Some("linux"), None, None), ColorDepth::Ansi16);
+    assert_eq!(
+        d(None, Some("xterm-direct"), None, None),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(d(None, Some("dumb"), None, None), ColorDepth::Mono);
+    let unset = if cfg!(unix) {
+        ColorDepth::Mono
+    } else {
+        ColorDepth::Ansi16
+    };
+    assert_eq!(d(None, None, None, None), unset);
+    assert_eq!(
+        d(Some("truecolor"), Some("xterm-256color"), Some("1"), None),
+        ColorDepth::Mono
+    );
+    // An empty NO_COLOR does not count; FORCE_COLOR beats NO_COLOR
+    assert_eq!(d(None, Some("xterm"), Some(""), None), ColorDepth::Ansi16);
+    assert_eq!(
+        d(None, Some("dumb"), Some("1"), Some("2")),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(d(None, Some("xterm"), None, Some("0")), ColorDepth::Mono);
+
+    assert_eq!(ColorChoice::parse("Never"), Some(ColorChoice::Never));
+    assert_eq!(ColorChoice::parse("sometimes"), None);
+}
+
+#[test]
+fn colours_map_to_the_nearest_palette_entry() {
+    assert_eq!(rgb_to_256((255, 0, 0)), 196);
+    assert_eq!(rgb_to_256((24, 24, 24)), 234);
+    assert_eq!(index_to_rgb(196), (255, 0, 0));
+    assert_eq!(index_to_rgb(232), (8, 8, 8));
+    assert_eq!(rgb_to_16((250, 250, 250)), Color::White);
+    assert_eq!(rgb_to_16((20, 70, 150)), Color::Blue);
+
+    let rgb = Color::Rgb(230, 230, 230);
+    assert_eq!(downsample(rgb, ColorDepth::TrueColor), rgb);
+    assert_eq!(downsample(rgb, ColorDepth::Ansi256), Color::Indexed(254));
+    assert_eq!(downsample(rgb, ColorDepth::Ansi16), Color::Gray);
+    assert_eq!(
+        downsample(Color::Indexed(196), ColorDepth::Ansi16),
+        Color::LightRed
+    );
+    assert_eq!(downsample(Color::Yellow, ColorDepth::Ansi16), Color::Yellow);
+    assert_eq!(downsample(Color::Yellow, ColorDepth::Mono), Color::Reset);
+}
+
+#[test]
+fn frames_lose_colour_but_keep_highlights_in_mono() {
+    let area = Rect::new(0, 0, 3, 1);
+    let mut buf = Buffer::empty(area);
+    buf.set_string(0, 0, "a", Style::default().fg(Color::Rgb(255, 0, 0)));
+    buf.set_string(
+        1,
+        0,
+        "b",
+        Style::default().fg(Color::White).bg(Color::Rgb(24, 24, 24)),
+    );
+
+    let mut c256 = buf.clone();
+    downsample_buffer(&mut c256, ColorDepth::Ansi256);
+    assert_eq!(c256.content[0].fg, Color::Indexed(196));
+
+    downsample_buffer(&mut buf, ColorDepth::Mono);
+    assert_eq!(buf.content[0].fg, Color::Reset);
+    assert!(!buf.content[0].modifier.contains(Modifier::REVERSED));
+    assert_eq!(buf.content[1].bg, Color::Reset);
+    assert!(buf.content[1].modifier.contains(Modifier::REVERSED));
+}
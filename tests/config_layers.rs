@@ -0,0 +1,202 @@
+use std::fs;
+
+use dx::config::{ConfigLayer, ConfigSource, LayeredConfig, env_config, open_config_state};
+use dx::menu::load_dx_file;
+
+fn env(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect()
+}
+
+#[test]
+fn layers_apply_in_precedence_order() {
+    let home = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    fs::create_dir(home.path().join(".dx")).unwrap();
+    fs::write(
+        home.path().join(".dx/config.toml"),
+        "theme = \"dark\"\nshow_fps = false\nhistory_runs = 3\nmotd_wrap = false\n",
+    )
+    .unwrap();
+    fs::write(
+        project.path().join("config.yaml"),
+        "theme: light\nhistory_runs: 5\nscrollback_lines: 100\n",
+    )
+    .unwrap();
+    let dx = project.path().join("dx.yaml");
+    fs::write(
+        &dx,
+        "config:\n  theme: solarized\n  scrollback_lines: 200\n  telemetry:\n    enabled: true\n    endpoint: https://example.test\nmenu:\n  - name: Build\n    cmd: make\n",
+    )
+    .unwrap();
+
+    let settings = LayeredConfig::load(
+        Some(home.path()),
+        project.path(),
+        Some(&dx),
+        env(&[("DX_SCROLLBACK_LINES", "300"), ("DX_RELAUNCHED", "1")]),
+        &["theme=cli-theme".to_string()],
+    )
+    .unwrap();
+    let cfg = &settings.merged;
+
+    assert_eq!(cfg.theme.as_deref(), Some("cli-theme"));
+    assert_eq!(cfg.scrollback_lines, Some(300));
+    assert_eq!(cfg.history_runs, Some(5));
+    assert_eq!(cfg.show_fps, Some(false));
+    assert_eq!(cfg.motd_wrap, Some(false));
+    assert!(cfg.telemetry.as_ref().is_some_and(|t| t.enabled));
+
+    assert_eq!(settings.source_of(|c| c.theme.is_some()), ConfigSource::Cli);
+    assert_eq!(
+        settings.source_of(|c| c.scrollback_lines.is_some()),
+        ConfigSource::Env
+    );
+    assert_eq!(
+        settings.source_of(|c| c.telemetry.is_some()),
+        ConfigSource::DxFile
+    );
+    assert_eq!(
+        settings.source_of(|c| c.history_runs.is_some()),
+        ConfigSource::Project
+    );
+    assert_eq!(
+        settings.source_of(|c| c.show_fps.is_some()),
+        ConfigSource::Global
+    );
+    assert_eq!(
+        settings.source_of(|c| c.images.is_some()),
+        ConfigSource::Default
+    );
+
+    // The menu is still read from the same file
+    assert_eq!(load_dx_file(&dx).unwrap().menu.len(), 1);
+}
+
+#[test]
+fn global_can_forbid_project_status_and_motd() {
+    let home = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    fs::create_dir(home.path().join(".dx")).unwrap();
+    fs::write(
+        home.path().join(".dx/config.toml"),
+        "allow_project_override = false\n[status]\ntext = \"global\"\n",
+    )
+    .unwrap();
+    fs::write(
+        project.path().join("config.toml"),
+        "[status]\ntext = \"project\"\n[motd.vars]\nuser = \"whoami\"\n",
+    )
+    .unwrap();
+
+    let settings =
+        LayeredConfig::load(Some(home.path()), project.path(), None, Vec::new(), &[]).unwrap();
+    let status = settings.merged.status.as_ref().unwrap();
+    assert_eq!(status.text.as_deref(), Some("global"));
+    assert!(settings.merged.motd.is_none());
+    assert!(!settings.merged.allow_project_override);
+}
+
+#[test]
+fn bad_values_are_reported() {
+    let (cfg, issues) = env_config(env(&[
+        ("DX_SHOW_FPS", "maybe"),
+        ("DX_MARKDOWN_ENABLED", "off"),
+        ("DX_NOTIFY_TITLE", "not a setting"),
+    ]));
+    assert_eq!(cfg.unwrap().markdown_enabled, Some(false));
+    assert_eq!(issues.len(), 1);
+    assert!(issues[0].starts_with("DX_SHOW_FPS"));
+
+    let project = tempfile::tempdir().unwrap();
+    let load = |sets: &[&str]| {
+        let sets: Vec<String> = sets.iter().map(|s| (*s).to_string()).collect();
+        LayeredConfig::load(None, project.path(), None, Vec::new(), &sets)
+    };
+    assert!(load(&["sigint_grace=soon"]).is_err());
+    assert!(load(&["no_such_key=1"]).is_err());
+    assert!(load(&["theme"]).is_err());
+    assert_eq!(
+        load(&["sigint_grace=1500ms"])
+            .unwrap()
+            .merged
+            .sigint_grace
+            .as_deref(),
+        Some("1500ms")
+    );
+}
+
+#[test]
+fn bad_config_section_is_reported_and_the_menu_still_loads() {
+    let project = tempfile::tempdir().unwrap();
+    let dx = project.path().join("dx.yaml");
+    fs::write(
+        &dx,
+        "config:\n  show_fps: sometimes\nmenu:\n  - name: Build\n    cmd: make\n",
+    )
+    .unwrap();
+    assert_eq!(load_dx_file(&dx).unwrap().menu.len(), 1);
+
+    let settings = LayeredConfig::load(None, project.path(), Some(&dx), Vec::new(), &[]).unwrap();
+    assert!(settings.layer(ConfigSource::DxFile).is_none());
+    assert_eq!(settings.issues.len(), 1);
+    assert!(settings.issues[0].contains("config:"));
+}
+
+#[test]
+fn saved_values_report_the_layer_that_overrides_them() {
+    let project = tempfile::tempdir().unwrap();
+    fs::write(project.path().join("config.toml"), "theme = \"light\"\n").unwrap();
+    let dx = project.path().join("dx.yaml");
+    fs::write(&dx, "config:\n  theme: dark\nmenu: []\n").unwrap();
+
+    let settings = LayeredConfig::load(None, project.path(), Some(&dx), Vec::new(), &[]).unwrap();
+    let theme = |c: &dx::config::AppConfig| c.theme.is_some();
+    assert_eq!(
+        settings.overridden_by(ConfigSource::Project, theme),
+        Some(ConfigSource::DxFile)
+    );
+    assert_eq!(settings.overridden_by(ConfigSource::DxFile, theme), None);
+    assert_eq!(
+        settings.overridden_by(ConfigSource::Project, |c| c.show_fps.is_some()),
+        None
+    );
+}
+
+#[test]
+fn config_screen_edits_the_file_the_settings_came_from() {
+    let home = tempfile::tempdir().unwrap();
+    let project = tempfile::tempdir().unwrap();
+    fs::create_dir(home.path().join(".dx")).unwrap();
+    fs::write(home.path().join(".dx/config.toml"), "theme = \"dark\"\n").unwrap();
+    fs::write(
+        project.path().join("config.json"),
+        "{\"output_dim\": false}\n",
+    )
+    .unwrap();
+
+    let mut settings =
+        LayeredConfig::load(Some(home.path()), project.path(), None, env(&[]), &[]).unwrap();
+    let state = open_config_state(&settings);
+    assert_eq!(state.path, project.path().join("config.json"));
+    assert!(!state.is_global);
+    assert_eq!(state.cfg.output_dim, Some(false));
+    assert_eq!(state.cfg.theme, None);
+
+    // A saved layer replaces the one loaded at startup
+    let mut cfg = state.cfg.clone();
+    cfg.theme = Some("light".to_string());
+    settings.set_layer(ConfigLayer {
+        source: ConfigSource::Project,
+        path: Some(state.path.clone()),
+        cfg,
+    });
+    assert_eq!(settings.merged.theme.as_deref(), Some("light"));
+    assert_eq!(settings.layers.len(), 2);
+    assert_eq!(
+        open_config_state(&settings).cfg.theme.as_deref(),
+        Some("light")
+    );
+}
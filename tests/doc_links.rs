@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use dx::links::{DocHistory, DocPos, LinkTarget, resolve_link, slugify};
+use dx::markdown::{MdLayout, render_markdown};
+use dx::theme::ThemeTokens;
+
+#[test]
+fn links_resolve_to_urls_anchors_and_local_documents() {
+    let tmp = tempfile::tempdir().unwrap();
+    let doc = tmp.path().join("README.md");
+    std::fs::write(&doc, "# Top\n").unwrap();
+    std::fs::create_dir(tmp.path().join("docs")).unwrap();
+    std::fs::write(tmp.path().join("docs/setup.md"), "# Setup\n").unwrap();
+
+    assert_eq!(
+        resolve_link("https://example.com/a#b", Some(&doc)),
+        Some(LinkTarget::External("https://example.com/a#b".into()))
+    );
+    assert_eq!(
+        resolve_link("#getting-started", Some(&doc)),
+        Some(LinkTarget::Anchor("getting-started".into()))
+    );
+    assert_eq!(
+        resolve_link("docs/setup.md#install", Some(&doc)),
+        Some(LinkTarget::Document {
+            path: tmp.path().join("docs/setup.md"),
+            anchor: Some("install".into()),
+        })
+    );
+    assert_eq!(resolve_link("docs/missing.md", Some(&doc)), None);
+    assert_eq!(resolve_link("javascript:alert(1)", Some(&doc)), None);
+}
+
+#[test]
+fn headings_get_github_anchors_and_links_are_located() {
+    assert_eq!(slugify("Getting Started!"), "getting-started");
+    assert_eq!(slugify("`dx config` & flags"), "dx-config--flags");
+
+    let theme = ThemeTokens::builtin_dark();
+    let md = "# Intro\n\nSee [setup](#setup) and [docs](https://x.dev).\n\n## Setup\n\n## Setup\n";
+    let r = render_markdown(md, &theme, MdLayout::default());
+    let slugs: Vec<&str> = r.headings.iter().map(|h| h.slug.as_str()).collect();
+    assert_eq!(slugs, vec!["intro", "setup", "setup-1"]);
+    assert_eq!(r.headings[1].title, "Setup");
+    assert_eq!(r.text.lines[r.headings[1].line].to_string(), "SETUP");
+
+    assert_eq!(r.link_spots.len(), 2);
+    let spot = r.link_spots[1].as_ref().unwrap();
+    let line = &r.text.lines[spot.line];
+    let drawn: String = line.spans[spot.spans.clone()]
+        .iter()
+        .map(|s| s.content.as_ref())
+        .collect();
+    assert_eq!(drawn, "🔗 docs [2↗]");
+}
+
+#[test]
+fn history_moves_back_and_forward() {
+    let pos = |p: &str, scroll| DocPos {
+        path: PathBuf::from(p),
+        scroll,
+    };
+    let mut h = DocHistory::default();
+    h.visit(pos("a.md", 4));
+    h.visit(pos("b.md", 0));
+    assert_eq!(h.go_back(pos("c.md", 2)), Some(pos("b.md", 0)));
+    assert_eq!(h.go_back(pos("b.md", 0)), Some(pos("a.md", 4)));
+    assert_eq!(h.go_back(pos("a.md", 4)), None);
+    assert_eq!(h.go_forward(pos("a.md", 4)), Some(pos("b.md", 0)));
+    h.visit(pos("b.md", 0));
+    assert!(h.forward.is_empty());
+}
@@ -0,0 +1,27 @@
+use dx::markdown::{MdLayout, current_section, outline, render_markdown};
+use dx::theme::ThemeTokens;
+
+#[test]
+fn outline_lists_h1_to_h3_and_tracks_the_current_section() {
+    let theme = ThemeTokens::builtin_dark();
+    let md = "# Runbook\n\nintro\n\n## Deploy\n\nsteps\n\n#### Detail\n\n### Rollback\n\nmore\n";
+    let r = render_markdown(md, &theme, MdLayout::default());
+    let entries = outline(&r.headings);
+    let titles: Vec<(u32, &str)> = entries
+        .iter()
+        .map(|h| (h.level, h.title.as_str()))
+        .collect();
+    assert_eq!(titles, vec![(1, "Runbook"), (2, "Deploy"), (3, "Rollback")]);
+    for h in &entries {
+        assert_eq!(
+            r.text.lines[h.line].to_string().to_lowercase(),
+            h.title.to_lowercase()
+        );
+    }
+
+    assert_eq!(current_section(&entries, 0), None);
+    assert_eq!(current_section(&entries, entries[0].line), Some(0));
+    assert_eq!(current_section(&entries, entries[1].line + 1), Some(1));
+    assert_eq!(current_section(&entries, entries[2].line), Some(2));
+    assert_eq!(current_section(&entries, 10_000), Some(2));
+}
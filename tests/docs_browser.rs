@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use dx::docs::{doc_title, docs_items, results_item, search_docs};
+use dx::links::{LinkTarget, resolve_link};
+use dx::markdown::{MdLayout, render_markdown};
+use dx::menu::{docs_root, validate_menu};
+use dx::search::SearchState;
+use ratatui::text::Text;
+
+fn write(path: &Path, content: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, content).unwrap();
+}
+
+#[test]
+fn docs_directory_becomes_a_titled_folder_tree() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_path_buf();
+    write(
+        &dir.join("deploy.md"),
+        "```sh\n# not a title\n```\n# Deploying\n\nRun the deploy job, then check the deploy log.\nSee [rollback](ops/rollback.md).\n",
+    );
+    write(
+        &dir.join("notes.md"),
+        "no heading, but deploy is mentioned\n",
+    );
+    write(
+        &dir.join("ops/rollback.md"),
+        "## Rolling back ##\n\nUndo a Deploy.\n",
+    );
+    write(&dir.join("empty/readme.txt"), "not a document\n");
+    write(&dir.join(".hidden/secret.md"), "# Secret deploy\n");
+
+    let items = docs_items(&dir);
+    let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, vec!["ops", "Deploying", "notes"]);
+    assert_eq!(items[0].items[0].name, "Rolling back");
+    assert!(items[1].file.as_deref().unwrap().ends_with("deploy.md"));
+    assert_eq!(doc_title(Path::new("x/plain.md"), "text"), "plain");
+
+    // Lowercase queries ignore case; one hit per line, counting earlier matches in the file
+    let hits = search_docs(&dir, "deploy", &MdLayout::default());
+    let found: Vec<(String, usize, usize)> = hits
+        .iter()
+        .map(|h| (h.title.clone(), h.line, h.nth))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("Deploying".to_string(), 4, 0),
+            ("Deploying".to_string(), 6, 1),
+            ("notes".to_string(), 1, 0),
+            ("Rolling back".to_string(), 3, 0),
+        ]
+    );
+    // Headings are shown uppercased, so a smart-case query skips them, as in the viewer
+    assert_eq!(search_docs(&dir, "Deploy", &MdLayout::default()).len(), 1);
+
+    let results = results_item("deploy", &hits);
+    assert_eq!(results.name, "Search: deploy — 4 matches");
+    assert_eq!(results.items[1].reveal, Some(("deploy".to_string(), 1)));
+    assert_eq!(
+        results.items[1].desc.as_deref(),
+        Some(hits[1].text.as_str())
+    );
+
+    // Relative links between documents resolve against the linking document
+    let doc = dir.join("deploy.md");
+    match resolve_link("ops/rollback.md", Some(&doc)) {
+        Some(LinkTarget::Document { path, .. }) => assert_eq!(path, dir.join("ops/rollback.md")),
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn docs_items_are_validated_and_found_from_inside() {
+    let menu: dx::menu::DxFile = toml::from_str(
+        "[[menu]]\nname = \"Docs\"\ndocs = \"src\"\n\n[[menu]]\nname = \"Bad\"\ndocs = \"no/such/dir\"\ncmd = \"ls\"\n",
+    )
+    .unwrap();
+    let issues = validate_menu(&menu.menu);
+    assert_eq!(issues.len(), 2, "{issues:?}");
+    assert!(issues.iter().all(|i| i.contains("'Bad'")));
+
+    assert_eq!(
+        docs_root(&menu.menu, &[0]),
+        Some((vec![0], "src".to_string()))
+    );
+    assert_eq!(docs_root(&menu.menu, &[]), None);
+}
+
+#[test]
+fn revealed_search_jumps_to_the_requested_match() {
+    let text = Text::from("a deploy\nb\nc deploy deploy\n");
+    let mut search = SearchState::reveal("deploy".to_string(), 2);
+    assert_eq!(search.update(&text, 0), Some(2));
+    assert_eq!(search.current, 2);
+    let mut past_end = SearchState::reveal("deploy".to_string(), 9);
+    assert_eq!(past_end.update(&text, 0), Some(2));
+}
+
+#[test]
+fn hits_count_matches_in_the_rendered_document() {
+    let tmp = tempfile::tempdir().unwrap();
+    let doc = tmp.path().join("guide.md");
+    // The link target moves into a footnote at the end of the rendered document
+    write(&doc, "See [the guide](deploy-guide.md).\n\nTo deploy, run make.\n");
+
+    let hits = search_docs(tmp.path(), "deploy", &MdLayout::default());
+    assert_eq!(hits[0].nth, 0);
+    assert_eq!(hits[0].line, 3);
+    assert_eq!(hits[0].text, "To deploy, run make.");
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_directories_are_not_followed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_path_buf();
+    write(&dir.join("guide/intro.md"), "# Intro\n\nloop here\n");
+    std::os::unix::fs::symlink(&dir, dir.join("guide/back")).unwrap();
+    std::os::unix::fs::symlink(dir.join("guide/intro.md"), dir.join("linked.md")).unwrap();
+
+    let items = docs_items(&dir);
+    let names: Vec<&str> = items.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, vec!["guide", "Intro"]);
+    assert_eq!(items[0].items.len(), 1);
+    assert_eq!(search_docs(&dir, "loop", &MdLayout::default()).len(), 2);
+}
+
+#[test]
+fn hits_are_counted_in_the_viewer_layout() {
+    let tmp = tempfile::tempdir().unwrap();
+    write(
+        &tmp.path().join("steps.md"),
+        "| Step | Notes |\n|---|---|\n| one | first deploy the service then check the deploy log |\n\nAfter the table, deploy again.\n",
+    );
+
+    // A narrow table cell is cut short, so the viewer finds one match fewer before the last
+    let natural = search_docs(tmp.path(), "deploy", &MdLayout::default());
+    assert_eq!(natural.last().unwrap().nth, 2);
+    let narrow = MdLayout {
+        width: Some(30),
+        ..MdLayout::default()
+    };
+    let hits = search_docs(tmp.path(), "deploy", &narrow);
+    assert_eq!(hits.last().unwrap().nth, 1);
+    assert_eq!(hits.last().unwrap().line, 5);
+    let content = std::fs::read_to_string(tmp.path().join("steps.md")).unwrap();
+    let rendered = render_markdown(&content, &Default::default(), narrow).text;
+    let mut search = SearchState::reveal("deploy".to_string(), hits[1].nth);
+    let line = search.update(&rendered, 0).unwrap();
+    assert_eq!(rendered.lines[line].to_string().trim(), "After the table, deploy again.");
+}
@@ -0,0 +1,116 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use dx::keys::{KeyModes, decode_input, encode_key, encode_paste};
+
+#[test]
+fn keys_encode_to_expected_byte_sequences() {
+    let none = KeyModifiers::NONE;
+    let shift = KeyModifiers::SHIFT;
+    let alt = KeyModifiers::ALT;
+    let ctrl = KeyModifiers::CONTROL;
+    let normal = KeyModes::default();
+    let app_cursor = KeyModes {
+        application_cursor: true,
+        ..KeyModes::default()
+    };
+    let table: Vec<(KeyCode, KeyModifiers, KeyModes, &[u8])> = vec![
+        (KeyCode::Char('a'), none, normal, b"a"),
+        (KeyCode::Char('A'), shift, normal, b"A"),
+        (KeyCode::Char('ą'), none, normal, "ą".as_bytes()),
+        (KeyCode::Char('ż'), alt, normal, "\x1bż".as_bytes()),
+        (KeyCode::Char('🦀'), none, normal, "🦀".as_bytes()),
+        (KeyCode::Char('c'), ctrl, normal, b"\x03"),
+        (KeyCode::Char('['), ctrl, normal, b"\x1b"),
+        (KeyCode::Char(' '), ctrl, normal, b"\x00"),
+        (KeyCode::Char('x'), ctrl | alt, normal, b"\x1b\x18"),
+        (KeyCode::Enter, none, normal, b"\r"),
+        (KeyCode::Backspace, none, normal, b"\x7f"),
+        (KeyCode::Backspace, alt, normal, b"\x1b\x7f"),
+        (KeyCode::Tab, none, normal, b"\t"),
+        (KeyCode::BackTab, shift, normal, b"\x1b[Z"),
+        (KeyCode::Esc, none, normal, b"\x1b"),
+        (KeyCode::Up, none, normal, b"\x1b[A"),
+        (KeyCode::Up, none, app_cursor, b"\x1bOA"),
+        (KeyCode::Left, shift, normal, b"\x1b[1;2D"),
+        (KeyCode::Right, alt, app_cursor, b"\x1b[1;3C"),
+        (KeyCode::Down, ctrl, normal, b"\x1b[1;5B"),
+        (KeyCode::Home, none, app_cursor, b"\x1bOH"),
+        (KeyCode::End, ctrl | shift, normal, b"\x1b[1;6F"),
+        (KeyCode::Delete, none, normal, b"\x1b[3~"),
+        (KeyCode::PageUp, ctrl, normal, b"\x1b[5;5~"),
+        (KeyCode::F(1), none, normal, b"\x1bOP"),
+        (KeyCode::F(4), shift, normal, b"\x1b[1;2S"),
+        (KeyCode::F(5), none, normal, b"\x1b[15~"),
+        (KeyCode::F(12), alt, normal, b"\x1b[24;3~"),
+    ];
+    for (code, mods, modes, expected) in table {
+        let got = encode_key(&KeyEvent::new(code, mods), modes);
+        assert_eq!(
+            got.as_deref(),
+            Some(expected),
+            "{code:?} {mods:?} {modes:?}"
+        );
+    }
+
+    let mut release = KeyEvent::new(KeyCode::Char('a'), none);
+    release.kind = KeyEventKind::Release;
+    assert_eq!(encode_key(&release, normal), None);
+}
+
+#[test]
+fn paste_is_bracketed_only_when_enabled() {
+    let on = KeyModes {
+        bracketed_paste: true,
+        ..KeyModes::default()
+    };
+    assert_eq!(
+        encode_paste("zażółć", KeyModes::default()),
+        "zażółć".as_bytes()
+    );
+    assert_eq!(
+        encode_paste("ls\x1b[201~rm", on),
+        b"\x1b[200~lsrm\x1b[201~".to_vec()
+    );
+
+    let mut t = dx::term::Emulator::with_scrollback(5, 20, 0);
+    assert_eq!(t.key_modes(), KeyModes::default());
+    t.process_bytes(b"\x1b[?1h\x1b[?2004h");
+    assert_eq!(
+        t.key_modes(),
+        KeyModes {
+            application_cursor: true,
+            bracketed_paste: true
+        }
+    );
+}
+
+#[test]
+fn typed_input_decodes_back_to_keys() {
+    let none = KeyModifiers::NONE;
+    let keys = [
+        KeyEvent::new(KeyCode::Char('j'), none),
+        KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT),
+        KeyEvent::new(KeyCode::Char('ż'), KeyModifiers::ALT),
+        KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+        KeyEvent::new(KeyCode::Enter, none),
+        KeyEvent::new(KeyCode::Backspace, none),
+        KeyEvent::new(KeyCode::Up, none),
+        KeyEvent::new(KeyCode::Down, KeyModifiers::CONTROL),
+        KeyEvent::new(KeyCode::PageDown, none),
+        KeyEvent::new(KeyCode::F(7), KeyModifiers::SHIFT),
+    ];
+    let mut bytes = Vec::new();
+    for key in &keys {
+        bytes.extend(encode_key(key, KeyModes::default()).unwrap());
+    }
+    assert_eq!(decode_input(&bytes), keys);
+
+    // Application-cursor arrows, a lone Escape and a focus report mixed in
+    assert_eq!(
+        decode_input(b"\x1bOA\x1b[Iq\x1b"),
+        vec![
+            KeyEvent::new(KeyCode::Up, none),
+            KeyEvent::new(KeyCode::Char('q'), none),
+            KeyEvent::new(KeyCode::Esc, none),
+        ]
+    );
+}
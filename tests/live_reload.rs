@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use dx::watch::FileWatcher;
+
+#[test]
+fn watcher_reports_changed_created_and_removed_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let doc = dir.join("doc.md");
+    let motd = dir.join("MOTD.md");
+    std::fs::write(&doc, "# one\n").unwrap();
+
+    let mut watcher = FileWatcher::new(Duration::from_secs(60));
+    watcher.set_watched(&[doc.as_path(), motd.as_path()]);
+    assert!(watcher.check_now().is_empty());
+
+    std::fs::write(&doc, "# one\n\nmore text\n").unwrap();
+    std::fs::write(&motd, "hello\n").unwrap();
+    assert_eq!(watcher.check_now(), vec![motd.clone(), doc.clone()]);
+    assert!(watcher.check_now().is_empty());
+
+    // The first poll checks; the next one waits for the interval
+    std::fs::remove_file(&motd).unwrap();
+    assert_eq!(watcher.poll(), vec![motd.clone()]);
+    std::fs::write(&doc, "# changed again\n").unwrap();
+    assert!(watcher.poll().is_empty());
+
+    // Unwatched files are forgotten
+    watcher.set_watched(&[motd.as_path()]);
+    assert!(watcher.check_now().is_empty());
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use dx::images::{ImageProtocol, fit_cells, kitty_sequence, sixel_sequence};
+use dx::markdown::{MdLayout, render_markdown};
+use dx::theme::ThemeTokens;
+use image::{Rgba, RgbaImage};
+use ratatui::style::Color;
+
+fn two_tone(w: u32, h: u32) -> RgbaImage {
+    RgbaImage::from_fn(w, h, |_, y| {
+        if y < h / 2 {
+            Rgba([255, 0, 0, 255])
+        } else {
+            Rgba([0, 0, 255, 255])
+        }
+    })
+}
+
+#[test]
+fn local_images_are_drawn_as_half_blocks_within_the_viewport() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir(tmp.path().join("assets")).unwrap();
+    two_tone(160, 160)
+        .save(tmp.path().join("assets/diagram.png"))
+        .unwrap();
+
+    let theme = ThemeTokens::builtin_dark();
+    let md = "Intro\n\n![Architecture](./assets/diagram.png)\n\n![Missing](./assets/none.png)\n";
+    let layout = MdLayout {
+        width: Some(40),
+        height: Some(12),
+        wrap: true,
+        images: true,
+        base_dir: Some(tmp.path().to_path_buf()),
+    };
+    // Images are decoded in the background; their alt text shows until then
+    let pending = render_markdown(md, &theme, layout.clone());
+    assert!(pending.images.is_empty());
+    assert!(
+        pending
+            .text
+            .lines
+            .iter()
+            .any(|l| l.to_string() == "🖼 Architecture")
+    );
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let r = loop {
+        let r = render_markdown(md, &theme, layout.clone());
+        if !r.images.is_empty() || Instant::now() > deadline {
+            break r;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    assert_eq!(r.images.len(), 1);
+    let img = &r.images[0];
+    assert_eq!(img.alt, "Architecture");
+    assert!(img.rows <= 10 && img.cols <= 40);
+    let first = &r.text.lines[img.line];
+    assert_eq!(first.spans.len(), img.cols as usize);
+    assert_eq!(first.spans[0].content, "▀");
+    assert_eq!(first.spans[0].style.fg, Some(Color::Rgb(255, 0, 0)));
+    let last = &r.text.lines[img.line + img.rows as usize - 1];
+    assert_eq!(last.spans[0].style.bg, Some(Color::Rgb(0, 0, 255)));
+    // Unreadable images fall back to their alt text
+    assert!(r.text.lines.iter().any(|l| l.to_string() == "🖼 Missing"));
+
+    let off = render_markdown(
+        md,
+        &theme,
+        MdLayout {
+            images: false,
+            ..layout
+        },
+    );
+    assert!(off.images.is_empty());
+    assert!(
+        off.text
+            .lines
+            .iter()
+            .any(|l| l.to_string() == "🖼 Architecture")
+    );
+}
+
+#[test]
+fn sizing_detection_and_protocol_encoding() {
+    assert_eq!(fit_cells(80, 32, 100, 100), (10, 2));
+    assert_eq!(fit_cells(1600, 800, 50, 100), (50, 13));
+    assert_eq!(fit_cells(800, 1600, 100, 10), (10, 10));
+
+    assert_eq!(
+        ImageProtocol::detect_from("xterm-kitty", "", false),
+        Some(ImageProtocol::Kitty)
+    );
+    assert_eq!(
+        ImageProtocol::detect_from("xterm-256color", "iTerm.app", false),
+        Some(ImageProtocol::Iterm2)
+    );
+    assert_eq!(
+        ImageProtocol::detect_from("foot", "", false),
+        Some(ImageProtocol::Sixel)
+    );
+    assert_eq!(
+        ImageProtocol::detect_from("xterm-kitty", "", true),
+        Some(ImageProtocol::Blocks)
+    );
+    assert_eq!(
+        ImageProtocol::detect_from("xterm-256color", "", false),
+        None
+    );
+    assert_eq!(ImageProtocol::parse("off"), Ok(None));
+    assert!(ImageProtocol::parse("ascii").is_err());
+
+    let kitty = kitty_sequence(&vec![7u8; 5000], 10, 4);
+    assert!(kitty.starts_with("\x1b_Ga=T,f=100,c=10,r=4,"));
+    assert_eq!(kitty.matches("\x1b_G").count(), 2);
+    assert!(kitty.contains("\x1b_Gm=0;"));
+
+    let sixel = sixel_sequence(&two_tone(4, 12), 4, 12);
+    assert!(sixel.starts_with("\x1bPq\"1;1;4;12"));
+    assert!(sixel.ends_with("\x1b\\"));
+    assert_eq!(sixel.matches('-').count(), 2);
+}
@@ -0,0 +1,59 @@
+use dx::markdown::{MdLayout, render_markdown, table_widths};
+use dx::theme::ThemeTokens;
+
+const TABLE: &str = "| Key | Default | Notes |\n|:----|:-------:|------:|\n\
+                     | theme | dark | colour scheme used by every screen |\n| wrap | on | x |\n";
+
+fn rows(layout: MdLayout) -> Vec<String> {
+    let theme = ThemeTokens::builtin_dark();
+    render_markdown(TABLE, &theme, layout)
+        .text
+        .lines
+        .iter()
+        .map(ToString::to_string)
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+#[test]
+fn tables_are_drawn_with_borders_and_alignment() {
+    let lines = rows(MdLayout::default());
+    assert_eq!(
+        lines,
+        vec![
+            "┌───────┬─────────┬────────────────────────────────────┐",
+            "│ Key   │ Default │                              Notes │",
+            "├───────┼─────────┼────────────────────────────────────┤",
+            "│ theme │  dark   │ colour scheme used by every screen │",
+            "│ wrap  │   on    │                                  x │",
+            "└───────┴─────────┴────────────────────────────────────┘",
+        ]
+    );
+    let theme = ThemeTokens::builtin_dark();
+    let text = render_markdown(TABLE, &theme, MdLayout::default()).text;
+    assert_eq!(text.lines[0].spans[0].style.fg, Some(theme.border));
+}
+
+#[test]
+fn wide_tables_wrap_or_truncate_to_the_viewport() {
+    let wrapped = rows(MdLayout {
+        width: Some(40),
+        wrap: true,
+        ..MdLayout::default()
+    });
+    assert!(wrapped.iter().all(|l| l.chars().count() <= 40));
+    assert!(wrapped.iter().any(|l| l.contains("colour scheme")));
+    assert!(wrapped.iter().any(|l| l.contains("every screen")));
+
+    let truncated = rows(MdLayout {
+        width: Some(40),
+        wrap: false,
+        ..MdLayout::default()
+    });
+    assert_eq!(truncated.len(), 6);
+    assert!(truncated.iter().all(|l| l.chars().count() <= 40));
+    assert!(truncated[3].contains('…'));
+
+    assert_eq!(table_widths(&[5, 30], Some(20)), vec![5, 8]);
+    assert_eq!(table_widths(&[5, 30], None), vec![5, 30]);
+}
@@ -67,3 +67,57 @@ fn markdown_links_and_footnotes_render() {
         .to_string();
     assert!(!joined.is_empty());
 }
+
+#[test]
+fn menu_item_run_policy_parses_timeout_and_backoff() {
+    let tmp = tempfile::tempdir().unwrap();
+    let y = tmp.path().join("menu.yaml");
+    std::fs::write(
+        &y,
+        "menu:\n  - name: Flaky\n    cmd: ./flaky.sh\n    timeout: 1m30s\n    retries: 2\n    backoff: 500ms\n",
+    )
+    .unwrap();
+    let m = dx::menu::load_menu(&y).unwrap();
+    let policy = m.items[0].run_policy();
+    assert_eq!(policy.timeout, Some(std::time::Duration::from_secs(90)));
+    assert_eq!(policy.retries, 2);
+    assert_eq!(policy.delay_before(1), std::time::Duration::ZERO);
+    assert_eq!(policy.delay_before(3), std::time::Duration::from_secs(1));
+    assert!(dx::menu::validate_menu(&m.items).is_empty());
+    assert_eq!(dx::menu::parse_duration("soon"), None);
+}
+
+#[test]
+fn menu_shortcuts_match_chords_and_report_conflicts() {
+    let tmp = tempfile::tempdir().unwrap();
+    let y = tmp.path().join("menu.yaml");
+    std::fs::write(
+        &y,
+        "menu:\n  - name: Build\n    cmd: make\n    key: b\n  - name: Git\n    items:\n      - name: Status\n        cmd: git status\n        key: g s\n        key_global: true\n      - name: Log\n        cmd: git log\n        key: l\n",
+    )
+    .unwrap();
+    let m = dx::menu::load_menu(&y).unwrap();
+    use dx::menu::{ShortcutMatch, match_shortcut};
+    assert_eq!(match_shortcut(&m.items, &[], &['b']), ShortcutMatch::Item(vec![0]));
+    assert_eq!(match_shortcut(&m.items, &[], &['g']), ShortcutMatch::Pending);
+    assert_eq!(match_shortcut(&m.items, &[], &['g', 's']), ShortcutMatch::Item(vec![1, 0]));
+    // Folder-local keys only work inside their folder
+    assert_eq!(match_shortcut(&m.items, &[], &['l']), ShortcutMatch::NoMatch);
+    assert_eq!(match_shortcut(&m.items, &[1], &['l']), ShortcutMatch::Item(vec![1, 1]));
+    assert!(dx::menu::validate_menu(&m.items).is_empty());
+
+    std::fs::write(
+        &y,
+        "menu:\n  - name: Quit-ish\n    cmd: 'true'\n    key: q\n  - name: Find\n    cmd: 'true'\n    key: /\n  - name: Go\n    cmd: 'true'\n    key: g\n  - name: Git status\n    cmd: git status\n    key: g s\n",
+    )
+    .unwrap();
+    let m = dx::menu::load_menu(&y).unwrap();
+    let issues = dx::menu::validate_menu(&m.items);
+    assert!(issues.iter().any(|i| i.contains("built-in key 'q'")));
+    // The clashing binding is ignored so q still quits
+    assert_eq!(match_shortcut(&m.items, &[], &['q']), ShortcutMatch::NoMatch);
+    // `/` opens docs search in the menu, so it cannot be bound either
+    assert!(issues.iter().any(|i| i.contains("built-in key '/'")));
+    assert_eq!(match_shortcut(&m.items, &[], &['/']), ShortcutMatch::NoMatch);
+    assert!(issues.iter().any(|i| i.contains("Shortcut 'g' on 'Go' conflicts with 'g s'")));
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use dx::motd::{
+    PENDING, builtin_vars, command_vars, is_template, render_template, template_names, today,
+};
+
+fn lines(s: &[&str]) -> Vec<String> {
+    s.iter().map(|l| (*l).to_string()).collect()
+}
+
+#[test]
+fn placeholders_are_filled_or_left_pending() {
+    let mut commands = HashMap::new();
+    commands.insert("ci".to_string(), "echo green".to_string());
+    let names = template_names(&commands);
+    let template = lines(&[
+        "# {{project.name}}",
+        "on `{{ git.branch }}` ({{git.dirty}}) · CI: {{ci}}",
+        "unclosed {{ stays",
+        "Go: {{ .Values.image }}",
+    ]);
+    assert!(is_template(&template, &names));
+    assert!(!is_template(&lines(&["# plain"]), &names));
+    // Only known names are placeholders; other braces are left as written
+    assert!(!is_template(
+        &lines(&["{{#each items}}{{this}}{{/each}}"]),
+        &names
+    ));
+
+    let mut vars = HashMap::new();
+    vars.insert("project.name".to_string(), "dx".to_string());
+    vars.insert("git.branch".to_string(), "main".to_string());
+    vars.insert("git.dirty".to_string(), "clean".to_string());
+    assert_eq!(
+        render_template(&template, &names, &vars),
+        lines(&[
+            "# dx",
+            &format!("on `main` (clean) · CI: {PENDING}"),
+            "unclosed {{ stays",
+            "Go: {{ .Values.image }}",
+        ])
+    );
+}
+
+#[test]
+fn builtin_values_are_available() {
+    let vars = builtin_vars();
+    for key in ["user", "date", "project.name", "git.branch", "git.dirty"] {
+        assert!(vars.contains_key(key), "missing {key}");
+    }
+    let date = today();
+    assert_eq!(date.len(), 10);
+    assert_eq!(&date[4..5], "-");
+    assert_eq!(vars["date"], date);
+}
+
+#[tokio::test]
+async fn commands_run_concurrently_with_a_timeout() {
+    let mut commands = HashMap::new();
+    commands.insert(
+        "ok".to_string(),
+        "echo; echo passing; echo more".to_string(),
+    );
+    commands.insert("slow".to_string(), "sleep 5; echo late".to_string());
+    commands.insert("fail".to_string(), "echo nope; exit 3".to_string());
+    let started = Instant::now();
+    let vars = command_vars(&commands, Duration::from_millis(500)).await;
+    assert!(started.elapsed() < Duration::from_secs(3));
+    assert_eq!(vars["ok"], "passing");
+    assert_eq!(vars["slow"], "?");
+    assert_eq!(vars["fail"], "?");
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use dx::exec::format_duration;
+use dx::notify::{Completion, Notifier, NotifyMethod, escape_sequence};
+
+fn done(exit_status: Option<i32>, secs: u64) -> Completion {
+    Completion {
+        title: "Build: cargo build".to_string(),
+        exit_status,
+        duration: Duration::from_secs(secs),
+    }
+}
+
+#[test]
+fn notification_sequences_carry_title_status_and_duration() {
+    let ok = done(Some(0), 723);
+    assert_eq!(ok.body(), "succeeded in 12m 3s");
+    assert_eq!(
+        escape_sequence(&NotifyMethod::Osc9, &ok).unwrap(),
+        b"\x1b]9;Build: cargo build: succeeded in 12m 3s\x07"
+    );
+
+    let mut failed = done(Some(2), 45);
+    failed.title = "a;b\x07".to_string();
+    assert_eq!(
+        escape_sequence(&NotifyMethod::Osc777, &failed).unwrap(),
+        b"\x1b]777;notify;a,b;failed (exit 2) in 45s\x07"
+    );
+    assert_eq!(
+        escape_sequence(&NotifyMethod::Bell, &failed).unwrap(),
+        b"\x07"
+    );
+    assert!(escape_sequence(&NotifyMethod::Command("true".into()), &failed).is_none());
+    assert_eq!(done(None, 1).status(), "terminated");
+}
+
+#[test]
+fn notifier_threshold_and_method_parsing() {
+    let n = Notifier {
+        after: Duration::from_secs(30),
+        method: NotifyMethod::Bell,
+    };
+    assert!(!n.should_notify(Duration::from_secs(29)));
+    assert!(n.should_notify(Duration::from_secs(30)));
+
+    assert_eq!(NotifyMethod::parse("OSC777"), Some(NotifyMethod::Osc777));
+    assert_eq!(NotifyMethod::parse("bell"), Some(NotifyMethod::Bell));
+    assert_eq!(NotifyMethod::parse("toast"), None);
+    assert_eq!(format_duration(Duration::from_millis(4200)), "4.2s");
+    assert_eq!(format_duration(Duration::from_secs(3900)), "1h 5m");
+}
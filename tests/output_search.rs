@@ -0,0 +1,35 @@
+use ansi_to_tui::IntoText;
+use ratatui::style::{Modifier, Style};
+
+#[test]
+fn search_ignores_ansi_escapes_and_is_smart_case() {
+    let text = "\x1b[31merror\x1b[0m: build failed\nok\nERROR again"
+        .into_text()
+        .unwrap();
+    // The "31m" inside the colour escape is not visible text
+    assert!(dx::search::find_matches(&text, "31m").is_empty());
+    let hits = dx::search::find_matches(&text, "error");
+    assert_eq!(hits.len(), 2);
+    assert_eq!((hits[0].line, hits[0].start, hits[0].end), (0, 0, 5));
+    assert_eq!(hits[1].line, 2);
+    // An uppercase letter makes the query case-sensitive
+    assert_eq!(dx::search::find_matches(&text, "ERROR").len(), 1);
+}
+
+#[test]
+fn search_steps_wrap_and_highlight_splits_spans() {
+    let mut text = "one foo\ntwo\nfoo three".into_text().unwrap();
+    let mut search = dx::search::SearchState::new();
+    search.query = "foo".into();
+    search.jump = true;
+    assert_eq!(search.update(&text, 1), Some(2));
+    assert_eq!(search.position_label(), "2/2");
+    search.step(true);
+    assert_eq!(search.update(&text, 0), Some(0));
+
+    let hit = Style::default().add_modifier(Modifier::REVERSED);
+    dx::search::highlight(&mut text, &search.matches, Some(search.current), hit, hit);
+    let first = &text.lines[0];
+    assert_eq!(first.spans.last().unwrap().content, "foo");
+    assert!(first.spans.last().unwrap().style.add_modifier.contains(Modifier::REVERSED));
+}
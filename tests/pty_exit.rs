@@ -0,0 +1,87 @@
+use std::time::{Duration, Instant};
+
+use dx::exec::{
+    OutputMsg, ProcessExit, StopStage, group_survivors, pty_signal, pty_try_wait, spawn_pty,
+};
+
+fn wait_exit(cmd: &str) -> ProcessExit {
+    let mut spawned = spawn_pty(cmd).expect("spawn pty");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(exit) = pty_try_wait(&mut spawned.child).expect("wait") {
+            return exit;
+        }
+        assert!(Instant::now() < deadline, "command did not exit: {cmd}");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn wait_for_output(rx: &mut tokio::sync::mpsc::Receiver<OutputMsg>, needle: &str) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        match rx.try_recv() {
+            Ok(OutputMsg::Line(l) | OutputMsg::ReplaceCurrent(l)) if l.contains(needle) => return,
+            Ok(_) => {}
+            Err(_) => std::thread::sleep(Duration::from_millis(20)),
+        }
+        assert!(Instant::now() < deadline, "no {needle:?} in output");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pty_reports_exit_code_and_signal() {
+    let ok = wait_exit("true");
+    assert!(ok.success());
+    assert_eq!(ok.describe(), "exit 0");
+
+    let failed = wait_exit("exit 3");
+    assert_eq!(failed.code, Some(3));
+    assert_eq!(failed.exit_code(), 3);
+    assert!(!failed.success());
+
+    let killed = wait_exit("kill -TERM $$");
+    assert_eq!(killed.signal, Some(15));
+    assert_eq!(killed.exit_code(), 143);
+    assert_eq!(killed.describe(), "killed by SIGTERM");
+    assert!(killed.usage.is_some());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stop_escalates_through_the_process_group() {
+    // The shell ignores SIGINT, so only the SIGTERM stage ends it; the background
+    // grandchild shares the group and must go too.
+    let mut spawned = spawn_pty("trap '' INT; sleep 30 & echo ready; wait").expect("spawn pty");
+    let pgid = spawned.child.process_id().expect("pid");
+    wait_for_output(&mut spawned.rx, "ready");
+
+    pty_signal(&mut spawned.child, StopStage::Interrupt);
+    std::thread::sleep(Duration::from_millis(300));
+    assert!(pty_try_wait(&mut spawned.child).expect("wait").is_none());
+
+    pty_signal(&mut spawned.child, StopStage::Terminate);
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let exit = loop {
+        if let Some(exit) = pty_try_wait(&mut spawned.child).expect("wait") {
+            break exit;
+        }
+        assert!(Instant::now() < deadline, "SIGTERM did not stop the shell");
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    assert_eq!(exit.signal, Some(15));
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(group_survivors(pgid).is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn survivors_lists_processes_left_in_the_group() {
+    let mut spawned = spawn_pty("nohup sleep 30 >/dev/null 2>&1 & sleep 0.3").expect("spawn pty");
+    let pgid = spawned.child.process_id().expect("pid");
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while pty_try_wait(&mut spawned.child).expect("wait").is_none() {
+        assert!(Instant::now() < deadline, "command did not exit");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let survivors = group_survivors(pgid);
+    assert!(survivors.iter().any(|p| p.name == "sleep"), "{survivors:?}");
+    pty_signal(&mut spawned.child, StopStage::Kill);
+}
@@ -0,0 +1,63 @@
+use dx::history::{
+    DiffLine, RunRecord, diff_lines, latest_run, list_runs, project_key, save_run, strip_ansi,
+};
+
+fn lines(s: &[&str]) -> Vec<String> {
+    s.iter().map(|l| (*l).to_string()).collect()
+}
+
+#[test]
+fn strip_ansi_and_diff_runs() {
+    assert_eq!(
+        strip_ansi("\x1b[31;1merror\x1b[0m: \x1b]8;;http://x\x07link\x1b]8;;\x1b\\ done\r"),
+        "error: link done"
+    );
+
+    let old = lines(&["compiling", "warning: unused x", "test a ... ok", "done"]);
+    let new = lines(&["compiling", "test a ... ok", "test b ... FAILED", "done"]);
+    assert_eq!(
+        diff_lines(&old, &new),
+        vec![
+            DiffLine::Same("compiling".into()),
+            DiffLine::Removed("warning: unused x".into()),
+            DiffLine::Same("test a ... ok".into()),
+            DiffLine::Added("test b ... FAILED".into()),
+            DiffLine::Same("done".into()),
+        ]
+    );
+    assert!(
+        diff_lines(&old, &old)
+            .iter()
+            .all(|d| matches!(d, DiffLine::Same(_)))
+    );
+}
+
+#[test]
+fn save_run_keeps_the_last_n_runs() {
+    let tmp = tempfile::tempdir().unwrap();
+    for i in 0..4u64 {
+        let record = RunRecord {
+            title: "Tests: cargo test".into(),
+            started_ms: 1_700_000_000_000 + i,
+            duration_ms: 10,
+            exit_status: Some(i as i32),
+            lines: vec![format!("run {i}")],
+        };
+        save_run(tmp.path(), "tests/unit", &record, 3).unwrap();
+    }
+    let runs = list_runs(tmp.path(), "tests/unit");
+    assert_eq!(runs.len(), 3);
+    assert!(runs[0].ends_with("tests_unit/1700000000001.json"));
+    let latest = latest_run(tmp.path(), "tests/unit").unwrap();
+    assert_eq!(latest.lines, vec!["run 3".to_string()]);
+    assert_eq!(latest.exit_status, Some(3));
+}
+
+#[test]
+fn project_key_separates_projects() {
+    let a = project_key(std::path::Path::new("/work/api"), "build");
+    let b = project_key(std::path::Path::new("/home/me/api"), "build");
+    assert!(a.starts_with("build@api-"));
+    assert_ne!(a, b);
+    assert_eq!(a, project_key(std::path::Path::new("/work/api"), "build"));
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use dx::exec::{TIMEOUT_EXIT_CODE, run_blocking_with_policy};
+use dx::menu::RunPolicy;
+
+/// A `sh -c` command that first appends a line to `log`, so attempts can be counted.
+fn counted(log: &Path, script: &str) -> impl FnMut() -> Command {
+    let script = format!("echo run >> '{}'; {script}", log.display());
+    move || {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(&script);
+        cmd
+    }
+}
+
+fn attempts(log: &Path) -> usize {
+    std::fs::read_to_string(log)
+        .unwrap_or_default()
+        .lines()
+        .count()
+}
+
+#[test]
+fn timed_out_runs_are_retried_and_report_the_timeout_code() {
+    let tmp = tempfile::tempdir().unwrap();
+    let log = tmp.path().join("runs");
+    let policy = RunPolicy {
+        timeout: Some(Duration::from_millis(200)),
+        retries: 1,
+        backoff: Duration::from_millis(10),
+        history: None,
+    };
+    let code = run_blocking_with_policy(counted(&log, "exec sleep 5"), &policy).unwrap();
+    assert_eq!(code, TIMEOUT_EXIT_CODE);
+    assert_eq!(attempts(&log), 2);
+}
+
+/// Whether `pid` is still running (zombies waiting to be reaped count as gone).
+fn running(pid: &str) -> bool {
+    let out = Command::new("ps")
+        .args(["-o", "state=", "-p", pid])
+        .output()
+        .unwrap();
+    let state = String::from_utf8_lossy(&out.stdout);
+    !state.trim().is_empty() && !state.trim().starts_with('Z')
+}
+
+#[test]
+fn timeouts_stop_processes_the_command_started() {
+    let tmp = tempfile::tempdir().unwrap();
+    let log = tmp.path().join("runs");
+    let started = tmp.path().join("sleep");
+    let policy = RunPolicy {
+        timeout: Some(Duration::from_millis(300)),
+        ..RunPolicy::default()
+    };
+    // The shell starts `sleep` as a child of its own instead of replacing itself with it
+    let script = format!("sleep 30 & echo $! > '{}'; wait", started.display());
+    let code = run_blocking_with_policy(counted(&log, &script), &policy).unwrap();
+    assert_eq!(code, TIMEOUT_EXIT_CODE);
+
+    let pid = std::fs::read_to_string(&started).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while running(pid.trim()) {
+        assert!(
+            Instant::now() < deadline,
+            "sleep {} left running",
+            pid.trim()
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn failed_runs_are_retried_until_one_succeeds() {
+    let tmp = tempfile::tempdir().unwrap();
+    let log = tmp.path().join("runs");
+    let policy = RunPolicy {
+        retries: 3,
+        backoff: Duration::from_millis(10),
+        ..RunPolicy::default()
+    };
+    // Fails on the first attempt only
+    let script = format!("[ $(wc -l < '{}') -ge 2 ]", log.display());
+    let code = run_blocking_with_policy(counted(&log, &script), &policy).unwrap();
+    assert_eq!(code, 0);
+    assert_eq!(attempts(&log), 2);
+
+    // Without retries the first exit code is returned
+    let log = tmp.path().join("once");
+    let code = run_blocking_with_policy(counted(&log, "exit 3"), &RunPolicy::default()).unwrap();
+    assert_eq!(code, 3);
+    assert_eq!(attempts(&log), 1);
+}
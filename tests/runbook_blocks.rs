@@ -0,0 +1,45 @@
+use dx::markdown::{MdLayout, render_markdown, run_attr};
+use dx::theme::ThemeTokens;
+
+#[test]
+fn run_attribute_marks_blocks_runnable_or_dangerous() {
+    assert_eq!(run_attr("sh"), Some(false));
+    assert_eq!(run_attr("bash title=\"setup\""), Some(false));
+    assert_eq!(run_attr("rust"), None);
+    assert_eq!(run_attr(""), None);
+    assert_eq!(run_attr("python dx-run"), Some(false));
+    assert_eq!(run_attr("sh dx-run=danger"), Some(true));
+    assert_eq!(run_attr("bash {dx-run=confirm}"), Some(true));
+    assert_eq!(run_attr("sh dx-run=no"), None);
+}
+
+#[test]
+fn shell_blocks_are_collected_with_their_header_lines() {
+    let theme = ThemeTokens::builtin_dark();
+    let md = "# Deploy\n\n```sh\necho one\necho two\n```\n\n```rust\nfn main() {}\n```\n\n\
+              ```bash dx-run=danger\nrm -rf build\n```\n\n```sh dx-run=no\nexample\n```\n";
+    let rendered = render_markdown(md, &theme, MdLayout::default());
+    assert_eq!(rendered.blocks.len(), 2);
+
+    let first = &rendered.blocks[0];
+    assert_eq!(first.code, "echo one\necho two\n");
+    assert_eq!(first.lang, "sh");
+    assert!(!first.dangerous);
+    assert_eq!(rendered.text.lines[first.header_line].to_string(), "▶ sh");
+    assert_eq!(
+        rendered.text.lines[first.header_line + 1].to_string(),
+        "echo one"
+    );
+
+    let second = &rendered.blocks[1];
+    assert_eq!(second.code, "rm -rf build\n");
+    assert!(second.dangerous);
+    assert_eq!(
+        rendered.text.lines[second.header_line].to_string(),
+        "⚠ bash"
+    );
+    assert_eq!(
+        rendered.text.lines[second.header_line].spans[0].style.fg,
+        Some(theme.accent_danger)
+    );
+}
@@ -0,0 +1,80 @@
+use std::path::Path;
+
+use dx::highlight::{highlight_lines, syntax_for_file, syntax_for_token};
+use dx::markdown::markdown_to_text_with_links;
+use dx::theme::ThemeTokens;
+use ratatui::style::{Color, Style};
+
+fn color_of(line: &ratatui::text::Line, needle: &str) -> Option<Color> {
+    line.spans
+        .iter()
+        .find(|s| s.content.contains(needle))
+        .and_then(|s| s.style.fg)
+}
+
+#[test]
+fn fenced_code_blocks_use_theme_syntax_colours() {
+    let theme = ThemeTokens::builtin_dark();
+    let md = "```rust\n// hi\nfn main() { let s = \"x\"; }\n```\n\n```\nplain\n```\n";
+    let (text, _) = markdown_to_text_with_links(md, &theme);
+    let comment = text
+        .lines
+        .iter()
+        .find(|l| l.to_string() == "// hi")
+        .unwrap();
+    assert_eq!(color_of(comment, "hi"), Some(theme.syntax_comment));
+    let code = text
+        .lines
+        .iter()
+        .find(|l| l.to_string().starts_with("fn main"))
+        .unwrap();
+    assert_eq!(color_of(code, "fn"), Some(theme.syntax_keyword));
+    assert_eq!(color_of(code, "\"x\""), Some(theme.syntax_string));
+    // No info string: the single code colour as before
+    let plain = text
+        .lines
+        .iter()
+        .find(|l| l.to_string() == "plain")
+        .unwrap();
+    assert_eq!(color_of(plain, "plain"), Some(theme.code));
+
+    let light = ThemeTokens::builtin_light();
+    let (text, _) = markdown_to_text_with_links(md, &light);
+    let comment = text
+        .lines
+        .iter()
+        .find(|l| l.to_string() == "// hi")
+        .unwrap();
+    assert_eq!(color_of(comment, "hi"), Some(light.syntax_comment));
+}
+
+#[test]
+fn files_are_detected_by_extension_or_shebang() {
+    assert_eq!(
+        syntax_for_file(Path::new("src/main.rs"), "fn main() {}")
+            .unwrap()
+            .name,
+        "Rust"
+    );
+    assert_eq!(
+        syntax_for_file(Path::new("scripts/deploy"), "#!/bin/bash\necho hi")
+            .unwrap()
+            .name,
+        "Bourne Again Shell (bash)"
+    );
+    assert!(syntax_for_file(Path::new("notes"), "just words").is_none());
+    assert!(syntax_for_token("yaml title=\"x\"").is_some());
+    assert!(syntax_for_token("").is_none());
+
+    let theme = ThemeTokens::builtin_dark();
+    let yaml = syntax_for_token("yml").unwrap();
+    let lines = highlight_lines(
+        "# c\nkey: 42\n",
+        yaml,
+        &theme,
+        Style::default().fg(theme.text_primary),
+    );
+    assert_eq!(lines.len(), 2);
+    assert_eq!(color_of(&lines[0], "c"), Some(theme.syntax_comment));
+    assert_eq!(color_of(&lines[1], "42"), Some(theme.syntax_number));
+}
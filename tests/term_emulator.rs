@@ -0,0 +1,158 @@
+use dx::term::Emulator;
+
+/// Visible row `row` as plain text.
+fn row(t: &Emulator, row: usize) -> String {
+    t.render_text(false).lines[row].to_string()
+}
+
+fn numbered_lines(n: usize) -> Vec<u8> {
+    (1..=n)
+        .map(|i| format!("line {i}\r\n"))
+        .collect::<String>()
+        .into_bytes()
+}
+
+#[test]
+fn emulator_scrollback_keeps_rows_and_stays_pinned() {
+    let mut t = Emulator::with_scrollback(5, 20, 100);
+    t.process_bytes(&numbered_lines(20));
+    assert_eq!(t.scrollback_offset(), 0);
+    assert_eq!(row(&t, 3), "line 20");
+
+    t.scroll_up(10);
+    assert_eq!(t.scrollback_offset(), 10);
+    assert_eq!(row(&t, 0), "line 7");
+
+    // New output arrives while scrolled back: the view stays on the same rows
+    t.process_bytes(b"line 21\r\n");
+    assert_eq!(t.scrollback_offset(), 11);
+    assert_eq!(row(&t, 0), "line 7");
+
+    t.scroll_down(usize::MAX);
+    assert_eq!(t.scrollback_offset(), 0);
+    assert_eq!(row(&t, 3), "line 21");
+}
+
+#[test]
+fn emulator_scrollback_is_bounded() {
+    let mut t = Emulator::with_scrollback(5, 20, 3);
+    t.process_bytes(&numbered_lines(20));
+    t.scroll_up(1000);
+    assert_eq!(t.scrollback_offset(), 3);
+}
+
+#[test]
+fn emulator_render_text_keeps_colours_attributes_and_cursor() {
+    use ratatui::style::{Color, Modifier};
+    let mut t = Emulator::with_scrollback(3, 20, 0);
+    t.process_bytes(
+        b"\x1b[1;31mred\x1b[0m \x1b[38;5;208mo\x1b[48;2;1;2;3mrgb\x1b[0m\r\n\x1b[7mrev\x1b[0m x",
+    );
+    let text = t.render_text(true);
+    let first = &text.lines[0];
+    assert_eq!(first.spans[0].content, "red");
+    assert_eq!(first.spans[0].style.fg, Some(Color::Indexed(1)));
+    assert!(first.spans[0].style.add_modifier.contains(Modifier::BOLD));
+    assert_eq!(first.spans[2].content, "o");
+    assert_eq!(first.spans[2].style.fg, Some(Color::Indexed(208)));
+    assert_eq!(first.spans[3].content, "rgb");
+    assert_eq!(first.spans[3].style.bg, Some(Color::Rgb(1, 2, 3)));
+
+    let second = &text.lines[1];
+    assert_eq!(second.spans[0].content, "rev");
+    assert!(
+        second.spans[0]
+            .style
+            .add_modifier
+            .contains(Modifier::REVERSED)
+    );
+    // Cursor sits right after "x" and is drawn as a reversed blank
+    let last = second.spans.last().unwrap();
+    assert_eq!(last.content, " ");
+    assert!(last.style.add_modifier.contains(Modifier::REVERSED));
+    assert!(
+        !t.render_text(false).lines[1]
+            .spans
+            .last()
+            .unwrap()
+            .style
+            .add_modifier
+            .contains(Modifier::REVERSED)
+    );
+}
+
+#[test]
+fn emulator_encodes_mouse_for_the_mode_the_program_enabled() {
+    use crossterm::event::{KeyModifiers, MouseButton, MouseEventKind};
+    let none = KeyModifiers::NONE;
+    let mut t = Emulator::with_scrollback(24, 80, 0);
+    assert!(!t.wants_mouse());
+    assert_eq!(
+        t.encode_mouse(MouseEventKind::Down(MouseButton::Left), none, 0, 0),
+        None
+    );
+
+    // 1000 (press/release), legacy encoding
+    t.process_bytes(b"\x1b[?1000h");
+    assert!(t.wants_mouse());
+    assert_eq!(
+        t.encode_mouse(MouseEventKind::Down(MouseButton::Left), none, 4, 2),
+        Some(b"\x1b[M %#".to_vec())
+    );
+    assert_eq!(
+        t.encode_mouse(MouseEventKind::Up(MouseButton::Left), none, 4, 2),
+        Some(b"\x1b[M#%#".to_vec())
+    );
+    // Drags are not reported without 1002
+    assert_eq!(
+        t.encode_mouse(MouseEventKind::Drag(MouseButton::Left), none, 5, 2),
+        None
+    );
+
+    // 1002 + 1006 SGR: drags, modifiers and releases with the real button
+    t.process_bytes(b"\x1b[?1002h\x1b[?1006h");
+    assert_eq!(
+        t.encode_mouse(MouseEventKind::Drag(MouseButton::Left), none, 5, 2),
+        Some(b"\x1b[<32;6;3M".to_vec())
+    );
+    assert_eq!(
+        t.encode_mouse(
+            MouseEventKind::Up(MouseButton::Right),
+            KeyModifiers::CONTROL,
+            0,
+            0
+        ),
+        Some(b"\x1b[<18;1;1m".to_vec())
+    );
+    assert_eq!(
+        t.encode_mouse(MouseEventKind::ScrollUp, none, 9, 9),
+        Some(b"\x1b[<64;10;10M".to_vec())
+    );
+}
+
+#[test]
+fn emulator_tracks_focus_reporting() {
+    let mut t = Emulator::with_scrollback(24, 80, 0);
+    assert!(!t.wants_focus());
+    t.process_bytes(b"\x1b[?1004h");
+    assert!(t.wants_focus());
+    t.process_bytes(b"\x1b[?25;1004l");
+    assert!(!t.wants_focus());
+}
+
+#[test]
+fn history_lines_walk_the_whole_scrollback() {
+    let mut t = Emulator::with_scrollback(5, 20, 100);
+    t.process_bytes(&numbered_lines(23));
+    t.scroll_up(3);
+    let lines = t.history_lines();
+    let expected: Vec<String> = (1..=23).map(|i| format!("line {i}")).collect();
+    assert_eq!(lines[..23], expected[..]);
+    assert_eq!(lines[23], "");
+    // The view keeps its place
+    assert_eq!(t.scrollback_offset(), 3);
+
+    let mut bounded = Emulator::with_scrollback(5, 20, 3);
+    bounded.process_bytes(&numbered_lines(20));
+    assert_eq!(bounded.history_lines()[0], "line 14");
+}
@@ -0,0 +1,71 @@
+use dx::theme::{
+    ThemeTokens, auto_theme, colorfgbg_is_dark, is_dark_background, load_theme_from,
+    parse_osc11_reply, strip_query_replies,
+};
+
+#[test]
+fn osc11_replies_are_parsed_and_classified() {
+    let dark = parse_osc11_reply(b"\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\\x1b[?62;22c").unwrap();
+    assert_eq!(dark, (0x1e, 0x1e, 0x2e));
+    assert!(is_dark_background(dark));
+
+    let light = parse_osc11_reply(b"\x1b]11;rgb:ff/fa/f0\x07").unwrap();
+    assert_eq!(light, (0xff, 0xfa, 0xf0));
+    assert!(!is_dark_background(light));
+
+    assert_eq!(
+        parse_osc11_reply(b"\x1b]11;rgb:f/8/0\x07"),
+        Some((255, 136, 0))
+    );
+    // Only the DA1 answer came back: no OSC 11 support
+    assert_eq!(parse_osc11_reply(b"\x1b[?62;22c"), None);
+    assert_eq!(parse_osc11_reply(b"\x1b]11;rgb:zz/00/00\x07"), None);
+
+    // Keys typed while the query was in flight are kept
+    assert_eq!(
+        strip_query_replies(b"j\x1b]11;rgb:1e1e/1e1e/2e2e\x1b\\k\x1b[?62;22c\x1b[A"),
+        b"jk\x1b[A".to_vec()
+    );
+}
+
+#[test]
+fn colorfgbg_falls_back_to_palette_indexes() {
+    assert_eq!(colorfgbg_is_dark("15;0"), Some(true));
+    assert_eq!(colorfgbg_is_dark("0;default;15"), Some(false));
+    assert_eq!(colorfgbg_is_dark("12;7"), Some(false));
+    assert_eq!(colorfgbg_is_dark("default"), None);
+}
+
+#[test]
+fn auto_picks_builtin_or_family_pair() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().to_str().unwrap();
+    std::fs::write(tmp.path().join("solar-light.dx-theme"), "tokens: {}\n").unwrap();
+
+    assert_eq!(
+        auto_theme(true, None, Some(dir)),
+        ("dark".to_string(), None)
+    );
+    assert_eq!(
+        auto_theme(false, Some("solar"), Some(dir)),
+        (
+            "solar-light".to_string(),
+            Some(tmp.path().join("solar-light.dx-theme"))
+        )
+    );
+    // Missing family member: the built-in theme for that background
+    assert_eq!(
+        auto_theme(true, Some("solar"), Some(dir)),
+        ("dark".to_string(), None)
+    );
+
+    let tokens = load_theme_from(
+        Some("solar-light"),
+        None,
+        None::<&std::collections::HashMap<String, String>>,
+    );
+    assert_eq!(
+        tokens.text_primary,
+        ThemeTokens::builtin_light().text_primary
+    );
+}
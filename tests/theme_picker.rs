@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use dx::theme::{ThemeTokens, list_themes, load_theme_from, resolve_theme_file};
+use ratatui::style::Color;
+
+#[test]
+fn picker_lists_builtins_then_theme_files() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::write(
+        tmp.path().join("solar.dx-theme"),
+        "tokens:\n  border: \"#112233\"\n",
+    )
+    .unwrap();
+    std::fs::write(tmp.path().join("amber.dx-theme"), "tokens: {}\n").unwrap();
+    std::fs::write(tmp.path().join("notes.txt"), "not a theme\n").unwrap();
+
+    let names: Vec<String> = list_themes(Some(tmp.path()))
+        .into_iter()
+        .map(|c| c.name)
+        .collect();
+    assert_eq!(names, vec!["dark", "light", "amber", "solar"]);
+    assert_eq!(list_themes(None).len(), 2);
+
+    let dir = tmp.path().to_str().unwrap();
+    let solar = tmp.path().join("solar.dx-theme");
+    assert_eq!(
+        resolve_theme_file(Some("solar"), None, Some(dir)),
+        Some(solar.clone())
+    );
+    assert_eq!(resolve_theme_file(Some("dark"), None, Some(dir)), None);
+    assert_eq!(resolve_theme_file(Some("missing"), None, Some(dir)), None);
+    assert_eq!(
+        resolve_theme_file(Some("dark"), Some("/x/y.dx-theme"), Some(dir)),
+        Some("/x/y.dx-theme".into())
+    );
+
+    // File tokens apply over the base, and config overrides over both
+    let tokens = load_theme_from(
+        Some("solar"),
+        solar.to_str(),
+        None::<&HashMap<String, String>>,
+    );
+    assert_eq!(tokens.border, Color::Rgb(0x11, 0x22, 0x33));
+    assert_eq!(tokens.link, ThemeTokens::builtin_dark().link);
+    let mut overrides = HashMap::new();
+    overrides.insert("border".to_string(), "red".to_string());
+    let tokens = load_theme_from(Some("light"), solar.to_str(), Some(&overrides));
+    assert_eq!(tokens.border, Color::Red);
+    assert_eq!(tokens.link, ThemeTokens::builtin_light().link);
+}
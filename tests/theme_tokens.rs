@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use dx::theme::{ThemeTokens, load_theme_from, parse_style};
+use ratatui::style::{Color, Modifier, Style};
+
+#[test]
+fn style_specs_combine_colours_and_modifiers() {
+    assert_eq!(
+        parse_style("bold yellow on #202020"),
+        Some(
+            Style::new()
+                .fg(Color::Yellow)
+                .bg(Color::Rgb(0x20, 0x20, 0x20))
+                .add_modifier(Modifier::BOLD)
+        )
+    );
+    assert_eq!(
+        parse_style("reversed"),
+        Some(Style::new().add_modifier(Modifier::REVERSED))
+    );
+    assert_eq!(parse_style("none"), Some(Style::new()));
+    assert_eq!(parse_style("on"), None);
+    assert_eq!(parse_style("bold sparkly"), None);
+}
+
+#[test]
+fn style_tokens_come_from_theme_files_and_overrides() {
+    let tmp = tempfile::tempdir().unwrap();
+    let file = tmp.path().join("mine.dx-theme");
+    std::fs::write(
+        &file,
+        "tokens:\n  status_running: \"bold #9a6700\"\n  modal: \"white on #3c3c3c\"\n  status_ok: \"nonsense words\"\n",
+    )
+    .unwrap();
+    let mut overrides = HashMap::new();
+    overrides.insert("spinner".to_string(), "italic cyan".to_string());
+    let t = load_theme_from(Some("mine"), file.to_str(), Some(&overrides));
+    assert_eq!(
+        t.status_running,
+        Style::new()
+            .fg(Color::Rgb(0x9a, 0x67, 0x00))
+            .add_modifier(Modifier::BOLD)
+    );
+    assert_eq!(
+        t.modal,
+        Style::new()
+            .fg(Color::White)
+            .bg(Color::Rgb(0x3c, 0x3c, 0x3c))
+    );
+    assert_eq!(
+        t.spinner,
+        Style::new().fg(Color::Cyan).add_modifier(Modifier::ITALIC)
+    );
+    // Unparseable values keep the base theme's style
+    assert_eq!(t.status_ok, ThemeTokens::builtin_dark().status_ok);
+}
+
+#[test]
+fn light_theme_has_its_own_readable_colours() {
+    let dark = ThemeTokens::builtin_dark();
+    let light = ThemeTokens::builtin_light();
+    for (d, l) in [
+        (dark.md_heading1, light.md_heading1),
+        (dark.md_heading2, light.md_heading2),
+        (dark.md_heading3, light.md_heading3),
+        (dark.accent_warning, light.accent_warning),
+    ] {
+        assert_ne!(d, l);
+    }
+    assert_ne!(dark.status_running, light.status_running);
+    assert_ne!(dark.modal, light.modal);
+}